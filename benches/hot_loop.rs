@@ -0,0 +1,35 @@
+//! Times a pass with a trivial closure over a large vector, where the bounds checks of `get` and `get_mut` are a large part of the work.
+//!
+//! Compare the checked and unchecked builds with `cargo bench --bench hot_loop` and `cargo bench --bench hot_loop --features unchecked`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use handlevec::mutate_vec_by_handles;
+
+const VEC_LEN: usize = 1_000_000;
+const ROUNDS: u32 = 20;
+
+fn main() {
+    let mut vec: Vec<u64> = (0..VEC_LEN as u64).collect();
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        mutate_vec_by_handles(&mut vec, |mut handle| {
+            let next = handle.peek_forward_slice(1).copied().unwrap_or(0);
+            *handle.get_mut() = handle.get().wrapping_add(next & 1);
+        });
+        total += start.elapsed();
+        black_box(&vec);
+    }
+
+    let mode = if cfg!(feature = "unchecked") {
+        "unchecked"
+    } else {
+        "checked"
+    };
+    println!(
+        "{mode}: {VEC_LEN} elements, {:>12?} per pass",
+        total / ROUNDS
+    );
+}
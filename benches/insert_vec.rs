@@ -0,0 +1,50 @@
+//! Compares inserting a batch of elements one at a time against `insert_and_process_vec`.
+//!
+//! Run with `cargo bench --bench insert_vec`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use handlevec::{HandleState, VecMutationHandle};
+
+const VEC_LEN: usize = 10_000;
+const ROUNDS: u32 = 20;
+
+fn time_rounds(
+    mut insert: impl FnMut(&mut VecMutationHandle<usize>, Vec<usize>),
+    batch_len: usize,
+) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let mut vec: Vec<usize> = (0..VEC_LEN).collect();
+        let batch: Vec<usize> = (0..batch_len).collect();
+        let mut state = HandleState::starting_at(VEC_LEN / 2);
+        let mut handle = VecMutationHandle::new(&mut vec, &mut state).unwrap();
+
+        let start = Instant::now();
+        insert(&mut handle, batch);
+        total += start.elapsed();
+
+        black_box(vec);
+    }
+    total / ROUNDS
+}
+
+fn main() {
+    for batch_len in [100, 300, 900] {
+        let repeated = time_rounds(
+            |handle, batch| {
+                for t in batch.into_iter().rev() {
+                    handle.insert_and_process(t);
+                }
+            },
+            batch_len,
+        );
+        let spliced = time_rounds(
+            |handle, batch| handle.insert_and_process_vec(batch),
+            batch_len,
+        );
+
+        println!("k = {batch_len:>4}: repeated inserts {repeated:>12?}, insert_and_process_vec {spliced:>12?}");
+    }
+}
@@ -0,0 +1,42 @@
+//! Compares the eager runner against the compacting runner on a pass which edits around every other element, replacing it
+//! with two new elements.
+//!
+//! Run with `cargo bench --bench localized_edits`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use handlevec::{
+    mutate_vec_by_handles, mutate_vec_by_handles_compacting, HandleStorage, VecMutationHandle,
+};
+
+fn edit<S: HandleStorage<Item = u64>>(mut handle: VecMutationHandle<u64, S>) {
+    let x = *handle.get();
+    if x.is_multiple_of(2) {
+        handle.insert_and_skip(x);
+        handle.insert_and_skip(x + 1);
+        handle.discard();
+    }
+}
+
+fn time(len: usize, pass: impl Fn(&mut Vec<u64>)) -> Duration {
+    let mut vec: Vec<u64> = (0..len as u64).collect();
+    let start = Instant::now();
+    pass(&mut vec);
+    let elapsed = start.elapsed();
+    black_box(vec);
+    elapsed
+}
+
+// The closures are needed for the handle lifetimes to be higher-ranked, which passing `edit` directly does not give.
+#[allow(clippy::redundant_closure)]
+fn main() {
+    for len in [20_000, 40_000, 80_000] {
+        let eager = time(len, |vec| mutate_vec_by_handles(vec, |handle| edit(handle)));
+        let compacting = time(len, |vec| {
+            mutate_vec_by_handles_compacting(vec, |handle| edit(handle))
+        });
+
+        println!("n = {len:>6}: eager {eager:>12?}, compacting {compacting:>12?}");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::driver::PassDriver;
+use crate::VecMutationHandle;
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), with an async closure.
+///
+/// The future for each element is awaited to completion before the next handle is created, so the handle can be held across
+/// await points, and there is never more than one handle at a time.
+pub async fn mutate_vec_by_handles_async<T>(
+    vec: &mut Vec<T>,
+    mut op: impl AsyncFnMut(VecMutationHandle<T>),
+) {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        op(handle).await;
+    }
+}
+
+/// Mutate a vec using index-style looping, with a fallible async closure. See [`mutate_vec_by_handles_async`], and
+/// [`try_mutate_vec_by_handles`](crate::try_mutate_vec_by_handles) for how errors end the pass.
+///
+/// # Errors
+/// Returns the first error returned by the closure.
+pub async fn try_mutate_vec_by_handles_async<T, E>(
+    vec: &mut Vec<T>,
+    mut op: impl AsyncFnMut(VecMutationHandle<T>) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        if let Err(error) = op(handle).await {
+            driver.finish(vec);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::Thread;
+
+    use super::*;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    // Returns `Pending` once, like a zero-duration sleep, so the element's future is suspended mid-element.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                context.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_discard_odd() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut seen = vec![];
+        block_on(mutate_vec_by_handles_async(&mut v, async |mut handle| {
+            seen.push(*handle.get());
+            YieldNow(false).await;
+            if *handle.get() % 2 == 1 {
+                handle.discard();
+            } else {
+                *handle.get_mut() *= 10;
+            }
+        }));
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+        assert_eq!(v, vec![0, 20, 40, 60, 80]);
+    }
+
+    #[test]
+    fn test_async_insertions_not_tracked() {
+        let mut v = vec![1, 2, 3];
+        let mut seen = vec![];
+        block_on(mutate_vec_by_handles_async(&mut v, async |mut handle| {
+            seen.push((*handle.get(), handle.was_inserted_this_pass()));
+            YieldNow(false).await;
+            match *handle.get() {
+                1 => handle.insert_and_process(10),
+                10 => handle.insert_and_process(100),
+                3 => handle.append_and_process(5),
+                _ => {}
+            }
+        }));
+        assert_eq!(v, vec![1, 10, 100, 2, 3, 5]);
+        // Only `HandlePass`, `HandleCursor` and `PassState` track insertions, when asked to.
+        assert_eq!(
+            seen,
+            vec![
+                (1, false),
+                (10, false),
+                (100, false),
+                (2, false),
+                (3, false),
+                (5, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_async_defer() {
+        let mut v = vec![3, 1, 2];
+        block_on(mutate_vec_by_handles_async(&mut v, async |mut handle| {
+            YieldNow(false).await;
+            handle.defer(|v| v.push(0));
+        }));
+        assert_eq!(v, vec![3, 1, 2, 0, 0, 0]);
+
+        let result = block_on(try_mutate_vec_by_handles_async(
+            &mut v,
+            async |mut handle| {
+                YieldNow(false).await;
+                handle.defer_once("sort", |v| v.sort_unstable());
+                if *handle.get() == 1 {
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            },
+        ));
+        assert_eq!(result, Err(()));
+        assert_eq!(v, vec![0, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_async_try() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let result = block_on(try_mutate_vec_by_handles_async(&mut v, async |handle| {
+            YieldNow(false).await;
+            match *handle.get() {
+                2 => {
+                    handle.discard();
+                    Ok(())
+                }
+                4 => Err("four"),
+                _ => Ok(()),
+            }
+        }));
+        assert_eq!(result, Err("four"));
+        assert_eq!(v, vec![1, 3, 4, 5]);
+
+        let result: Result<(), ()> = block_on(try_mutate_vec_by_handles_async(
+            &mut v,
+            async |mut handle| {
+                handle.insert_and_skip(0);
+                Ok(())
+            },
+        ));
+        assert_eq!(result, Ok(()));
+        assert_eq!(v, vec![1, 0, 3, 0, 4, 0, 5, 0]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::VecMutationHandle;
+
+/// Where in a pass run by [`mutate_vec_by_handles_batched`] the batch is flushed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassPoint {
+    /// Right after the closure asked for it with [`BatchContext::request_flush`].
+    Manual,
+    /// When the pass is over, either by reaching the end of the vector, or by being stopped, e.g. with `stop_iteration`.
+    End,
+}
+
+/// The batch of a pass run by [`mutate_vec_by_handles_batched`], as given to the closure. Derefs to the batch.
+#[derive(Debug)]
+pub struct BatchContext<B> {
+    batch: B,
+    flush_requested: bool,
+}
+
+impl<B> BatchContext<B> {
+    /// Flush the batch once the closure returns, with [`PassPoint::Manual`]. Asking more than once per element flushes once.
+    pub fn request_flush(&mut self) {
+        self.flush_requested = true;
+    }
+}
+
+impl<B> Deref for BatchContext<B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        &self.batch
+    }
+}
+
+impl<B> DerefMut for BatchContext<B> {
+    fn deref_mut(&mut self) -> &mut B {
+        &mut self.batch
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), while accumulating
+/// something in a batch, starting from `init`, which is flushed with `flush`. Returns the batch as the final flush left it.
+///
+/// The batch is always flushed once the pass is over, with [`PassPoint::End`], also if it was stopped early, so the last partial
+/// batch is never forgotten. The closure can also flush in between, e.g. when the batch is full, with [`BatchContext::request_flush`].
+pub fn mutate_vec_by_handles_batched<T, B>(
+    vec: &mut Vec<T>,
+    init: B,
+    mut op: impl FnMut(&mut BatchContext<B>, VecMutationHandle<T>),
+    mut flush: impl FnMut(&mut B, PassPoint),
+) -> B {
+    let mut context = BatchContext {
+        batch: init,
+        flush_requested: false,
+    };
+    crate::mutate_vec_by_handles(vec, |handle| {
+        op(&mut context, handle);
+        if context.flush_requested {
+            context.flush_requested = false;
+            flush(&mut context.batch, PassPoint::Manual);
+        }
+    });
+    flush(&mut context.batch, PassPoint::End);
+    context.batch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batched_discards() {
+        let mut v: Vec<i32> = (0..12).collect();
+        let mut flushed = vec![];
+        let batch = mutate_vec_by_handles_batched(
+            &mut v,
+            Vec::new(),
+            |batch, handle| {
+                if *handle.get() % 3 != 0 {
+                    batch.push(handle.discard());
+                    if batch.len() == 3 {
+                        batch.request_flush();
+                    }
+                }
+            },
+            |batch, point| flushed.push((std::mem::take(batch), point)),
+        );
+        assert_eq!(v, vec![0, 3, 6, 9]);
+        assert!(batch.is_empty());
+        assert_eq!(
+            flushed,
+            vec![
+                (vec![1, 2, 4], PassPoint::Manual),
+                (vec![5, 7, 8], PassPoint::Manual),
+                (vec![10, 11], PassPoint::End),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batched_stop() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut flushed = vec![];
+        let batch = mutate_vec_by_handles_batched(
+            &mut v,
+            0,
+            |sum, handle| {
+                **sum += *handle.get();
+                if *handle.get() == 4 {
+                    handle.stop_iteration();
+                }
+            },
+            |sum, point| flushed.push((*sum, point)),
+        );
+        assert_eq!(batch, 10);
+        assert_eq!(flushed, vec![(10, PassPoint::End)]);
+
+        // An empty pass is flushed too.
+        let flush_count = mutate_vec_by_handles_batched(
+            &mut Vec::<i32>::new(),
+            0,
+            |_, _| unreachable!(),
+            |count, _| *count += 1,
+        );
+        assert_eq!(flush_count, 1);
+    }
+}
@@ -0,0 +1,207 @@
+use std::slice::SliceIndex;
+
+use crate::VecMutationHandle;
+
+/// The error of the insert methods of [`CappedHandle`], when inserting would make the vector longer than its cap.
+/// Holds the value which was not inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapExceeded<T> {
+    /// The value which was not inserted.
+    pub value: T,
+    /// The cap on the length of the vector.
+    pub max_len: usize,
+}
+
+impl<T> CapExceeded<T> {
+    /// Get back the value which was not inserted.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// A [`VecMutationHandle`] on a vector with a cap on its length. Created by [`mutate_vec_by_handles_capped`].
+///
+/// Only has fallible insert methods, which insert nothing if the vector would grow past the cap. Methods which can't fail, like
+/// `insert_and_process` on a [`VecMutationHandle`], are not available, so a pass can't grow the vector by mistake.
+#[derive(Debug)]
+pub struct CappedHandle<'a, 'b, T> {
+    handle: VecMutationHandle<'a, 'b, T>,
+    max_len: usize,
+}
+
+impl<T> CappedHandle<'_, '_, T> {
+    /// The number of elements which can still be inserted before reaching the cap. Discarding elements makes more room.
+    #[must_use]
+    pub fn headroom(&self) -> usize {
+        let len = self.handle.index() + self.handle.peek_forward_slice(..).map_or(0, <[T]>::len);
+        self.max_len.saturating_sub(len)
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.handle.get()
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.handle.get_mut()
+    }
+
+    /// Assign a new value to the current element.
+    pub fn set(&mut self, t: T) {
+        self.handle.set(t);
+    }
+
+    /// "Peek" a reference to a slice of the elements, with 0 being the index of the current element, like
+    /// `VecMutationHandle::peek_forward_slice`.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.handle.peek_forward_slice(slice)
+    }
+
+    /// Remove the current element, and return it as owned.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard(self) -> T {
+        self.handle.discard()
+    }
+
+    /// Insert a new element AFTER the current one, and process it in the next iteration, like `VecMutationHandle::insert_and_process`.
+    ///
+    /// # Errors
+    /// Returns the element back, without inserting it, if the vector is already at its cap.
+    pub fn try_insert_and_process(&mut self, t: T) -> Result<(), CapExceeded<T>> {
+        self.check_headroom(1, t)
+            .map(|t| self.handle.insert_and_process(t))
+    }
+
+    /// Insert a new element AFTER the current one, but do not process it, like `VecMutationHandle::insert_and_skip`.
+    ///
+    /// # Errors
+    /// Returns the element back, without inserting it, if the vector is already at its cap.
+    pub fn try_insert_and_skip(&mut self, t: T) -> Result<(), CapExceeded<T>> {
+        self.check_headroom(1, t)
+            .map(|t| self.handle.insert_and_skip(t))
+    }
+
+    /// Insert all elements of a vec AFTER the current one, and process them afterwards, like
+    /// `VecMutationHandle::insert_and_process_vec`. Iterators can be collected into a vec first, as their length must be known.
+    ///
+    /// # Errors
+    /// Returns the vec back, without inserting any of its elements, if they don't all fit within the cap.
+    pub fn try_insert_and_process_vec(&mut self, vec: Vec<T>) -> Result<(), CapExceeded<Vec<T>>> {
+        self.check_headroom(vec.len(), vec)
+            .map(|vec| self.handle.insert_and_process_vec(vec))
+    }
+
+    /// Insert all elements of a vec AFTER the current one, but do not process them, like `VecMutationHandle::insert_and_skip_vec`.
+    ///
+    /// # Errors
+    /// Returns the vec back, without inserting any of its elements, if they don't all fit within the cap.
+    pub fn try_insert_and_skip_vec(&mut self, vec: Vec<T>) -> Result<(), CapExceeded<Vec<T>>> {
+        self.check_headroom(vec.len(), vec)
+            .map(|vec| self.handle.insert_and_skip_vec(vec))
+    }
+
+    fn check_headroom<V>(&self, count: usize, value: V) -> Result<V, CapExceeded<V>> {
+        if count <= self.headroom() {
+            Ok(value)
+        } else {
+            Err(CapExceeded {
+                value,
+                max_len: self.max_len,
+            })
+        }
+    }
+
+    /// Skip a certain amount of the next elements, like `VecMutationHandle::skip_forward`.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        self.handle.skip_forward(steps_to_skip);
+    }
+
+    /// Do not process any more elements after this one.
+    pub fn stop_iteration(self) {
+        self.handle.stop_iteration();
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), without letting it grow
+/// longer than `max_len`. See [`CappedHandle`].
+///
+/// The cap is checked against the length of the vector at the time of each insertion, so elements discarded earlier in the pass
+/// make room for later insertions. A vector which is already longer than the cap is not shortened, but nothing can be inserted
+/// until it is back under it.
+pub fn mutate_vec_by_handles_capped<T>(
+    vec: &mut Vec<T>,
+    max_len: usize,
+    mut op: impl FnMut(CappedHandle<T>),
+) {
+    crate::mutate_vec_by_handles(vec, |handle| op(CappedHandle { handle, max_len }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capped_expansion_hits_cap() {
+        let mut v = vec![3, 1, 2];
+        let mut rejected = vec![];
+        mutate_vec_by_handles_capped(&mut v, 5, |mut handle| {
+            let x = *handle.get();
+            // Expand each element into that many copies.
+            if x > 1 {
+                if let Err(error) = handle.try_insert_and_skip_vec(vec![x; x - 1]) {
+                    assert_eq!(error.max_len, 5);
+                    rejected.push(error.into_inner());
+                    handle.set(0);
+                }
+            }
+        });
+        assert_eq!(v, vec![3, 3, 3, 1, 0]);
+        assert_eq!(rejected, vec![vec![2]]);
+
+        let mut v = vec![1, 2];
+        let mut errors = vec![];
+        mutate_vec_by_handles_capped(&mut v, 3, |mut handle| {
+            if let Err(error) = handle.try_insert_and_process(*handle.get() * 10) {
+                errors.push(error);
+            }
+        });
+        assert_eq!(v, vec![1, 10, 2]);
+        assert_eq!(
+            errors,
+            vec![
+                CapExceeded {
+                    value: 100,
+                    max_len: 3,
+                },
+                CapExceeded {
+                    value: 20,
+                    max_len: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capped_discards_make_room() {
+        let mut v = vec![0, 1, 0, 2, 0, 3];
+        let mut headroom = vec![];
+        mutate_vec_by_handles_capped(&mut v, 6, |mut handle| {
+            headroom.push(handle.headroom());
+            if *handle.get() == 0 {
+                handle.discard();
+            } else {
+                let x = *handle.get();
+                handle.try_insert_and_skip(x * 10).unwrap();
+            }
+        });
+        assert_eq!(v, vec![1, 10, 2, 20, 3, 30]);
+        assert_eq!(headroom, vec![0, 1, 0, 1, 0, 1]);
+    }
+}
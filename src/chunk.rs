@@ -0,0 +1,153 @@
+use std::num::NonZeroUsize;
+
+// Contract:
+// `index + chunk_size <= vec.len()`
+// `next_index >= index`, and `next_index - index` is a multiple of `chunk_size`
+// `vec` may not be mutated at indices smaller than `index`
+/// Represents a chunk of `chunk_size` elements in a vector, allowing mutation of the vector with that chunk as a "context".
+/// Created by [`mutate_vec_by_chunk_handles`].
+#[derive(Debug)]
+pub struct ChunkHandle<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    index: usize, // The index of the first element of the current chunk. Should not be mutated.
+    chunk_size: usize,
+    next_index: &'b mut usize, // The index of the first element of the next chunk to be processed.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> ChunkHandle<'a, 'b, T> {
+    fn new(
+        vec: &'a mut Vec<T>,
+        chunk_size: usize,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index.checked_add(chunk_size)? > vec.len() {
+            return None;
+        }
+        *next_index = index + chunk_size;
+        Some(ChunkHandle {
+            vec,
+            index,
+            chunk_size,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current chunk.
+    #[must_use]
+    pub fn get(&self) -> &[T] {
+        &self.vec[self.index..self.index + self.chunk_size]
+    }
+
+    /// Get a mutable reference to the current chunk.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut [T] {
+        &mut self.vec[self.index..self.index + self.chunk_size]
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the current chunk, and return its elements as owned.
+    pub fn discard_chunk(self) -> Vec<T> {
+        *self.next_index = self.index;
+        self.vec
+            .drain(self.index..self.index + self.chunk_size)
+            .collect()
+    }
+
+    /// Insert a chunk after the current one, which will be skipped by the iteration.
+    ///
+    /// # Errors
+    /// Returns the chunk back, without inserting it, if it does not have exactly `chunk_size` elements.
+    pub fn insert_chunk_and_skip(&mut self, chunk: Vec<T>) -> Result<(), Vec<T>> {
+        if chunk.len() != self.chunk_size {
+            return Err(chunk);
+        }
+        let position = self.index + self.chunk_size;
+        self.vec.splice(position..position, chunk);
+        *self.next_index += self.chunk_size;
+        Ok(())
+    }
+
+    /// Skip a certain amount of the chunks after the current one.
+    pub fn skip_chunks(&mut self, chunks_to_skip: usize) {
+        let steps = chunks_to_skip.saturating_mul(self.chunk_size);
+        *self.next_index = self.next_index.saturating_add(steps);
+    }
+
+    /// Do not process any more chunks.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a vec of fixed-size records stored back to back, where each handle refers to a whole chunk of `chunk_size` elements.
+///
+/// If the length of the vector is not a multiple of `chunk_size`, the trailing partial chunk is never given to the closure,
+/// and is left as it is after the last whole chunk.
+pub fn mutate_vec_by_chunk_handles<T>(
+    vec: &mut Vec<T>,
+    chunk_size: NonZeroUsize,
+    mut op: impl FnMut(ChunkHandle<T>),
+) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = ChunkHandle::new(vec, chunk_size.get(), &mut next_index, &mut stopped)
+    {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THREE: NonZeroUsize = NonZeroUsize::new(3).unwrap();
+
+    #[test]
+    fn test_chunks_trailing_remainder() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut seen = vec![];
+        mutate_vec_by_chunk_handles(&mut v, THREE, |mut handle| {
+            seen.push(handle.get().to_vec());
+            handle.get_mut().reverse();
+        });
+        assert_eq!(seen, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(v, vec![3, 2, 1, 6, 5, 4, 7, 8]);
+    }
+
+    #[test]
+    fn test_chunks_discard_mid_vector() {
+        let mut v = vec![1, 2, 3, 0, 0, 0, 4, 5, 6, 0, 0, 0, 7];
+        let mut discarded = vec![];
+        mutate_vec_by_chunk_handles(&mut v, THREE, |handle| {
+            if handle.get()[0] == 0 {
+                discarded.extend(handle.discard_chunk());
+            }
+        });
+        assert_eq!(discarded, vec![0; 6]);
+        assert_eq!(v, vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_chunks_insert_skip_stop() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let mut seen = vec![];
+        mutate_vec_by_chunk_handles(&mut v, THREE, |mut handle| {
+            seen.push(handle.get()[0]);
+            match handle.get()[0] {
+                1 => {
+                    assert_eq!(handle.insert_chunk_and_skip(vec![0, 0]), Err(vec![0, 0]));
+                    assert_eq!(handle.insert_chunk_and_skip(vec![0, 0, 0]), Ok(()));
+                }
+                4 => handle.skip_chunks(1),
+                _ => handle.stop_iteration(),
+            }
+        });
+        assert_eq!(seen, vec![1, 4, 10]);
+        assert_eq!(v, vec![1, 2, 3, 0, 0, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    }
+}
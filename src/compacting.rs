@@ -0,0 +1,485 @@
+use std::collections::VecDeque;
+
+use crate::driver::PassDriver;
+use crate::vec_mut_handle_core::sealed::Sealed;
+use crate::{HandleStorage, VecMutationHandle};
+
+/// Storage used by [`mutate_vec_by_handles_compacting`].
+///
+/// Elements before the current index (which can no longer be touched by a handle) are moved to the front of the original vector,
+/// while the current and remaining elements are kept in a contiguous double-ended queue. Discarding the current element then only
+/// pops the front of the queue, instead of shifting the whole remainder of the vector.
+#[derive(Debug)]
+pub struct CompactingStorage<'v, T> {
+    kept: &'v mut Vec<T>,
+    pending: VecDeque<T>,
+}
+
+impl<'v, T> CompactingStorage<'v, T> {
+    fn new(vec: &'v mut Vec<T>) -> Self {
+        let pending = VecDeque::from(std::mem::take(vec));
+        CompactingStorage { kept: vec, pending }
+    }
+
+    // Move all elements before `next_index` to the kept region. They may never be touched by a handle again.
+    fn settle(&mut self, next_index: usize) {
+        let to_settle = next_index.saturating_sub(self.kept.len());
+        let to_settle = to_settle.min(self.pending.len());
+        self.kept.extend(self.pending.drain(..to_settle));
+    }
+
+    // Peeks require the queue to be contiguous. Cheap when it already is, which is always the case after discards.
+    fn make_contiguous(&mut self) {
+        self.pending.make_contiguous();
+    }
+}
+
+impl<T> Drop for CompactingStorage<'_, T> {
+    // Restores the vector, also if the closure panics.
+    fn drop(&mut self) {
+        self.kept.extend(self.pending.drain(..));
+    }
+}
+
+impl<T> Sealed for CompactingStorage<'_, T> {}
+
+impl<T> HandleStorage for CompactingStorage<'_, T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.kept.len() + self.pending.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        match index.checked_sub(self.kept.len()) {
+            Some(pending_index) => self.pending.get(pending_index),
+            None => self.kept.get(index),
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match index.checked_sub(self.kept.len()) {
+            Some(pending_index) => self.pending.get_mut(pending_index),
+            None => self.kept.get_mut(index),
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        match index.checked_sub(self.kept.len()) {
+            Some(pending_index) => {
+                let element = self.pending.remove(pending_index)?;
+                self.make_contiguous();
+                Some(element)
+            }
+            None => HandleStorage::remove(self.kept, index),
+        }
+    }
+
+    fn insert(&mut self, index: usize, element: T) {
+        match index.checked_sub(self.kept.len()) {
+            Some(pending_index) => {
+                self.pending.insert(pending_index, element);
+                self.make_contiguous();
+            }
+            None => self.kept.insert(index, element),
+        }
+    }
+
+    fn insert_iter<I: IntoIterator<Item = T>>(&mut self, index: usize, elements: I) {
+        match index.checked_sub(self.kept.len()) {
+            Some(pending_index) => {
+                // Rotating the elements before the insertion point to the back, appending the new elements after them, and
+                // rotating both back to the front only moves the elements before the insertion point, once.
+                let len_before = self.pending.len();
+                self.pending.rotate_left(pending_index);
+                self.pending.extend(elements);
+                let count = self.pending.len() - len_before;
+                self.pending.rotate_right(pending_index + count);
+                self.make_contiguous();
+            }
+            None => {
+                self.kept.splice(index..index, elements);
+            }
+        }
+    }
+
+    fn remove_range(&mut self, start: usize, end: usize) -> Vec<T> {
+        match start.checked_sub(self.kept.len()) {
+            Some(pending_start) => {
+                let pending_end = end - self.kept.len();
+                let removed = self.pending.drain(pending_start..pending_end).collect();
+                self.make_contiguous();
+                removed
+            }
+            None => self.kept.drain(start..end).collect(),
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if let Some(pending_len) = len.checked_sub(self.kept.len()) {
+            self.pending.truncate(pending_len);
+        } else {
+            self.kept.truncate(len);
+            self.pending.clear();
+        }
+    }
+
+    fn retain_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, mut f: F) {
+        if let Some(pending_start) = start.checked_sub(self.kept.len()) {
+            let mut position = 0;
+            self.pending.retain_mut(|t| {
+                position += 1;
+                position <= pending_start || f(t)
+            });
+            self.make_contiguous();
+        } else {
+            self.kept.extract_if(start.., |t| !f(t)).for_each(drop);
+            self.pending.retain_mut(f);
+        }
+    }
+
+    fn extract_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, mut f: F) -> Vec<T> {
+        let (mut extracted, pending_start) = match start.checked_sub(self.kept.len()) {
+            Some(pending_start) => (vec![], pending_start),
+            None => (self.kept.extract_if(start.., &mut f).collect(), 0),
+        };
+        // Each element after the start is moved once, either back onto the queue, or into the result.
+        let rest: Vec<T> = self.pending.drain(pending_start..).collect();
+        for mut t in rest {
+            if f(&mut t) {
+                extracted.push(t);
+            } else {
+                self.pending.push_back(t);
+            }
+        }
+        self.make_contiguous();
+        extracted
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        let kept_len = self.kept.len();
+        match (a.checked_sub(kept_len), b.checked_sub(kept_len)) {
+            (Some(a), Some(b)) => self.pending.swap(a, b),
+            (None, None) => self.kept.swap(a, b),
+            (None, Some(b)) => std::mem::swap(&mut self.kept[a], &mut self.pending[b]),
+            (Some(a), None) => std::mem::swap(&mut self.pending[a], &mut self.kept[b]),
+        }
+    }
+
+    fn rotate_range_left(&mut self, start: usize, end: usize, mid: usize) {
+        if let Some(pending_start) = start.checked_sub(self.kept.len()) {
+            let pending_end = end - self.kept.len();
+            self.pending.make_contiguous()[pending_start..pending_end].rotate_left(mid);
+        } else {
+            // Handles never rotate elements before the current one, which are the only ones that can be kept. Still correct otherwise.
+            self.kept.extend(self.pending.drain(..));
+            self.kept[start..end].rotate_left(mid);
+        }
+    }
+
+    fn split_at_index(&self, index: usize) -> Option<(&T, &[T])> {
+        // Handles only split at the current element, which is always pending. The queue is always kept contiguous, like in `tail`.
+        let pending_index = index.checked_sub(self.kept.len())?;
+        self.pending
+            .as_slices()
+            .0
+            .get(pending_index..)?
+            .split_first()
+    }
+
+    fn split_at_index_mut(&mut self, index: usize) -> Option<(&mut T, &mut [T])> {
+        let pending_index = index.checked_sub(self.kept.len())?;
+        self.pending
+            .as_mut_slices()
+            .0
+            .get_mut(pending_index..)?
+            .split_first_mut()
+    }
+
+    fn split_prev_mut(&mut self, index: usize) -> Option<(&mut T, &mut T)> {
+        let prev_index = index.checked_sub(1)?;
+        match prev_index.checked_sub(self.kept.len()) {
+            Some(pending_index) => {
+                let (prev, rest) = self
+                    .pending
+                    .as_mut_slices()
+                    .0
+                    .get_mut(pending_index..)?
+                    .split_first_mut()?;
+                Some((prev, rest.first_mut()?))
+            }
+            None if index < self.kept.len() => self.kept.split_prev_mut(index),
+            None => Some((self.kept.get_mut(prev_index)?, self.pending.front_mut()?)),
+        }
+    }
+
+    #[cfg(feature = "unchecked")]
+    unsafe fn get_unchecked(&self, index: usize) -> &T {
+        // SAFETY: The caller guarantees `index < len`, and the queue is always kept contiguous, like in `tail`.
+        unsafe {
+            match index.checked_sub(self.kept.len()) {
+                Some(pending_index) => self.pending.as_slices().0.get_unchecked(pending_index),
+                None => self.kept.get_unchecked(index),
+            }
+        }
+    }
+
+    #[cfg(feature = "unchecked")]
+    unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut T {
+        // SAFETY: As for `get_unchecked`.
+        unsafe {
+            match index.checked_sub(self.kept.len()) {
+                Some(pending_index) => self
+                    .pending
+                    .as_mut_slices()
+                    .0
+                    .get_unchecked_mut(pending_index),
+                None => self.kept.get_unchecked_mut(index),
+            }
+        }
+    }
+
+    fn tail(&self, index: usize) -> Option<&[T]> {
+        // The queue is always kept contiguous, so the first slice contains all pending elements.
+        let pending_index = index.checked_sub(self.kept.len())?;
+        self.pending.as_slices().0.get(pending_index..)
+    }
+
+    fn tail_mut(&mut self, index: usize) -> Option<&mut [T]> {
+        let pending_index = index.checked_sub(self.kept.len())?;
+        self.pending.as_mut_slices().0.get_mut(pending_index..)
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), but without shifting the
+/// remainder of the vector on every discard, or on every insertion right after the current element.
+///
+/// The handle API and the resulting vector are the same, also if the closure panics or the iteration is stopped early. A pass doing
+/// many discards or localized edits is O(n + edits), where the eager runner is O(n) per edit.
+pub fn mutate_vec_by_handles_compacting<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T, CompactingStorage<T>>),
+) {
+    let mut storage = CompactingStorage::new(vec);
+    let mut driver = PassDriver::new(&storage);
+    while let Some(handle) = driver.next(&mut storage) {
+        op(handle);
+        storage.settle(driver.position());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutate_vec_by_handles;
+
+    #[test]
+    fn test_compacting_discard_all_but_some() {
+        let mut v: Vec<usize> = (0..10_000).collect();
+        mutate_vec_by_handles_compacting(&mut v, |handle| {
+            if *handle.get() % 1000 != 0 {
+                handle.discard();
+            }
+        });
+        assert_eq!(
+            v,
+            vec![0, 1000, 2000, 3000, 4000, 5000, 6000, 7000, 8000, 9000]
+        );
+    }
+
+    #[test]
+    fn test_compacting_matches_eager_runner() {
+        let original = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+
+        let mut seen_eager = vec![];
+        let mut eager = original.clone();
+        mutate_vec_by_handles(&mut eager, |mut handle| {
+            let next = handle.peek_forward_slice(1).copied();
+            seen_eager.push((
+                *handle.get(),
+                handle.peek_forward_slice(0..).unwrap().len(),
+                next,
+            ));
+            match *handle.get() {
+                x if x % 3 == 0 => {
+                    handle.discard();
+                }
+                x if x % 4 == 0 => handle.insert_and_process(x * 10 + 1),
+                x if x % 5 == 0 => handle.insert_and_skip_vec(vec![50, 51]),
+                7 => handle.skip_forward(1),
+                _ => {}
+            }
+        });
+
+        let mut seen_compacting = vec![];
+        let mut compacting = original;
+        mutate_vec_by_handles_compacting(&mut compacting, |mut handle| {
+            let next = handle.peek_forward_slice(1).copied();
+            seen_compacting.push((
+                *handle.get(),
+                handle.peek_forward_slice(0..).unwrap().len(),
+                next,
+            ));
+            match *handle.get() {
+                x if x % 3 == 0 => {
+                    handle.discard();
+                }
+                x if x % 4 == 0 => handle.insert_and_process(x * 10 + 1),
+                x if x % 5 == 0 => handle.insert_and_skip_vec(vec![50, 51]),
+                7 => handle.skip_forward(1),
+                _ => {}
+            }
+        });
+
+        assert_eq!(compacting, eager);
+        assert_eq!(seen_compacting, seen_eager);
+    }
+
+    #[test]
+    fn test_compacting_stop_iteration() {
+        let mut v = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        mutate_vec_by_handles_compacting(&mut v, |handle| {
+            if *handle.get() > 10 {
+                handle.stop_iteration();
+            } else {
+                handle.discard();
+            }
+        });
+        assert_eq!(v, vec![11, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_compacting_insert_many() {
+        let mut v: Vec<usize> = (0..1000).collect();
+        mutate_vec_by_handles_compacting(&mut v, |mut handle| match *handle.get() {
+            1 => handle.insert_and_skip_iter(10_000..110_000),
+            998 => handle.insert_and_process_iter(200_000..300_000),
+            x if x >= 200_000 => handle.set(x + 1),
+            _ => {}
+        });
+
+        let expected: Vec<usize> = (0..2)
+            .chain(10_000..110_000)
+            .chain(2..999)
+            .chain(200_001..300_001)
+            .chain([999])
+            .collect();
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_compacting_defer() {
+        let mut v = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        mutate_vec_by_handles_compacting(&mut v, |mut handle| match *handle.get() {
+            3 => handle.defer(|storage| storage.truncate(2)),
+            x if x > 10 => handle.stop_iteration(),
+            _ => {
+                handle.discard();
+            }
+        });
+        assert_eq!(v, vec![3, 11]);
+    }
+
+    #[test]
+    fn test_compacting_restores_on_panic() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mutate_vec_by_handles_compacting(&mut v, |handle| {
+                assert!(*handle.get() != 4, "boom");
+                if *handle.get() == 2 {
+                    handle.discard();
+                }
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![1, 3, 4, 5]);
+    }
+
+    // A small xorshift generator, so the differential test needs no dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    // Decide on an edit from the element and a random number, using only the handle API shared by both runners.
+    fn random_edit<S: HandleStorage<Item = u64>>(
+        mut handle: VecMutationHandle<u64, S>,
+        roll: u64,
+        seen: &mut Vec<(u64, Option<u64>, usize)>,
+    ) {
+        let current = *handle.get();
+        let rest = handle.peek_forward_slice(0..).unwrap().len();
+        seen.push((current, handle.peek_forward_slice(1).copied(), rest));
+        // Only original elements insert with processing, so every pass ends.
+        let original = current < 1000;
+        match roll {
+            0..=19 => {
+                handle.discard();
+            }
+            20..=29 if original => handle.insert_and_process(current + 1000),
+            30..=39 => handle.insert_and_skip_vec(vec![current + 2000, current + 3000]),
+            40..=44 => handle.skip_forward(usize::try_from(roll - 40).unwrap()),
+            45..=49 => handle.append_and_skip(current + 4000),
+            50..=54 => {
+                handle.take_forward(2);
+            }
+            55..=59 if original => handle.append_and_process(current + 5000),
+            60..=61 => {
+                handle.retain_rest(|t| t % 3 != 0);
+            }
+            62 => handle.stop_iteration(),
+            63..=69 => {
+                let merged = handle.merge_with_next(|a, b| a + b);
+                seen.push((u64::from(merged), None, 0));
+            }
+            70..=71 => {
+                let drained = handle.drain_rest_matching(|t| t % 5 == 0);
+                seen.push((drained.iter().sum(), None, drained.len()));
+            }
+            72..=73 => {
+                if let Some((prev, current)) = handle.split_prev_current_mut() {
+                    *prev += 7000;
+                    *current += 8000;
+                }
+            }
+            _ => handle.set(current + 6000),
+        }
+    }
+
+    #[test]
+    fn test_compacting_matches_eager_runner_randomized() {
+        for seed in 1..300_u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let len = rng.below(40);
+            let original: Vec<u64> = (0..len).collect();
+            let rolls: Vec<u64> = (0..400).map(|_| rng.below(100)).collect();
+
+            let mut eager = original.clone();
+            let mut seen_eager = vec![];
+            let mut rolls_eager = rolls.iter().copied().cycle();
+            mutate_vec_by_handles(&mut eager, |handle| {
+                random_edit(handle, rolls_eager.next().unwrap(), &mut seen_eager);
+            });
+
+            let mut compacting = original;
+            let mut seen_compacting = vec![];
+            let mut rolls_compacting = rolls.iter().copied().cycle();
+            mutate_vec_by_handles_compacting(&mut compacting, |handle| {
+                random_edit(
+                    handle,
+                    rolls_compacting.next().unwrap(),
+                    &mut seen_compacting,
+                );
+            });
+
+            assert_eq!(compacting, eager, "seed {seed}");
+            assert_eq!(seen_compacting, seen_eager, "seed {seed}");
+        }
+    }
+}
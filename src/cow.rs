@@ -0,0 +1,192 @@
+use std::borrow::Cow;
+use std::slice::SliceIndex;
+
+// Contract:
+// `index < cow.len()`
+// `next_index > index`, or `next_index >= index` after discarding the current element
+// The slice is only made owned by the mutating methods, so reading never clones.
+/// Represents an element of a slice which may be borrowed, allowing it to be mutated like with a [`VecMutationHandle`](crate::VecMutationHandle).
+/// Created by [`mutate_cow_by_handles`].
+///
+/// The first mutating method called in a pass clones a borrowed slice into an owned vector, and the pass continues on it at the
+/// same position. Reading methods work on the slice as it is, borrowed or owned.
+#[derive(Debug)]
+pub struct CowHandle<'a, 'b, 'c, T: Clone> {
+    cow: &'a mut Cow<'c, [T]>,
+    index: usize, // Should not be mutated.
+    next_index: &'b mut usize,
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, 'c, T: Clone> CowHandle<'a, 'b, 'c, T> {
+    fn new(
+        cow: &'a mut Cow<'c, [T]>,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index >= cow.len() {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(CowHandle {
+            cow,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    // The owned vector, cloning the slice if it is still borrowed.
+    fn owned(&mut self) -> &mut Vec<T> {
+        self.cow.to_mut()
+    }
+
+    /// Get a reference to the current element. Never clones the slice.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.cow[self.index]
+    }
+
+    /// Get a mutable reference to the current element. Clones the slice if it is still borrowed, also if nothing is changed.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        let index = self.index;
+        &mut self.owned()[index]
+    }
+
+    /// Assign a new value to the current element. Clones the slice if it is still borrowed.
+    pub fn set(&mut self, t: T) {
+        *self.get_mut() = t;
+    }
+
+    /// "Peek" a reference to a slice of the elements, with 0 being the index of the current element, like `VecMutationHandle::peek_forward_slice`.
+    /// Never clones the slice.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.cow[self.index..].get(slice)
+    }
+
+    /// Remove the current element, and return it as owned. Clones the slice if it is still borrowed.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard(mut self) -> T {
+        let index = self.index;
+        *self.next_index = self.next_index.saturating_sub(1).max(index);
+        self.owned().remove(index)
+    }
+
+    /// Insert a new element AFTER the current one, and process it in the next iteration. Clones the slice if it is still borrowed.
+    pub fn insert_and_process(&mut self, t: T) {
+        let index = self.index;
+        self.owned().insert(index + 1, t);
+    }
+
+    /// Insert a new element AFTER the current one, but do not process it. Clones the slice if it is still borrowed.
+    pub fn insert_and_skip(&mut self, t: T) {
+        self.insert_and_process(t);
+        *self.next_index = self.next_index.saturating_add(1);
+    }
+
+    /// Skip a certain amount of elements after this one. Never clones the slice.
+    pub fn skip_forward(&mut self, elements_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(elements_to_skip);
+    }
+
+    /// Do not process any more elements after this one. Never clones the slice.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a slice which may be borrowed using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles),
+/// and only clone it if something is mutated. See [`CowHandle`].
+///
+/// Returns whether the slice was cloned by this pass, i.e. whether it was borrowed before, and is owned afterwards.
+/// A pass which only reads leaves a borrowed slice borrowed.
+pub fn mutate_cow_by_handles<T: Clone>(
+    cow: &mut Cow<'_, [T]>,
+    mut op: impl FnMut(CowHandle<T>),
+) -> bool {
+    let was_borrowed = matches!(cow, Cow::Borrowed(_));
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = CowHandle::new(cow, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+
+    was_borrowed && matches!(cow, Cow::Owned(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Counted(self.0)
+        }
+    }
+
+    #[test]
+    fn test_cow_read_only() {
+        let data: Vec<Counted> = (0..10).map(Counted).collect();
+        let mut cow = Cow::Borrowed(&data[..]);
+        let mut sum = 0;
+        let cloned = mutate_cow_by_handles(&mut cow, |mut handle| {
+            sum += handle.get().0;
+            if handle.peek_forward_slice(1).is_some_and(|next| next.0 == 5) {
+                handle.skip_forward(1);
+            }
+            if handle.get().0 == 8 {
+                handle.stop_iteration();
+            }
+        });
+        assert!(!cloned);
+        assert!(matches!(cow, Cow::Borrowed(_)));
+        assert_eq!(sum, 1 + 2 + 3 + 4 + 6 + 7 + 8);
+    }
+
+    #[test]
+    fn test_cow_clones_once() {
+        let data: Vec<Counted> = (0..1000).map(Counted).collect();
+        let mut cow = Cow::Borrowed(&data[..]);
+        CLONES.with(|clones| clones.set(0));
+        let cloned = mutate_cow_by_handles(&mut cow, |mut handle| match handle.get().0 {
+            500 => handle.set(Counted(5000)),
+            600 => {
+                handle.discard();
+            }
+            700 => handle.insert_and_skip(Counted(7000)),
+            800 => handle.insert_and_process(Counted(8000)),
+            8000 => handle.get_mut().0 += 1,
+            _ => {}
+        });
+        assert!(cloned);
+        assert_eq!(CLONES.with(Cell::get), 1000);
+
+        let mut expected: Vec<Counted> = (0..1000).map(Counted).collect();
+        expected[500] = Counted(5000);
+        expected.insert(801, Counted(8001));
+        expected.insert(701, Counted(7000));
+        expected.remove(600);
+        assert_eq!(&*cow, &expected[..]);
+
+        // Already owned, so nothing is cloned.
+        let cloned = mutate_cow_by_handles(&mut cow, |mut handle| handle.set(Counted(0)));
+        assert!(!cloned);
+        assert_eq!(CLONES.with(Cell::get), 1000);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::driver::PassDriver;
+use crate::VecMutationHandle;
+
+/// A cursor over a vector, creating one handle per loop iteration, for the while-loop style without managing the state yourself.
+///
+/// ```
+/// use handlevec::HandleCursor;
+/// let mut my_vec = vec![1, 2, 3, 4];
+///
+/// let mut cursor = HandleCursor::new(&mut my_vec);
+/// while let Some(mut elem) = cursor.next() {
+///     if *elem.get() == 2 {
+///         elem.insert_and_skip(20);
+///     }
+/// }
+///
+/// assert_eq!(my_vec, vec![1, 2, 20, 3, 4]);
+/// ```
+///
+/// Elements appended with `append_and_skip` are not processed, and actions scheduled with `defer` run when `next` returns `None`.
+/// The cursor has `for_each` and `fold` of its own, and does not implement a `LendingIterator` trait, as this crate has no dependencies.
+#[derive(Debug)]
+pub struct HandleCursor<'a, T> {
+    vec: &'a mut Vec<T>,
+    driver: PassDriver<Vec<T>>,
+}
+
+impl<'a, T> HandleCursor<'a, T> {
+    /// Creates a cursor starting at the first element of the vector.
+    pub fn new(vec: &'a mut Vec<T>) -> Self {
+        let driver = PassDriver::new(vec);
+        HandleCursor { vec, driver }
+    }
+
+    /// Whether to keep track of the elements inserted during the pass, for `was_inserted_this_pass`, like
+    /// [`HandlePass::track_insertions`](crate::HandlePass::track_insertions).
+    #[must_use]
+    pub fn track_insertions(mut self, track_insertions: bool) -> Self {
+        self.driver = self.driver.track_insertions(track_insertions);
+        self
+    }
+
+    /// Get a handle to the next element to be processed, or `None` if the iteration is done (or stopped).
+    /// The handle borrows the cursor, so it has to be dropped (or consumed) before the next one is created.
+    #[allow(clippy::should_implement_trait)] // The handles borrow the cursor, so this can't be an `Iterator`.
+    pub fn next(&mut self) -> Option<VecMutationHandle<'_, '_, T>> {
+        self.driver.next(self.vec)
+    }
+
+    /// Call `op` with a handle to each remaining element, like `Iterator::for_each`. Equivalent to `mutate_vec_by_handles`
+    /// from the current position.
+    pub fn for_each(mut self, mut op: impl FnMut(VecMutationHandle<T>)) {
+        while let Some(handle) = self.next() {
+            op(handle);
+        }
+    }
+
+    /// Call `f` with an accumulator and a handle to each remaining element, like `Iterator::fold`, and return the final accumulator.
+    pub fn fold<B>(mut self, init: B, mut f: impl FnMut(B, VecMutationHandle<T>) -> B) -> B {
+        let mut acc = init;
+        while let Some(handle) = self.next() {
+            acc = f(acc, handle);
+        }
+        acc
+    }
+
+    /// The index of the next element to be processed. See `HandleState::position`.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.driver.position()
+    }
+
+    /// The number of elements left to process in this pass, which is 0 once the iteration is stopped.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        if self.driver.is_stopped() {
+            0
+        } else {
+            self.driver.end().saturating_sub(self.driver.position())
+        }
+    }
+
+    /// Stop the iteration, so `next` returns `None` from now on.
+    pub fn stop(&mut self) {
+        self.driver.stop();
+    }
+
+    /// Whether the iteration was stopped, either with `stop` or by a handle.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.driver.is_stopped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VecMutateByHandles;
+
+    #[test]
+    fn test_cursor_complex_break_loop() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+
+        let mut cursor = HandleCursor::new(&mut my_vec);
+
+        while let Some(elem) = cursor.next() {
+            if *elem.get() > 10 {
+                elem.stop_iteration();
+            } else {
+                elem.discard();
+            }
+        }
+
+        assert!(cursor.is_stopped());
+        assert_eq!(my_vec, vec![11, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_cursor_complex_break_loop_with_final_discard() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+
+        let mut cursor = HandleCursor::new(&mut my_vec);
+
+        while let Some(mut elem) = cursor.next() {
+            if *elem.get() > 10 {
+                elem.discard_and_stop_iteration();
+            } else {
+                elem.set(20);
+            }
+        }
+
+        assert_eq!(cursor.position(), 5);
+        assert_eq!(my_vec, vec![20, 20, 20, 20, 20, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_cursor_position_and_remaining() {
+        let mut my_vec = vec![1, 2, 3, 4, 5];
+        let mut cursor = HandleCursor::new(&mut my_vec);
+        assert_eq!(cursor.remaining(), 5);
+
+        let mut handle = cursor.next().unwrap();
+        handle.insert_and_skip(10);
+        handle.append_and_skip(20);
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(cursor.remaining(), 4);
+
+        cursor.next().unwrap().discard();
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(cursor.remaining(), 3);
+
+        let mut seen = vec![];
+        while let Some(handle) = cursor.next() {
+            seen.push(*handle.get());
+        }
+        assert_eq!(seen, vec![3, 4, 5]);
+        assert_eq!(cursor.remaining(), 0);
+        assert_eq!(my_vec, vec![1, 10, 3, 4, 5, 20]);
+    }
+
+    #[test]
+    fn test_cursor_handles_for_each_matches_runner() {
+        let mut my_vec = vec![1, 4, 9, 16, 25, 36, 49, 64, 81, 100];
+        let mut expected = my_vec.clone();
+
+        let op = |mut elem: VecMutationHandle<i32>| {
+            if let Some(n) = elem.peek_forward_slice(1).copied() {
+                *elem.get_mut() *= n;
+            } else {
+                elem.discard();
+            }
+        };
+        my_vec.handles().for_each(op);
+        expected.mutate_vec_by_handles(op);
+
+        assert_eq!(my_vec, expected);
+        assert_eq!(my_vec, vec![4, 36, 144, 400, 900, 1764, 3136, 5184, 8100]);
+    }
+
+    #[test]
+    fn test_cursor_handles_fold() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+
+        let discarded = my_vec.handles().fold(0, |discarded, elem| {
+            if *elem.get() > 10 {
+                elem.stop_iteration();
+                discarded
+            } else {
+                elem.discard();
+                discarded + 1
+            }
+        });
+
+        assert_eq!(discarded, 5);
+        assert_eq!(my_vec, vec![11, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_cursor_stop() {
+        let mut my_vec = vec![1, 2, 3, 4, 5];
+        let mut cursor = HandleCursor::new(&mut my_vec);
+        let mut seen = vec![];
+
+        while let Some(handle) = cursor.next() {
+            seen.push(*handle.get());
+            if seen.len() == 2 {
+                cursor.stop();
+            }
+        }
+
+        assert!(cursor.is_stopped());
+        assert_eq!(cursor.remaining(), 0);
+        assert!(cursor.next().is_none());
+        assert_eq!(seen, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_cursor_defer_after_skipped_tail() {
+        let mut my_vec = vec![1, 2, 4];
+        let mut cursor = HandleCursor::new(&mut my_vec);
+
+        while let Some(mut elem) = cursor.next() {
+            if *elem.get() == 1 {
+                // Lands after the last element, which the cursor then passes over at the end of the pass.
+                assert_eq!(elem.insert_into_rest_sorted_and_skip(5), 3);
+                elem.defer(|v| v.push(0));
+            }
+        }
+
+        assert_eq!(my_vec, vec![1, 2, 4, 5, 0]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::VecMutationHandle;
+
+/// Mutate a vec by looking for elements whose key was already seen earlier in the pass, keeping the order of the vector.
+/// For every element with a key seen before, `on_dup` is called with a handle on it and its key, e.g. to `discard` it.
+///
+/// Keys are taken from the elements when they are visited, so elements inserted and skipped are not seen.
+pub fn dedup_vec_by_key_by_handles<T, K: Hash + Eq>(
+    vec: &mut Vec<T>,
+    mut key: impl FnMut(&T) -> K,
+    mut on_dup: impl FnMut(VecMutationHandle<T>, &K),
+) {
+    let mut seen = HashSet::new();
+    crate::mutate_vec_by_handles(vec, |handle| {
+        let k = key(handle.get());
+        if seen.contains(&k) {
+            on_dup(handle, &k);
+        } else {
+            seen.insert(k);
+        }
+    });
+}
+
+/// Remove every element whose key was already seen earlier in the vector, keeping the first element of each key, in order.
+/// Unlike `Vec::dedup_by_key`, the duplicates don't have to be adjacent. See [`dedup_vec_by_key_by_handles`].
+pub fn dedup_vec_by_key<T, K: Hash + Eq>(vec: &mut Vec<T>, key: impl FnMut(&T) -> K) {
+    dedup_vec_by_key_by_handles(vec, key, |handle, _| {
+        handle.discard();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_by_key_scattered() {
+        let mut v = vec![3, 14, 1, 23, 5, 33, 2, 41, 12];
+        dedup_vec_by_key(&mut v, |x| x % 10);
+        assert_eq!(v, vec![3, 14, 1, 5, 2]);
+
+        let mut words = vec![
+            "apple",
+            "Avocado",
+            "banana",
+            "cherry",
+            "blueberry",
+            "Cranberry",
+            "date",
+        ];
+        dedup_vec_by_key(&mut words, |w| {
+            w.chars().next().map(|c| c.to_ascii_lowercase())
+        });
+        assert_eq!(words, vec!["apple", "banana", "cherry", "date"]);
+    }
+
+    #[test]
+    fn test_dedup_by_key_keep_and_insert() {
+        let mut v = vec![1, 2, 1, 3, 2, 1, 4];
+        let mut duplicates = vec![];
+        let mut kept = false;
+        dedup_vec_by_key_by_handles(
+            &mut v,
+            |x| *x,
+            |mut handle, key| {
+                duplicates.push(*key);
+                match *key {
+                    // Keep the second 1, but mark it.
+                    1 if !kept => {
+                        kept = true;
+                        handle.set(-1);
+                    }
+                    // Replace a duplicate 2 with a 3, which is itself a duplicate, and then a 20, which is new.
+                    2 => {
+                        handle.insert_and_process_iter([3, 20]);
+                        handle.discard();
+                    }
+                    _ => {
+                        handle.discard();
+                    }
+                }
+            },
+        );
+        assert_eq!(v, vec![1, 2, -1, 3, 20, 4]);
+        assert_eq!(duplicates, vec![1, 2, 3, 1]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+
+use crate::{HandleStorage, VecMutationHandle};
+
+type DeferredAction<S> = Box<dyn FnOnce(&mut S)>;
+
+// The actions scheduled with `VecMutationHandle::defer` during a pass, run by the runner in order once the pass is over.
+pub(crate) struct DeferredActions<S> {
+    actions: Vec<DeferredAction<S>>,
+    keys: Vec<&'static str>, // The keys of the actions scheduled with `defer_once`.
+}
+
+impl<S> DeferredActions<S> {
+    pub(crate) fn new() -> Self {
+        DeferredActions {
+            actions: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    pub(crate) fn run(self, storage: &mut S) {
+        for action in self.actions {
+            action(storage);
+        }
+    }
+}
+
+impl<S> fmt::Debug for DeferredActions<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferredActions")
+            .field("actions", &self.actions.len())
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+impl<T, S: HandleStorage<Item = T>> VecMutationHandle<'_, '_, T, S> {
+    /// Schedule an action on the whole vector, to run once the pass is over, also if it was stopped early. Actions run in the
+    /// order they were scheduled. Useful for operations which can't be done mid-pass, like sorting the vector.
+    ///
+    /// Handles created with `new` or `new_bounded` drop the action, and handles of an inner pass (see `mutate_rest_by_handles`) only
+    /// see the rest of the vector.
+    pub fn defer(&mut self, action: impl FnOnce(&mut S) + 'static) {
+        if let Some(deferred) = self.deferred_actions() {
+            deferred.actions.push(Box::new(action));
+        }
+    }
+
+    /// Schedule an action like `defer`, unless an action with the same key was already scheduled with this method in this pass.
+    /// Returns whether the action was scheduled.
+    pub fn defer_once(&mut self, key: &'static str, action: impl FnOnce(&mut S) + 'static) -> bool {
+        let Some(deferred) = self.deferred_actions() else {
+            return false;
+        };
+        if deferred.keys.contains(&key) {
+            return false;
+        }
+        deferred.keys.push(key);
+        deferred.actions.push(Box::new(action));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use std::num::NonZeroUsize;
+
+    use crate::{
+        mutate_first_match_by_handles, mutate_last_match_by_handles, mutate_matching_by_handles,
+        mutate_vec_by_handles, mutate_vec_by_handles_budgeted, mutate_vec_by_handles_observed,
+        mutate_vec_by_handles_resumable, mutate_vec_by_handles_step_by,
+        mutate_vec_range_by_handles, mutate_vecs_by_handles, try_mutate_vec_by_handles,
+        HandleCursor, HandleState, PassState,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_defer_sort() {
+        let mut v = vec![5, -1, 3, 8, -2, 1, 9];
+        let runs = Rc::new(Cell::new(0));
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            if *handle.get() < 0 {
+                handle.discard();
+                return;
+            }
+            if *handle.get() == 8 {
+                let runs = Rc::clone(&runs);
+                handle.defer(move |v| {
+                    runs.set(runs.get() + 1);
+                    assert_eq!(*v, vec![5, 3, 8, 1, 10]);
+                    v.sort_unstable();
+                });
+            }
+            if *handle.get() == 9 {
+                handle.set(10);
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5, 8, 10]);
+        assert_eq!(runs.get(), 1);
+    }
+
+    #[test]
+    fn test_defer_once_and_stop() {
+        let mut v = vec![3, 1, 2, 1, 4, 1];
+        let result = try_mutate_vec_by_handles(&mut v, |mut handle| {
+            if *handle.get() == 1 {
+                handle.defer_once("dedup", Vec::dedup);
+                handle.defer(|v| v.push(0));
+                handle.insert_and_skip(1);
+            }
+            if *handle.get() == 4 {
+                handle.stop_iteration();
+                return Err(());
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err(()));
+        // The dedup runs once, before the pushes scheduled after it, but after the pass stopped.
+        assert_eq!(v, vec![3, 1, 2, 1, 4, 1, 0, 0]);
+
+        // Handles created directly can't run deferred actions.
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert!(!handle.defer_once("clear", Vec::clear));
+        handle.defer(Vec::clear);
+        assert_eq!(v.len(), 8);
+    }
+
+    // Sorts the vector after the pass if it contains 8, stopping at 9.
+    fn defer_sort(mut handle: VecMutationHandle<i32>) {
+        match *handle.get() {
+            8 => handle.defer(|v| v.sort_unstable()),
+            9 => handle.stop_iteration(),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_defer_in_every_runner() {
+        let runners: [fn(&mut Vec<i32>); 11] = [
+            |v| mutate_vec_by_handles_step_by(v, NonZeroUsize::MIN, 0, defer_sort),
+            |v| mutate_vec_range_by_handles(v, 1.., defer_sort),
+            |v| mutate_matching_by_handles(v, |&x| x != 3, defer_sort),
+            |v| assert!(mutate_first_match_by_handles(v, |&x| x == 8, defer_sort)),
+            |v| assert!(mutate_last_match_by_handles(v, |&x| x % 4 == 0, defer_sort)),
+            |v| mutate_vecs_by_handles(std::slice::from_mut(v), defer_sort),
+            |v| mutate_vec_by_handles_observed(v, |_| {}, defer_sort),
+            |v| mutate_vec_by_handles_resumable(v, &mut PassState::new(), defer_sort),
+            |v| {
+                mutate_vec_by_handles_budgeted(v, 100, &mut 0, defer_sort);
+            },
+            |v| HandleCursor::new(v).for_each(defer_sort),
+            |v| {
+                let mut cursor = HandleCursor::new(v);
+                while let Some(handle) = cursor.next() {
+                    defer_sort(handle);
+                }
+            },
+        ];
+        for (i, runner) in runners.iter().enumerate() {
+            let mut v = vec![5, 3, 8, 1, 9, 2];
+            runner(&mut v);
+            assert_eq!(v, vec![1, 2, 3, 5, 8, 9], "runner {i}");
+        }
+    }
+
+    #[test]
+    fn test_defer_chained_vecs() {
+        let mut vecs = [vec![3, 1, 2], vec![6, 5, 4], vec![9, 8, 7]];
+        mutate_vecs_by_handles(&mut vecs, |mut handle| match *handle.get() {
+            3 | 5 => handle.defer(|v| v.sort_unstable()),
+            8 => handle.stop_iteration(),
+            _ => {}
+        });
+        // Each action runs on the vec it was scheduled on, also in the vec where the traversal stopped.
+        assert_eq!(vecs, [vec![1, 2, 3], vec![4, 5, 6], vec![9, 8, 7]]);
+    }
+
+    #[test]
+    fn test_defer_inner_pass() {
+        let mut v = vec![1, 4, 3, 2];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            if *handle.get() == 1 {
+                handle.mutate_rest_by_handles(|mut inner| {
+                    inner.defer_once("sort", |rest| {
+                        assert_eq!(*rest, vec![4, 3, 2]);
+                        rest.sort_unstable();
+                    });
+                });
+                handle.defer(|v| v.reverse());
+            }
+        });
+        assert_eq!(v, vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_defer_cursor_left_early() {
+        let mut v = vec![2, 1, 3];
+        let mut cursor = HandleCursor::new(&mut v);
+        cursor.next().unwrap().defer(|v| v.sort_unstable());
+        drop(cursor);
+        // The loop was left before `next` returned `None`, so the action never ran.
+        assert_eq!(v, vec![2, 1, 3]);
+    }
+}
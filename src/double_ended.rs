@@ -0,0 +1,190 @@
+// Contract:
+// `front < back <= vec.len()`
+// The elements before `front` and from `back` on are done, and not touched by the handle.
+/// Represents the unprocessed middle of a vector, walked from both ends by a front and a back cursor.
+/// Created by [`mutate_vec_by_handles_double_ended`].
+///
+/// The front element is the first unprocessed one, and the back element the last. They are the same element if only one is left.
+/// Moving a cursor consumes the handle, so the closure is called again for the new pair of elements.
+#[derive(Debug)]
+pub struct FrontBackHandle<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    front: &'b mut usize,
+    back: &'b mut usize, // One past the back element.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> FrontBackHandle<'a, 'b, T> {
+    fn new(
+        vec: &'a mut Vec<T>,
+        front: &'b mut usize,
+        back: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        if *stopped || *front >= *back {
+            return None;
+        }
+        Some(FrontBackHandle {
+            vec,
+            front,
+            back,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the front element.
+    #[must_use]
+    pub fn front(&self) -> &T {
+        &self.vec[*self.front]
+    }
+
+    /// Get a mutable reference to the front element.
+    #[must_use]
+    pub fn front_mut(&mut self) -> &mut T {
+        &mut self.vec[*self.front]
+    }
+
+    /// Get a reference to the back element.
+    #[must_use]
+    pub fn back(&self) -> &T {
+        &self.vec[*self.back - 1]
+    }
+
+    /// Get a mutable reference to the back element.
+    #[must_use]
+    pub fn back_mut(&mut self) -> &mut T {
+        &mut self.vec[*self.back - 1]
+    }
+
+    /// The number of unprocessed elements, from the front element to the back element, both included. Never 0.
+    #[must_use]
+    pub fn gap(&self) -> usize {
+        *self.back - *self.front
+    }
+
+    /// Swap the front and back elements. The cursors stay where they are.
+    pub fn swap_front_back(&mut self) {
+        self.vec.swap(*self.front, *self.back - 1);
+    }
+
+    /// Mark the front element as done, continuing with the element after it.
+    pub fn advance_front(self) {
+        *self.front += 1;
+    }
+
+    /// Mark the back element as done, continuing with the element before it.
+    pub fn retreat_back(self) {
+        *self.back -= 1;
+    }
+
+    /// Remove the front element, and return it as owned. The elements after it, including the done ones at the back, move one place forward.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard_front(self) -> T {
+        *self.back -= 1;
+        self.vec.remove(*self.front)
+    }
+
+    /// Remove the back element, and return it as owned. The done elements after it move one place forward.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard_back(self) -> T {
+        *self.back -= 1;
+        self.vec.remove(*self.back)
+    }
+
+    /// Do not process any more elements.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a vec by walking it from both ends at once, with a front and a back cursor converging until they meet. See [`FrontBackHandle`].
+///
+/// If the closure returns without moving either cursor (or stopping), the front cursor advances, like the index of
+/// [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), so every pass ends. Returns the position of the front cursor at the
+/// end, which is where the cursors met, unless the iteration was stopped.
+pub fn mutate_vec_by_handles_double_ended<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(FrontBackHandle<T>),
+) -> usize {
+    let mut front = 0;
+    let mut back = vec.len();
+    let mut stopped = false;
+
+    while let Some(handle) = FrontBackHandle::new(vec, &mut front, &mut back, &mut stopped) {
+        let before = (*handle.front, *handle.back);
+        op(handle);
+        if !stopped && (front, back) == before {
+            front += 1;
+        }
+    }
+    front
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_ended_partition() {
+        let original: Vec<i32> = (0..50).map(|i| (i * 37) % 23 - 11).collect();
+        let keep = |x: &i32| x % 3 != 0;
+
+        let mut v = original.clone();
+        let split = mutate_vec_by_handles_double_ended(&mut v, |mut handle| {
+            if keep(handle.front()) {
+                handle.advance_front();
+            } else {
+                handle.swap_front_back();
+                handle.retreat_back();
+            }
+        });
+
+        let mut kept = original.clone();
+        let mut rejected: Vec<i32> = original.iter().copied().filter(|x| !keep(x)).collect();
+        kept.retain(keep);
+        let (mut front, mut back) = (v[..split].to_vec(), v[split..].to_vec());
+        for part in [&mut kept, &mut rejected, &mut front, &mut back] {
+            part.sort_unstable();
+        }
+        assert_eq!(front, kept);
+        assert_eq!(back, rejected);
+    }
+
+    #[test]
+    fn test_double_ended_discard_alternately() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7];
+        let mut from_front = true;
+        let mut discarded = vec![];
+        let mut gaps = vec![];
+        mutate_vec_by_handles_double_ended(&mut v, |handle| {
+            gaps.push(handle.gap());
+            if handle.gap() <= 2 {
+                return;
+            }
+            discarded.push(if from_front {
+                handle.discard_front()
+            } else {
+                handle.discard_back()
+            });
+            from_front = !from_front;
+        });
+        assert_eq!(v, vec![4, 5]);
+        assert_eq!(discarded, vec![1, 7, 2, 6, 3]);
+        assert_eq!(gaps, vec![7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_double_ended_stop() {
+        let mut v = vec![1, 2, 3, 4];
+        let end = mutate_vec_by_handles_double_ended(&mut v, |mut handle| {
+            *handle.back_mut() *= 10;
+            if *handle.back() == 30 {
+                handle.stop_iteration();
+            } else {
+                handle.retreat_back();
+            }
+        });
+        assert_eq!(v, vec![1, 2, 30, 40]);
+        assert_eq!(end, 0);
+    }
+}
@@ -0,0 +1,200 @@
+/// What happens to an element in [`drain_vec_by_handles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action<T> {
+    /// Keep the (possibly changed) element.
+    Keep(T),
+    /// Drop the element.
+    Drop,
+    /// Put these elements in place of the element, in order. They are not given to the closure.
+    Replace(Vec<T>),
+    /// Keep the element, and keep all following elements as they are, without giving them to the closure.
+    KeepAndStop(T),
+}
+
+/// Read-only view of the elements after the current one in [`drain_vec_by_handles`], which have not been given to the closure yet.
+#[derive(Debug, Clone, Copy)]
+pub struct Lookahead<'a, T> {
+    rest: &'a [T],
+}
+
+impl<'a, T> Lookahead<'a, T> {
+    /// Get the element `offset` positions after the current one, with 0 being the next element.
+    #[must_use]
+    pub fn peek(&self, offset: usize) -> Option<&'a T> {
+        self.rest.get(offset)
+    }
+
+    /// All elements after the current one, in order.
+    #[must_use]
+    pub fn as_slice(&self) -> &'a [T] {
+        self.rest
+    }
+
+    /// The number of elements after the current one.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rest.len()
+    }
+
+    /// Whether the current element is the last one.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+}
+
+// Puts elements which have not been given to the closure back after the output, also if the closure panics.
+struct Remaining<'v, T> {
+    output: &'v mut Vec<T>,
+    input: std::vec::IntoIter<T>,
+}
+
+impl<T> Drop for Remaining<'_, T> {
+    fn drop(&mut self) {
+        self.output.extend(self.input.by_ref());
+    }
+}
+
+/// Rebuild a vec by deciding what happens to each element, given by value along with a view of the elements after it.
+///
+/// Unlike the handle based runners, this moves each element only once, into a new vector, so a pass is O(n) plus the number of inserted
+/// elements, however many elements are dropped or replaced. This makes it the efficient choice for heavy filtering.
+///
+/// If the closure panics, the element it was given is lost, but the vector contains everything produced before it followed by all
+/// elements after it.
+pub fn drain_vec_by_handles<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(T, Lookahead<'_, T>) -> Action<T>,
+) {
+    let input = std::mem::take(vec).into_iter();
+    vec.reserve(input.len());
+    let mut remaining = Remaining { output: vec, input };
+
+    while let Some(t) = remaining.input.next() {
+        let lookahead = Lookahead {
+            rest: remaining.input.as_slice(),
+        };
+        match op(t, lookahead) {
+            Action::Keep(t) => remaining.output.push(t),
+            Action::Drop => {}
+            Action::Replace(replacements) => remaining.output.extend(replacements),
+            Action::KeepAndStop(t) => {
+                remaining.output.push(t);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::mutate_vec_by_handles;
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(i32);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Counted(self.0)
+        }
+    }
+
+    #[test]
+    fn test_drain_vec_matches_handles() {
+        let original: Vec<i32> = (0..30).collect();
+
+        let mut drained = original.clone();
+        drain_vec_by_handles(&mut drained, |t, lookahead| match t {
+            t if t % 3 == 0 => Action::Drop,
+            t if t % 5 == 0 => Action::Replace(vec![t, -t]),
+            t if lookahead.peek(0) == Some(&(t + 1)) => Action::Keep(t * 10),
+            t => Action::Keep(t),
+        });
+
+        let mut handled = original;
+        mutate_vec_by_handles(&mut handled, |mut handle| {
+            let t = *handle.get();
+            if t % 3 == 0 {
+                handle.discard();
+            } else if t % 5 == 0 {
+                handle.insert_and_skip(-t);
+            } else if handle.peek_forward_slice(1) == Some(&(t + 1)) {
+                handle.set(t * 10);
+            }
+        });
+
+        assert_eq!(drained, handled);
+    }
+
+    #[test]
+    fn test_drain_vec_keep_and_stop() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut seen = vec![];
+        drain_vec_by_handles(&mut v, |t, lookahead| {
+            seen.push(t);
+            if t == 2 {
+                Action::Drop
+            } else if lookahead.len() == 3 {
+                Action::KeepAndStop(t * 10)
+            } else {
+                Action::Keep(t)
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(v, vec![1, 30, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_drain_vec_replacements_not_refed() {
+        let mut v = vec![1, 2];
+        let mut calls = 0;
+        drain_vec_by_handles(&mut v, |t, _| {
+            calls += 1;
+            Action::Replace(vec![t, t, t])
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(v, vec![1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_drain_vec_restores_on_panic() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            drain_vec_by_handles(&mut v, |t, _| {
+                assert!(t != 4, "boom");
+                if t == 2 {
+                    Action::Drop
+                } else {
+                    Action::Keep(t)
+                }
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_drain_vec_no_clones() {
+        let mut v: Vec<Counted> = (0..1000).map(Counted).collect();
+        CLONES.with(|clones| clones.set(0));
+        drain_vec_by_handles(&mut v, |t, lookahead| {
+            assert!(lookahead.as_slice().len() < 1000);
+            if t.0 % 2 == 0 {
+                Action::Drop
+            } else {
+                Action::Keep(t)
+            }
+        });
+        assert_eq!(CLONES.with(Cell::get), 0);
+        assert_eq!(v.len(), 500);
+        assert!(v.iter().all(|t| t.0 % 2 == 1));
+    }
+}
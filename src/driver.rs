@@ -0,0 +1,151 @@
+use crate::defer::DeferredActions;
+use crate::{HandleState, HandleStorage, InsertedPositions, VecMutationHandle};
+
+// The loop behind every runner taking a closure, and `HandleCursor`: creates the handles of a pass one at a time, keeping where the
+// pass is and ends, the positions of inserted elements, and the actions scheduled with `defer`, which run once `next` returns `None`.
+pub(crate) struct PassDriver<S: HandleStorage> {
+    state: HandleState,
+    end: usize,
+    inserted: InsertedPositions,
+    deferred: DeferredActions<S>,
+    stride: usize,
+    stepping: bool, // Whether a handle was created since the pass last moved on by the stride.
+    windowed: bool,
+}
+
+impl<S: HandleStorage> PassDriver<S> {
+    // A pass over the whole storage.
+    pub(crate) fn new(storage: &S) -> Self {
+        PassDriver {
+            state: HandleState::new(),
+            end: storage.len(),
+            inserted: InsertedPositions::new(),
+            deferred: DeferredActions::new(),
+            stride: 1,
+            stepping: false,
+            windowed: false,
+        }
+    }
+
+    // Start the pass at `position` instead of the first element.
+    pub(crate) fn starting_at(mut self, position: usize) -> Self {
+        self.state = HandleState::starting_at(position);
+        self
+    }
+
+    // End the pass at `end` instead of the end of the storage, confining the methods working on the rest to it as well.
+    pub(crate) fn within(mut self, end: usize) -> Self {
+        self.end = end;
+        self.windowed = true;
+        self
+    }
+
+    // Visit every `stride`-th element, see `mutate_vec_by_handles_step_by`.
+    pub(crate) fn step_by(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    // Keep the positions of all inserted elements, for `was_inserted_this_pass`.
+    pub(crate) fn track_insertions(mut self, track: bool) -> Self {
+        self.inserted.track_all(track);
+        self
+    }
+
+    // Keep the positions of inserted elements in `inserted`, which may already hold some, e.g. from an outer pass.
+    pub(crate) fn with_inserted(mut self, inserted: InsertedPositions) -> Self {
+        self.inserted = inserted;
+        self
+    }
+
+    // Continue from the positions of an earlier call, see `take_progress`.
+    pub(crate) fn resume(
+        mut self,
+        state: HandleState,
+        end: usize,
+        inserted: InsertedPositions,
+    ) -> Self {
+        self.state = state;
+        self.end = end;
+        self.inserted = inserted;
+        self
+    }
+
+    // The index of the element `next` creates a handle for, or `None` if the pass is over.
+    pub(crate) fn upcoming(&mut self, storage: &S) -> Option<usize> {
+        if std::mem::take(&mut self.stepping) && !self.state.is_stopped() {
+            let position = self.state.position().saturating_add(self.stride - 1);
+            self.state = HandleState::starting_at(position);
+        }
+        while !self.state.is_stopped() && self.inserted.is_skipped(self.state.position()) {
+            self.state = HandleState::starting_at(self.state.position() + 1);
+        }
+        self.state
+            .visits(self.end, storage.len())
+            .then(|| self.state.position())
+    }
+
+    // The handle for the next element of the pass, or `None` once the pass is over, after running the deferred actions.
+    pub(crate) fn next<'a>(
+        &'a mut self,
+        storage: &'a mut S,
+    ) -> Option<VecMutationHandle<'a, 'a, S::Item, S>> {
+        // Checked up front, as the handle borrows the storage. `new_bounded` makes the same check, so it creates the handle after it.
+        if self.upcoming(storage).is_none() {
+            self.finish(storage);
+            return None;
+        }
+        self.stepping = self.stride > 1;
+        let handle = VecMutationHandle::new_bounded(storage, &mut self.state, &mut self.end)?
+            .with_inserted(&mut self.inserted)
+            .with_stride(self.stride)
+            .with_deferred(&mut self.deferred);
+        Some(if self.windowed {
+            handle.within_window()
+        } else {
+            handle
+        })
+    }
+
+    // Run the deferred actions, for runners ending the pass before `next` returns `None`. Does nothing if they already ran.
+    pub(crate) fn finish(&mut self, storage: &mut S) {
+        std::mem::replace(&mut self.deferred, DeferredActions::new()).run(storage);
+    }
+
+    // The position, end and inserted elements of the pass, to be resumed with `resume`. Leaves no inserted elements behind.
+    pub(crate) fn take_progress(&mut self) -> (HandleState, usize, InsertedPositions) {
+        (self.state, self.end, std::mem::take(&mut self.inserted))
+    }
+
+    pub(crate) fn into_inserted(self) -> InsertedPositions {
+        self.inserted
+    }
+
+    // The index of the next element to process, like `HandleState::position`.
+    pub(crate) fn position(&self) -> usize {
+        self.state.position()
+    }
+
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.state.is_stopped()
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.state.stop();
+    }
+}
+
+impl<S: HandleStorage> std::fmt::Debug for PassDriver<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassDriver")
+            .field("state", &self.state)
+            .field("end", &self.end)
+            .field("inserted", &self.inserted)
+            .field("deferred", &self.deferred)
+            .finish_non_exhaustive()
+    }
+}
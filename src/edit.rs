@@ -0,0 +1,177 @@
+use crate::driver::PassDriver;
+
+#[derive(Debug)]
+enum Command<T> {
+    InsertAfter(T),
+    InsertAfterSkipped(T),
+    Skip(usize),
+}
+
+/// Records the structural changes to make around the current element in [`edit_vec_by_handles`], which applies them after the
+/// closure returns. This way, the closure can hold the `&mut T` to the current element for its whole body.
+#[derive(Debug)]
+pub struct EditCtl<'a, T> {
+    rest: &'a [T],
+    commands: Vec<Command<T>>,
+    discard: bool,
+    stop: bool,
+}
+
+impl<T> EditCtl<'_, T> {
+    /// The elements after the current one, as they are before the recorded changes are applied.
+    #[must_use]
+    pub fn rest(&self) -> &[T] {
+        self.rest
+    }
+
+    /// Remove the current element, after applying the other recorded changes. Recording this more than once has no further effect.
+    /// Changes made to the element through the `&mut T` are then irrelevant, as the element is dropped.
+    pub fn discard(&mut self) {
+        self.discard = true;
+    }
+
+    /// Insert an element AFTER the current one, and process it later, like `VecMutationHandle::insert_and_process`.
+    pub fn insert_after(&mut self, t: T) {
+        self.commands.push(Command::InsertAfter(t));
+    }
+
+    /// Insert an element AFTER the current one, without processing it, like `VecMutationHandle::insert_and_skip`.
+    pub fn insert_after_skipped(&mut self, t: T) {
+        self.commands.push(Command::InsertAfterSkipped(t));
+    }
+
+    /// Skip a certain amount of the next elements, like `VecMutationHandle::skip_forward`.
+    pub fn skip(&mut self, steps_to_skip: usize) {
+        self.commands.push(Command::Skip(steps_to_skip));
+    }
+
+    /// Do not process any more elements. Recording this more than once has no further effect.
+    pub fn stop(&mut self) {
+        self.stop = true;
+    }
+}
+
+/// Mutate a vec using index-style looping, where the closure gets the current element itself, and records structural changes
+/// with an [`EditCtl`], instead of calling methods on a handle.
+///
+/// After the closure returns, the recorded insertions and skips are applied in the order they were recorded, with the same
+/// meaning as the corresponding `VecMutationHandle` methods. Then the element is discarded and the iteration is stopped, if
+/// that was recorded. So inserted elements are kept when the element they were inserted after is discarded.
+pub fn edit_vec_by_handles<T>(vec: &mut Vec<T>, mut op: impl FnMut(&mut T, &mut EditCtl<T>)) {
+    let mut driver = PassDriver::new(vec);
+    let mut commands = vec![]; // Reused for every element, to avoid allocating.
+
+    while let Some(mut handle) = driver.next(vec) {
+        let (current, rest) = handle.split_current_and_rest_mut();
+        let mut ctl = EditCtl {
+            rest,
+            commands: std::mem::take(&mut commands),
+            discard: false,
+            stop: false,
+        };
+        op(current, &mut ctl);
+
+        let EditCtl {
+            commands: mut recorded,
+            discard,
+            stop,
+            ..
+        } = ctl;
+        for command in recorded.drain(..) {
+            match command {
+                Command::InsertAfter(t) => handle.insert_and_process(t),
+                Command::InsertAfterSkipped(t) => handle.insert_and_skip(t),
+                Command::Skip(steps_to_skip) => handle.skip_forward(steps_to_skip),
+            }
+        }
+        commands = recorded;
+
+        match (discard, stop) {
+            (true, true) => drop(handle.discard_and_stop_iteration()),
+            (true, false) => drop(handle.discard()),
+            (false, true) => handle.stop_iteration(),
+            (false, false) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_insertion_and_deletion() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        edit_vec_by_handles(&mut v, |t, ctl| {
+            if *t == 3 {
+                ctl.insert_after_skipped(100);
+                ctl.discard();
+            }
+        });
+        assert_eq!(v, vec![1, 2, 100, 4, 5]);
+
+        let mut v = vec![1, 2, 3];
+        edit_vec_by_handles(&mut v, |_, ctl| ctl.insert_after_skipped(10));
+        assert_eq!(v, vec![1, 10, 2, 10, 3, 10]);
+    }
+
+    #[test]
+    fn test_edit_peeks_and_state_insert_process() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 7, 1];
+        let mut my_count = 0;
+
+        edit_vec_by_handles(&mut my_vec, |elem, ctl| {
+            my_count += *elem;
+            if *elem > 6 {
+                ctl.discard();
+            } else if *elem < 3 {
+                *elem = ctl.rest().len() + 1;
+            } else if *elem == 4 {
+                ctl.insert_after(7);
+            }
+        });
+
+        assert_eq!(my_count, 35);
+        assert_eq!(my_vec, vec![7, 3, 4, 5, 6, 1]);
+    }
+
+    #[test]
+    fn test_edit_break_loop_and_skip() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        edit_vec_by_handles(&mut my_vec, |elem, ctl| {
+            if *elem > 10 {
+                ctl.stop();
+                ctl.stop();
+            } else {
+                ctl.discard();
+            }
+        });
+        assert_eq!(my_vec, vec![11, 1, 5, 7]);
+
+        let mut visited = vec![];
+        edit_vec_by_handles(&mut my_vec, |elem, ctl| {
+            visited.push(*elem);
+            ctl.skip(1);
+        });
+        assert_eq!(visited, vec![11, 5]);
+    }
+
+    #[test]
+    fn test_edit_hold_reference_and_discard() {
+        let mut v: Vec<String> = ["keep", "drop", "keep"].map(String::from).to_vec();
+        let mut dropped = 0;
+        edit_vec_by_handles(&mut v, |s, ctl| {
+            // The reference is held across the recording of structural changes.
+            s.push('!');
+            if s.starts_with("drop") {
+                ctl.discard();
+                ctl.discard();
+                ctl.insert_after_skipped(String::from("inserted"));
+                dropped += 1;
+            }
+            s.push('?');
+        });
+        assert_eq!(dropped, 1);
+        assert_eq!(v, vec!["keep!?", "inserted", "keep!?"]);
+    }
+}
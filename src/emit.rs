@@ -0,0 +1,94 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::VecMutationHandle;
+
+/// A [`VecMutationHandle`] which can also emit values into the output of the pass. Created by [`mutate_vec_by_handles_emitting`].
+///
+/// Derefs to the handle, so all methods borrowing the handle can be called directly. For the methods consuming it, like
+/// `discard`, emit first, and then use `into_handle`.
+#[derive(Debug)]
+pub struct EmitHandle<'a, 'b, 'o, T, U> {
+    handle: VecMutationHandle<'a, 'b, T>,
+    output: &'o mut Vec<U>,
+}
+
+impl<'a, 'b, T, U> EmitHandle<'a, 'b, '_, T, U> {
+    /// Append a value to the output of the pass. May be called any number of times per element.
+    pub fn emit(&mut self, u: U) {
+        self.output.push(u);
+    }
+
+    /// Give up emitting for this element, and get the handle, e.g. to `discard` the element.
+    #[must_use]
+    pub fn into_handle(self) -> VecMutationHandle<'a, 'b, T> {
+        self.handle
+    }
+}
+
+impl<'a, 'b, T, U> Deref for EmitHandle<'a, 'b, '_, T, U> {
+    type Target = VecMutationHandle<'a, 'b, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl<T, U> DerefMut for EmitHandle<'_, '_, '_, T, U> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), and return the values
+/// emitted with [`EmitHandle::emit`] in the order they were emitted, also if the pass was stopped early.
+pub fn mutate_vec_by_handles_emitting<T, U>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(EmitHandle<T, U>),
+) -> Vec<U> {
+    let mut output = Vec::new();
+    crate::mutate_vec_by_handles(vec, |handle| {
+        op(EmitHandle {
+            handle,
+            output: &mut output,
+        });
+    });
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emitting_validation() {
+        let mut v = vec![3, -1, 4, 0, 5, -9, 2];
+        let errors = mutate_vec_by_handles_emitting(&mut v, |mut handle| {
+            let x = *handle.get();
+            if x > 0 {
+                return;
+            }
+            handle.emit(format!("{x} at {}", handle.index()));
+            handle.into_handle().discard();
+        });
+        assert_eq!(v, vec![3, 4, 5, 2]);
+        assert_eq!(errors, vec!["-1 at 1", "0 at 2", "-9 at 3"]);
+    }
+
+    #[test]
+    fn test_emitting_several_and_stop() {
+        let mut v = vec![1, 2, 3, 4];
+        let output = mutate_vec_by_handles_emitting(&mut v, |mut handle| {
+            let x = *handle.get();
+            for _ in 0..x {
+                handle.emit(x);
+            }
+            if x == 2 {
+                handle.insert_and_skip(20);
+            } else if x == 3 {
+                handle.into_handle().stop_iteration();
+            }
+        });
+        assert_eq!(v, vec![1, 2, 20, 3, 4]);
+        assert_eq!(output, vec![1, 2, 2, 3, 3, 3]);
+    }
+}
@@ -1,4 +1,5 @@
-#![deny(unsafe_code)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg_attr(not(feature = "unchecked"), deny(unsafe_code))]
 #![warn(clippy::pedantic)]
 #![warn(missing_docs)]
 #![warn(clippy::cargo)]
@@ -36,12 +37,12 @@
 //!
 //! # Example with while loop, if you don't like closures:
 //! ```
-//! use handlevec::VecMutationHandle;
+//! use handlevec::{HandleState, VecMutationHandle};
 //! let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
 //!
-//! let mut my_index = 0;
+//! let mut my_state = HandleState::new();
 //!
-//! while let Some(mut elem) = VecMutationHandle::new(&mut my_vec, &mut my_index) {
+//! while let Some(mut elem) = VecMutationHandle::new(&mut my_vec, &mut my_state) {
 //!     if *elem.get() > 10 {
 //!        elem.discard_and_stop_iteration();
 //!     } else {
@@ -50,6 +51,18 @@
 //! }
 //!
 //! assert_eq!(my_vec, vec![20, 20, 20, 20, 20, 1, 5, 7]);
+//!
+//! // Or with a cursor, which keeps track of the state itself:
+//! use handlevec::HandleCursor;
+//!
+//! let mut cursor = HandleCursor::new(&mut my_vec);
+//! while let Some(elem) = cursor.next() {
+//!     if *elem.get() == 1 {
+//!        elem.discard();
+//!     }
+//! }
+//!
+//! assert_eq!(my_vec, vec![20, 20, 20, 20, 20, 5, 7]);
 //! ```
 //!
 //! For most of these examples, it might have been far better to use normal iterators, maybe flatmap or filter or map.
@@ -58,8 +71,10 @@
 //!
 //! Please note that this package does not in any way attempt to "buffer" changes done to the vector. Changes are applied at function call.
 //! For long vectors and many insertions or deletions, reorganizing the vector after each iteration might not be very performant.
+//! For passes with many discards, `mutate_vec_by_handles_compacting` provides the same handle API, but moves each element to its final place only once.
 //!
-//! This does contain unwraps, but they are never supposed to be reachable using any inputs on the public API.
+//! Handles are only created for valid indices, and every method keeps them valid, so accessing the current element needs no unwraps,
+//! and the bounds checks of indexing the vector are never supposed to fail using any inputs on the public API.
 //! If you get a panic from this crate, a bug report is very appreciated.
 //!
 //! # Features
@@ -73,263 +88,5487 @@
 //! 8. Stop iteration. The remainder of the closure or loop is still executed, as the method must return, but no further elements are processed.
 //! 9. Discard the current element, and stop the iteration. Both the `discard` and `stop_iteration` methods consume ownership of the handle, so this is provided if you want to do both.
 //! 10. "Peek" a (potentially mutable) reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
-//! 11. Insert multiple elements, in the correct order. (calling insert multiple times will reverse the order of the inserted elements, akin to a stack push.)
-//! 12. Replace the element at a specific place with another one.
-//! 13. Finally, the closure is an `FnMut`, so the inner loop can affect mutable variables outside the closure.
+//! 11. Insert multiple elements from a vec or any iterator, in the correct order. (calling insert multiple times will reverse the order of the inserted elements, akin to a stack push.)
+//! 12. Replace the element at a specific place with another one, or with several (or no) elements.
+//! 13. Merge the current element with the next one, by value.
+//! 14. Split the current element into several pieces, by value.
+//! 15. Take ownership of a number of the following elements, removing them from the vector.
+//! 16. Remove or keep the remaining elements in bulk, e.g. removing everything after the current element, or retaining the remaining elements matching a predicate.
+//! 17. Append elements to the end of the vector, either processing them when the iteration reaches them, or leaving them untouched by the current pass.
+//! 18. Defer the current element to the end of the vector, to be processed again later in the pass or not.
+//! 19. Temporarily take ownership of the current element, then put it (or a rebuilt value) back, or discard it.
+//! 20. Insert elements into their sorted position among the remaining elements, to be processed later or skipped.
+//! 21. Iterate from the last element towards the first instead, with `mutate_vec_by_handles_rev`.
+//! 22. Restrict a pass to a range of the vector, with `mutate_vec_range_by_handles`.
+//! 23. Visit only every `step`-th element, e.g. one lane of interleaved data, with `mutate_vec_by_handles_step_by`.
+//! 24. Consume a vector into a vector of another type, emitting any number of elements per element, with `map_vec_by_handles`.
+//! 25. A by-value drain mode, `drain_vec_by_handles`, where the closure returns an `Action` for each element and the vector is rebuilt in a single pass.
+//! 26. Mutating two parallel vectors in lockstep with `mutate_vecs_zipped_by_handles`, where discards and inserts keep them aligned.
+//! 27. Nested passes over the elements after the current one, with `mutate_rest_by_handles`.
+//! 28. Pairwise passes with `mutate_vec_by_adjacent_pairs`, giving mutable access to an element and its successor at once.
+//! 29. Passes over fixed-size chunks of a flat vector with `mutate_vec_by_chunk_handles`.
+//! 30. Passes over maximal runs of related elements with `mutate_vec_by_runs`, generalizing `dedup_by`.
+//! 31. Repeating passes until nothing changes with `mutate_vec_by_handles_until_stable`.
+//! 32. A `HandlePass` builder for combining options like a start index, a visit limit and skipping inserted elements, reporting how the pass ended.
+//! 33. Char by char mutation of strings with `mutate_string_by_handles`, keeping the string valid UTF-8.
+//! 34. Async runners taking async closures, `mutate_vec_by_handles_async` and `try_mutate_vec_by_handles_async`, behind the `async` feature.
+//! 35. A `debug-invariants` feature, which checks the internal contract of handles in every method and panics with a description of what was violated.
+//! 36. An `unchecked` feature, which skips the bounds checks of `get`, `get_mut` and the peek methods in hot loops. The default build contains no unsafe code.
+//! 37. Giving only the elements matching a predicate to the closure, with `mutate_matching_by_handles`.
+//! 38. Mutating slices, arrays and boxed slices in place with `mutate_slice_by_handles`, without insertions or removals.
+//! 39. Look at a sliding window of several consecutive elements starting at each element, and rewrite it into any number of elements, with `mutate_vec_by_windows`.
+//! 40. Split a vector in two, with lookahead for each decision, in a single O(n) compaction, with `partition_vec_by_handles`.
+//! 41. Break a vector into segments at boundaries decided per element, with lookahead, with `split_vec_by_handles`.
+//! 42. A parallel runner for passes which only change elements in place, with read-only lookahead, `par_mutate_vec_by_handles`, behind the `rayon` feature.
+//! 43. A transactional runner, `mutate_vec_by_handles_transactional`, which restores the vector as it was before the pass when the closure returns an error.
+//! 44. Dry runs recording what a pass would do, with `plan_vec_by_handles`, and replaying the recording onto a vector with `apply_plan`.
+//! 45. Editing around the first or last element matching a predicate, with `mutate_first_match_by_handles` and `mutate_last_match_by_handles`.
+//! 46. A runner giving the closure the element itself as `&mut T`, with structural changes recorded on an `EditCtl` and applied afterwards, `edit_vec_by_handles`.
+//! 47. Observing a pass as it happens, with an observer given a `HandleEvent` for each visit, removal, insertion, replacement, skip and stop, `mutate_vec_by_handles_observed`.
+//! 48. Passes over a `Cow<[T]>` which only clone the borrowed slice once something is mutated, with `mutate_cow_by_handles`.
+//! 49. Flattened passes over the elements of a `Vec<Vec<T>>`, removing inner vectors which become empty, with `mutate_nested_by_handles`.
+//! 50. Emitting values of another type alongside a pass, like diagnostics, returned in order by `mutate_vec_by_handles_emitting`.
+//! 51. A fallible runner which visits every element and collects all errors with their indices, `mutate_vec_by_handles_accumulate_errors`.
+//! 52. Folding adjacent elements together in place, like itertools' `coalesce`, with `coalesce_vec_by_handles`.
+//! 53. Walking a vector from both ends at once, with front and back cursors converging, with `mutate_vec_by_handles_double_ended`.
+//! 54. An `allocator_api` feature for nightly compilers, which lets `mutate_vec_by_handles` run over a `Vec` in any allocator.
+//! 55. Opt-in access to the element right before the current one, for merging backward, with `prev_mut` and `split_prev_current_mut`.
+//! 56. Schedule actions on the whole vector to run after the pass, with `defer` and `defer_once`.
+//! 57. Process a vec in slices of a budget of elements per call, resuming from a cursor, with `mutate_vec_by_handles_budgeted`.
+//! 58. Remove or replace the element a given offset ahead, with `discard_forward_at` and `replace_forward_at`.
+//! 59. Detect changes made through `get_mut` with `with_change_detection` and `mutate_vec_by_handles_count_changes`.
+//! 60. Remove non-adjacent duplicates by key, or handle each of them, with `dedup_vec_by_key` and `dedup_vec_by_key_by_handles`.
+//! 61. Merge a second vector into the vector of a pass, e.g. a sorted merge, with `merge_vec_by_handles` and `merge_sorted_by`.
+//! 62. Accumulate into a batch during a pass, with a guaranteed flush at the end, with `mutate_vec_by_handles_batched`.
+//! 63. Skip ahead by the total cost of the skipped elements instead of their count, with `skip_forward_by_cost`.
+//! 64. Give a helper a handle restricted to a bounded lookahead, with `scoped`.
+//! 65. Store passes owning their closure, choose them at runtime, and apply them to many vectors, with `Pass` and `BoxedPass`.
+//! 66. Cap the length of a vector during a pass, with fallible inserts, with `mutate_vec_by_handles_capped`.
+//! 67. Move elements into buckets with `move_current_to`, or route a whole vector into buckets in one compaction with `route_vec_by_handles`.
+//! 68. Finally, the closure is an `FnMut`, so the inner loop can affect mutable variables outside the closure.
 //!
 //! By design, mutating or obtaining elements prior to the current one is not allowed.
+//!
+//! # Panic safety
+//! If the closure panics, the vector is left in a valid state: every element is either still in the vector, or was given to the closure by value (e.g. by `discard`), and the elements are in the order the operations done before the panic left them in. Methods which take the current element out of the vector temporarily (like `replace_with`) put the other elements back in order while unwinding, and runners with their own storage (like `mutate_vec_by_handles_compacting`) restore the vector. To continue a pass after catching the panic, see `mutate_vec_by_handles_resumable`.
 
+#[cfg(feature = "async")]
+pub use crate::asynchronous::*;
+pub use crate::batch::*;
+pub use crate::capped::*;
+pub use crate::chunk::*;
+pub use crate::compacting::*;
+pub use crate::cow::*;
+pub use crate::cursor::*;
+pub use crate::dedup::*;
+pub use crate::double_ended::*;
+pub use crate::drain::*;
+pub use crate::edit::*;
+pub use crate::emit::*;
+pub use crate::map::*;
+pub use crate::merge::*;
+pub use crate::nested::*;
+pub use crate::observe::*;
+pub use crate::origin::*;
+pub use crate::pair::*;
+#[cfg(feature = "rayon")]
+pub use crate::parallel::*;
+pub use crate::partition::*;
+pub use crate::pass::*;
+pub use crate::plan::*;
+pub use crate::rev::*;
+pub use crate::route::*;
+pub use crate::run::*;
+pub use crate::scoped::*;
+pub use crate::slice::*;
+pub use crate::slot::*;
+pub use crate::split::*;
+pub use crate::string::*;
+pub use crate::summary::*;
 pub use crate::vec_mut_handle_core::*;
+pub use crate::window::*;
+pub use crate::zip::*;
+
+#[cfg(feature = "async")]
+mod asynchronous;
+mod batch;
+mod capped;
+mod chunk;
+mod compacting;
+mod cow;
+mod cursor;
+mod dedup;
+mod defer;
+mod double_ended;
+mod drain;
+mod driver;
+mod edit;
+mod emit;
+mod map;
+mod merge;
+mod nested;
+mod observe;
+mod origin;
+mod pair;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod partition;
+mod pass;
+mod plan;
+mod rev;
+mod route;
+mod run;
+mod scoped;
+mod slice;
+mod slot;
+mod split;
+mod string;
+mod summary;
+mod window;
+mod zip;
+
+use std::borrow::Borrow;
+use std::num::NonZeroUsize;
+use std::ops::{Bound, ControlFlow, RangeBounds};
+
+use crate::driver::PassDriver;
 
 // Core of vector mutations. Attempt to keep small, to have guaranteed no panics. Sealed in it's own module to restrict surface area.
 mod vec_mut_handle_core {
+    use std::cmp::Ordering;
+    use std::num::NonZeroUsize;
     use std::slice::SliceIndex;
 
+    use crate::defer::DeferredActions;
+    use crate::{HandleEvent, MutationSummary};
+
+    pub(crate) mod sealed {
+        pub trait Sealed {}
+    }
+
+    /// The storage a [`VecMutationHandle`] operates on. Implemented for `Vec<T>`, and for the internal storage of
+    /// alternative runners such as [`mutate_vec_by_handles_compacting`](crate::mutate_vec_by_handles_compacting).
+    ///
+    /// All indices are "logical" indices, as if the storage was a single vector. This trait is sealed.
+    pub trait HandleStorage: sealed::Sealed {
+        /// The element type of the storage.
+        type Item;
+
+        /// The logical length of the storage.
+        fn len(&self) -> usize;
+
+        /// Whether the storage contains no elements.
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Get a reference to the element at `index`, if it exists.
+        fn get(&self, index: usize) -> Option<&Self::Item>;
+
+        /// Get a mutable reference to the element at `index`, if it exists.
+        fn get_mut(&mut self, index: usize) -> Option<&mut Self::Item>;
+
+        /// Remove and return the element at `index`, shifting all elements after it, or `None` if `index` is out of bounds.
+        fn remove(&mut self, index: usize) -> Option<Self::Item>;
+
+        /// Insert an element at `index`, shifting all elements after it. Panics if `index > len`, like `Vec::insert`.
+        fn insert(&mut self, index: usize, element: Self::Item);
+
+        /// Insert all elements of an iterator at `index`, in order, shifting the elements after them only once.
+        fn insert_iter<I: IntoIterator<Item = Self::Item>>(&mut self, index: usize, elements: I);
+
+        /// Remove the elements in `start..end` with a single shift of the elements after them, and return them in order.
+        /// Panics if the range is out of bounds, like `Vec::drain`.
+        fn remove_range(&mut self, start: usize, end: usize) -> Vec<Self::Item>;
+
+        /// Shorten the storage to `len` elements, dropping the rest. Does nothing if the storage is not longer than that.
+        fn truncate(&mut self, len: usize);
+
+        /// Keep only the elements from `start` and to the end for which `f` returns `true`, calling `f` once for each of them, in order.
+        /// Removes the other elements with a single compaction.
+        fn retain_from<F: FnMut(&mut Self::Item) -> bool>(&mut self, start: usize, f: F);
+
+        /// Remove the elements from `start` and to the end for which `f` returns `true`, calling `f` once for each of them, in order.
+        /// Returns the removed elements in order. Removes them with a single compaction.
+        fn extract_from<F: FnMut(&mut Self::Item) -> bool>(
+            &mut self,
+            start: usize,
+            f: F,
+        ) -> Vec<Self::Item>;
+
+        /// Swap the elements at `a` and `b`. Panics if either is out of bounds, like `slice::swap`.
+        fn swap(&mut self, a: usize, b: usize);
+
+        /// Rotate the elements in `start..end` to the left, such that the element `mid` positions after `start` ends up at `start`.
+        /// Panics if `start > end`, `end > len` or `mid > end - start`, like `slice::rotate_left`.
+        fn rotate_range_left(&mut self, start: usize, end: usize, mid: usize);
+
+        /// Get the elements from `index` and to the end as a contiguous slice. Storages may only support this for
+        /// indices at or after the current handle index.
+        fn tail(&self, index: usize) -> Option<&[Self::Item]>;
+
+        /// Get the elements from `index` and to the end as a contiguous mutable slice. Storages may only support this for
+        /// indices at or after the current handle index.
+        fn tail_mut(&mut self, index: usize) -> Option<&mut [Self::Item]>;
+
+        /// Get a reference to the element at `index`, and the elements after it as a contiguous slice, or `None` if `index` is out
+        /// of bounds. Storages may only support this for indices at or after the current handle index.
+        fn split_at_index(&self, index: usize) -> Option<(&Self::Item, &[Self::Item])>;
+
+        /// Get a mutable reference to the element at `index`, and to the elements after it as a contiguous slice, at the same time.
+        /// Returns `None` like `split_at_index`.
+        fn split_at_index_mut(
+            &mut self,
+            index: usize,
+        ) -> Option<(&mut Self::Item, &mut [Self::Item])>;
+
+        /// Get mutable references to the element before `index`, and the element at `index`, at the same time.
+        /// Returns `None` if `index` is 0 or out of bounds.
+        fn split_prev_mut(&mut self, index: usize) -> Option<(&mut Self::Item, &mut Self::Item)>;
+
+        /// Get a reference to the element at `index`, without bounds checking.
+        ///
+        /// # Safety
+        /// `index` must be less than `len()`.
+        #[cfg(feature = "unchecked")]
+        unsafe fn get_unchecked(&self, index: usize) -> &Self::Item;
+
+        /// Get a mutable reference to the element at `index`, without bounds checking.
+        ///
+        /// # Safety
+        /// `index` must be less than `len()`.
+        #[cfg(feature = "unchecked")]
+        unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut Self::Item;
+
+        /// Get the elements from `index` and to the end like `tail`, without bounds checking where the storage supports it.
+        ///
+        /// # Safety
+        /// `index` must be at most `len()`, and supported by `tail`.
+        #[cfg(feature = "unchecked")]
+        unsafe fn tail_unchecked(&self, index: usize) -> &[Self::Item] {
+            self.tail(index).unwrap_or_default()
+        }
+
+        /// Get the elements from `index` and to the end like `tail_mut`, without bounds checking where the storage supports it.
+        ///
+        /// # Safety
+        /// `index` must be at most `len()`, and supported by `tail_mut`.
+        #[cfg(feature = "unchecked")]
+        unsafe fn tail_unchecked_mut(&mut self, index: usize) -> &mut [Self::Item] {
+            self.tail_mut(index).unwrap_or_default()
+        }
+    }
+
+    // The storage impls for `Vec`, which are generic over the allocator when the `allocator_api` feature is enabled.
+    macro_rules! impl_vec_storage {
+        ([$($params:tt)*] $vec:ty) => {
+            impl<$($params)*> sealed::Sealed for $vec {}
+
+            impl<$($params)*> HandleStorage for $vec {
+                type Item = T;
+
+                fn len(&self) -> usize {
+                    Vec::len(self)
+                }
+
+                fn get(&self, index: usize) -> Option<&T> {
+                    <[T]>::get(self, index)
+                }
+
+                fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+                    <[T]>::get_mut(self, index)
+                }
+
+                fn remove(&mut self, index: usize) -> Option<T> {
+                    (index < Vec::len(self)).then(|| Vec::remove(self, index))
+                }
+
+                fn insert(&mut self, index: usize, element: T) {
+                    Vec::insert(self, index, element);
+                }
+
+                fn insert_iter<I: IntoIterator<Item = T>>(&mut self, index: usize, elements: I) {
+                    self.splice(index..index, elements);
+                }
+
+                fn remove_range(&mut self, start: usize, end: usize) -> Vec<T> {
+                    self.drain(start..end).collect()
+                }
+
+                fn truncate(&mut self, len: usize) {
+                    Vec::truncate(self, len);
+                }
+
+                fn retain_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, mut f: F) {
+                    self.extract_if(start.., |t| !f(t)).for_each(drop);
+                }
+
+                fn extract_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, f: F) -> Vec<T> {
+                    self.extract_if(start.., f).collect()
+                }
+
+                fn swap(&mut self, a: usize, b: usize) {
+                    <[T]>::swap(self, a, b);
+                }
+
+                fn rotate_range_left(&mut self, start: usize, end: usize, mid: usize) {
+                    self[start..end].rotate_left(mid);
+                }
+
+                fn tail(&self, index: usize) -> Option<&[T]> {
+                    <[T]>::get(self, index..)
+                }
+
+                fn tail_mut(&mut self, index: usize) -> Option<&mut [T]> {
+                    <[T]>::get_mut(self, index..)
+                }
+
+                fn split_at_index(&self, index: usize) -> Option<(&T, &[T])> {
+                    <[T]>::get(self, index..)?.split_first()
+                }
+
+                fn split_at_index_mut(&mut self, index: usize) -> Option<(&mut T, &mut [T])> {
+                    <[T]>::get_mut(self, index..)?.split_first_mut()
+                }
+
+                fn split_prev_mut(&mut self, index: usize) -> Option<(&mut T, &mut T)> {
+                    let (prev, rest) = <[T]>::get_mut(self, index.checked_sub(1)?..)?.split_first_mut()?;
+                    Some((prev, rest.first_mut()?))
+                }
+
+                #[cfg(feature = "unchecked")]
+                unsafe fn get_unchecked(&self, index: usize) -> &T {
+                    // SAFETY: The caller guarantees `index < len`.
+                    unsafe { <[T]>::get_unchecked(self, index) }
+                }
+
+                #[cfg(feature = "unchecked")]
+                unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut T {
+                    // SAFETY: The caller guarantees `index < len`.
+                    unsafe { <[T]>::get_unchecked_mut(self, index) }
+                }
+
+                #[cfg(feature = "unchecked")]
+                unsafe fn tail_unchecked(&self, index: usize) -> &[T] {
+                    // SAFETY: The caller guarantees `index <= len`.
+                    unsafe { <[T]>::get_unchecked(self, index..) }
+                }
+
+                #[cfg(feature = "unchecked")]
+                unsafe fn tail_unchecked_mut(&mut self, index: usize) -> &mut [T] {
+                    // SAFETY: The caller guarantees `index <= len`.
+                    unsafe { <[T]>::get_unchecked_mut(self, index..) }
+                }
+            }
+        };
+    }
+
+    #[cfg(not(feature = "allocator_api"))]
+    impl_vec_storage!([T] Vec<T>);
+    #[cfg(feature = "allocator_api")]
+    impl_vec_storage!([T, A: std::alloc::Allocator] Vec<T, A>);
+
+    // While the current element is taken out of the vector (swapped with the last element, and removed), this removes its place for good
+    // if the value is never put back, i.e. when unwinding: the other elements are restored in order, and the iteration continues with
+    // the element after it. Forgotten when the value is put back.
+    struct ForgetCurrentOnUnwind<'h, 'a, 'b, T, S: HandleStorage<Item = T>> {
+        handle: &'h mut VecMutationHandle<'a, 'b, T, S>,
+    }
+
+    impl<T, S: HandleStorage<Item = T>> Drop for ForgetCurrentOnUnwind<'_, '_, '_, T, S> {
+        fn drop(&mut self) {
+            self.handle.forget_taken_current();
+        }
+    }
+
     // Contract:
     // `index < vec.len()`
     // `next_index >= index`
-    // `vec` may not be mutated at indices smaller than `index`
+    // `index < end <= vec.len()`, if the end of the pass is tracked
+    // `vec` may not be mutated at indices smaller than `index`, except the element at `index - 1` in place (see `prev_mut`)
     // as long as all internal methods respect and preserve these, all of them may assume these.
     /// Represents an index in a vector, allowing mutation of the vector with that index as a "context".
+    ///
+    /// The storage parameter `S` is a `Vec<T>` for all the usual runners, and only differs for alternative runners like
+    /// [`mutate_vec_by_handles_compacting`](crate::mutate_vec_by_handles_compacting).
     #[derive(Debug)]
-    pub struct VecMutationHandle<'a, 'b, T> {
-        vec: &'a mut Vec<T>,
-        index: usize,              // The current index. Should not be mutated.
+    pub struct VecMutationHandle<'a, 'b, T, S = Vec<T>>
+    where
+        S: HandleStorage<Item = T>,
+    {
+        vec: &'a mut S,
+        index: Position, // The current index. Only moved by `insert_before`.
         next_index: &'b mut usize, // The index for the next iteration. Mutated e.g. when element is removed, so none are skipped.
+        stopped: Option<&'b mut bool>, // Set when stopping the iteration. Without it, `next_index` is set to `usize::MAX` instead.
+        end: Option<&'b mut usize>, // The end of the pass, if tracked. Elements appended past it are not processed.
+        stride: usize, // The number of elements skipped per step of `skip_forward`. Only differs from 1 in strided passes.
+        summary: Option<&'b mut MutationSummary>, // Counts of the operations done, if the pass is summarized.
+        sink: Option<&'b mut Vec<T>>, // Receives the elements discarded with `discard_into_sink`, if the pass collects them.
+        skip_insertions: bool, // Whether `insert_and_process` skips the inserted elements, as set up by `HandlePass::skip_inserted`.
+        next_pinned: bool, // Set by `set_next_offset`. Insertions then no longer shift `next_index`.
+        observer: Option<Observer<'b>>, // Told about each operation as it happens, if the pass is observed.
+        inserted: Option<&'b mut InsertedPositions>, // The positions of the elements inserted during the pass, if tracked.
+        deferred: Option<&'b mut DeferredActions<S>>, // Actions to run after the pass, if the runner supports them.
+        windowed: bool, // Set in range passes, where the methods working on the rest of the vector stop at the end of the pass.
+    }
+
+    // The position of the current element. It is only created in `start`, after checking that it is within the pass and
+    // so within the storage, and only moved by `insert_before`, which grows the storage in front of it. The methods needing
+    // the current element go through it, so a broken contract is reported here instead of at each access.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Position(usize);
+
+    impl Position {
+        fn get(self) -> usize {
+            self.0
+        }
+
+        // Keep the position on the same element after inserting one before it.
+        fn shift_for_insertion_before(&mut self) {
+            self.0 += 1;
+        }
+
+        fn current<S: HandleStorage>(self, vec: &S) -> (&S::Item, &[S::Item]) {
+            let len = vec.len();
+            vec.split_at_index(self.0)
+                .unwrap_or_else(|| self.out_of_bounds(len))
+        }
+
+        fn current_mut<S: HandleStorage>(self, vec: &mut S) -> (&mut S::Item, &mut [S::Item]) {
+            let len = vec.len();
+            vec.split_at_index_mut(self.0)
+                .unwrap_or_else(|| self.out_of_bounds(len))
+        }
+
+        fn remove<S: HandleStorage>(self, vec: &mut S) -> S::Item {
+            let len = vec.len();
+            vec.remove(self.0)
+                .unwrap_or_else(|| self.out_of_bounds(len))
+        }
+
+        // Swap the element at the position with the last one, and remove it from the end.
+        fn take_out<S: HandleStorage>(self, vec: &mut S) -> S::Item {
+            let len = vec.len();
+            len.checked_sub(1)
+                .filter(|&last| self.0 <= last)
+                .and_then(|last| {
+                    vec.swap(self.0, last);
+                    vec.remove(last)
+                })
+                .unwrap_or_else(|| self.out_of_bounds(len))
+        }
+
+        #[cold]
+        #[track_caller]
+        fn out_of_bounds(self, len: usize) -> ! {
+            invariant_violated(format_args!(
+                "the current index {} is not below the vector length {len}",
+                self.0
+            ))
+        }
+    }
+
+    // Report a broken internal invariant. All such checks outside of `check_invariants` end up here, so there is a single place
+    // where the crate can panic on its own account.
+    #[cold]
+    #[track_caller]
+    pub(crate) fn invariant_violated(message: std::fmt::Arguments) -> ! {
+        panic!("handlevec invariant violated: {message}")
+    }
+
+    // The observer of a pass, wrapped so the handle can still derive `Debug`.
+    pub(crate) struct Observer<'b>(&'b mut dyn FnMut(HandleEvent));
+
+    impl std::fmt::Debug for Observer<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Observer")
+        }
+    }
+
+    // The sorted positions of the elements inserted during a pass, if tracked, kept on the same elements as they move, and of those
+    // among them which the iteration passes over when it reaches them.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub(crate) struct InsertedPositions {
+        all: Vec<usize>,
+        skipped: Vec<usize>, // Inserted with `insert_into_rest_sorted_and_skip_by`, after the next element at the time.
+        track_all: bool, // Whether `all` is kept, which shifts it on every insertion and removal before its positions.
+    }
+
+    impl InsertedPositions {
+        pub(crate) fn new() -> Self {
+            Self::default()
+        }
+
+        fn contains(&self, position: usize) -> bool {
+            self.all.binary_search(&position).is_ok()
+        }
+
+        // Keep the positions of all inserted elements, not only those to be skipped, for `was_inserted_this_pass`.
+        pub(crate) fn track_all(&mut self, track: bool) {
+            self.track_all = track;
+        }
+
+        pub(crate) fn tracks_all(&self) -> bool {
+            self.track_all
+        }
+
+        fn is_empty(&self) -> bool {
+            self.all.is_empty() && self.skipped.is_empty()
+        }
+
+        pub(crate) fn is_skipped(&self, position: usize) -> bool {
+            self.skipped.binary_search(&position).is_ok()
+        }
+
+        fn each_list(&mut self, mut f: impl FnMut(&mut Vec<usize>)) {
+            f(&mut self.all);
+            f(&mut self.skipped);
+        }
+
+        // Record `count` elements inserted at `position`.
+        fn insert(&mut self, position: usize, count: usize) {
+            self.each_list(|list| {
+                let first = list.partition_point(|&other| other < position);
+                for other in &mut list[first..] {
+                    *other += count;
+                }
+            });
+            if self.track_all {
+                let first = self.all.partition_point(|&other| other < position);
+                self.all.splice(first..first, position..position + count);
+            }
+        }
+
+        // Mark the element inserted at `position` to be passed over by the iteration.
+        fn skip(&mut self, position: usize) {
+            let first = self.skipped.partition_point(|&other| other < position);
+            self.skipped.insert(first, position);
+        }
+
+        // Record the removal of the elements in `start..end`.
+        fn remove(&mut self, start: usize, end: usize) {
+            self.each_list(|list| {
+                let first = list.partition_point(|&position| position < start);
+                let last = list.partition_point(|&position| position < end);
+                list.drain(first..last);
+                for position in &mut list[first..] {
+                    *position -= end - start;
+                }
+            });
+        }
+
+        // Record the swap of the elements at `a` and `b`.
+        fn swap(&mut self, a: usize, b: usize) {
+            self.each_list(|list| {
+                if list.binary_search(&a).is_ok() != list.binary_search(&b).is_ok() {
+                    for position in list.iter_mut() {
+                        if *position == a || *position == b {
+                            *position = a + b - *position;
+                        }
+                    }
+                    list.sort_unstable();
+                }
+            });
+        }
+
+        // Record the rotation of the `len` elements from `start` to the left by `mid`.
+        fn rotate_left(&mut self, start: usize, len: usize, mid: usize) {
+            self.each_list(|list| {
+                for position in list.iter_mut().filter(|position| **position >= start) {
+                    *position = start + (*position - start + len - mid) % len;
+                }
+                list.sort_unstable();
+            });
+        }
+
+        // Move the positions from `start` on to a new set, relative to `start`.
+        fn split_off(&mut self, start: usize) -> Self {
+            let split = |list: &mut Vec<usize>| {
+                let first = list.partition_point(|&position| position < start);
+                list.drain(first..)
+                    .map(|position| position - start)
+                    .collect()
+            };
+            InsertedPositions {
+                all: split(&mut self.all),
+                skipped: split(&mut self.skipped),
+                track_all: self.track_all,
+            }
+        }
+
+        // Put back the positions split off with `split_off`, all of which are after the ones still here.
+        fn append(&mut self, rest: Self, start: usize) {
+            self.all
+                .extend(rest.all.into_iter().map(|position| position + start));
+            self.skipped
+                .extend(rest.skipped.into_iter().map(|position| position + start));
+        }
+    }
+
+    /// The state of an iteration by handles: the index of the next element to process, and whether the iteration was stopped.
+    ///
+    /// Used with [`VecMutationHandle::new`] for the while-loop style. After the loop, it tells where the iteration ended,
+    /// and it can be reset (or kept) for another pass over the same vector.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct HandleState {
+        position: usize,
+        stopped: bool,
+    }
+
+    impl HandleState {
+        /// A state for iterating from the first element.
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// A state for iterating from the element at `position`.
+        #[must_use]
+        pub fn starting_at(position: usize) -> Self {
+            HandleState {
+                position,
+                stopped: false,
+            }
+        }
+
+        /// The index of the next element to be processed. After stopping the iteration, this is the index of the element that
+        /// would have been processed next.
+        #[must_use]
+        pub fn position(&self) -> usize {
+            self.position
+        }
+
+        /// Whether the iteration was stopped by a handle, e.g. with `stop_iteration`. No more handles are created while it is.
+        #[must_use]
+        pub fn is_stopped(&self) -> bool {
+            self.stopped
+        }
+
+        // Whether a handle is created at the position, for a pass ending at `end` in a vector of `len` elements, like in `start`.
+        pub(crate) fn visits(&self, end: usize, len: usize) -> bool {
+            !self.stopped && self.position < end.min(len)
+        }
+
+        /// Stop the iteration, so no more handles are created, like `VecMutationHandle::stop_iteration`.
+        pub fn stop(&mut self) {
+            self.stopped = true;
+        }
+
+        /// Start over from the first element, also if the iteration was stopped.
+        pub fn reset(&mut self) {
+            *self = Self::new();
+        }
+    }
+
+    // Puts the elements after the current one back in place after a nested pass, also if its closure panics.
+    struct DetachedRest<'s, S: HandleStorage> {
+        vec: &'s mut S,
+        start: usize,
+        rest: Vec<S::Item>,
+    }
+
+    impl<S: HandleStorage> Drop for DetachedRest<'_, S> {
+        fn drop(&mut self) {
+            self.vec
+                .insert_iter(self.start, std::mem::take(&mut self.rest));
+        }
     }
 
-    impl<'a, 'b, T> VecMutationHandle<'a, 'b, T> {
+    impl<'a, 'b, T, S: HandleStorage<Item = T>> VecMutationHandle<'a, 'b, T, S> {
         /// Creates a vector mutation handle, allowing mutation of a vector with a specific element (index) as a "context".
-        /// Mutates this index reference, so that it points to the next element in the vector that should be processed.
+        /// Mutates the state, so that it points to the next element in the vector that should be processed.
         ///
-        /// Provides `None` if index is less than vector length (iteration should be stopped).
-        /// In case the `index` is valid, `index` is always immediately overwritten with `index + 1`
-        /// (and a copy of the original value is used inside here), even if no methods are called on the handle.
-        /// Future methods may alter this index further. It may contain "junk" values like `usize::MAX` afterwards (in the case of stopping iteration).
-        /// Ideally, nothing other than this crate should depend on the value of the index reference.
+        /// Provides `None` if the position is not less than the vector length, or the iteration was stopped.
+        /// Otherwise, the position is always immediately advanced by one (and a copy of the original value is used inside here),
+        /// even if no methods are called on the handle. Future methods may alter the state further.
         #[must_use]
-        pub fn new(vec: &'a mut Vec<T>, index: &'b mut usize) -> Option<Self> {
+        pub fn new(vec: &'a mut S, state: &'b mut HandleState) -> Option<Self> {
+            Self::start(vec, &mut state.position, Some(&mut state.stopped), None)
+        }
+
+        /// Creates a vector mutation handle from a bare index, like `new`.
+        ///
+        /// Stopping the iteration sets the index to the sentinel `usize::MAX`, so it can neither be inspected nor reused afterwards.
+        #[deprecated(note = "use `VecMutationHandle::new` with a `HandleState` instead")]
+        #[must_use]
+        pub fn new_with_index(vec: &'a mut S, index: &'b mut usize) -> Option<Self> {
+            Self::start(vec, index, None, None)
+        }
+
+        /// Creates a vector mutation handle like `new`, but only for indices before `end`, and keeps `end` updated as elements
+        /// are inserted and removed before it. This is what the runners of this crate use.
+        ///
+        /// Elements appended with `append_and_skip` are placed after `end`, so they are not processed by this pass.
+        /// `end` should initially be the length of the vector (it is clamped to it).
+        #[must_use]
+        pub fn new_bounded(
+            vec: &'a mut S,
+            state: &'b mut HandleState,
+            end: &'b mut usize,
+        ) -> Option<Self> {
+            Self::start(
+                vec,
+                &mut state.position,
+                Some(&mut state.stopped),
+                Some(end),
+            )
+        }
+
+        fn start(
+            vec: &'a mut S,
+            index: &'b mut usize,
+            stopped: Option<&'b mut bool>,
+            mut end: Option<&'b mut usize>,
+        ) -> Option<Self> {
+            if let Some(end) = end.as_deref_mut() {
+                *end = (*end).min(vec.len());
+            }
+            let pass_end = end.as_deref().map_or(vec.len(), |end| *end);
             let curr_index: usize = *index;
-            if curr_index < vec.len() {
+            if curr_index < pass_end && !stopped.as_deref().is_some_and(|stopped| *stopped) {
                 *index = curr_index + 1;
-                Some(VecMutationHandle {
+                let handle = VecMutationHandle {
                     vec,
-                    index: curr_index,
+                    index: Position(curr_index),
                     next_index: index,
-                })
+                    stopped,
+                    end,
+                    stride: 1,
+                    summary: None,
+                    sink: None,
+                    skip_insertions: false,
+                    next_pinned: false,
+                    observer: None,
+                    inserted: None,
+                    deferred: None,
+                    windowed: false,
+                };
+                handle.check_invariants("new");
+                Some(handle)
             } else {
                 None
             }
         }
 
         /// Get a reference to the current element.
-        /// # Panics
-        /// Might panic in case of a bug in this crate, due to a potentially invalid index.
         #[must_use]
         pub fn get(&self) -> &T {
-            self.vec.get(self.index).unwrap() // From the new method, we are always within bounds. The discard method consumes ownership. This is ok.
+            self.check_invariants("get");
+            self.current()
         }
 
         /// Get a mutable reference to the current element.
-        /// # Panics
-        /// Might panic in case of a bug in this crate, due to a potentially invalid index.
         #[must_use]
         pub fn get_mut(&mut self) -> &mut T {
-            self.vec.get_mut(self.index).unwrap() // From the new method, we are always within bounds. The discard method consumes ownership. This is ok.
+            self.check_invariants("get_mut");
+            self.current_mut()
+        }
+
+        /// Get a mutable reference to the element right before the current one, or `None` for the first element of the vector.
+        /// For merging backward: the previous element can only be changed in place, not removed or moved.
+        #[must_use]
+        pub fn prev_mut(&mut self) -> Option<&mut T> {
+            self.check_invariants("prev_mut");
+            let prev = self.index.get().checked_sub(1)?;
+            self.vec.get_mut(prev)
+        }
+
+        /// Get mutable references to the element right before the current one and the current element, at the same time,
+        /// or `None` for the first element of the vector. See `prev_mut`.
+        #[must_use]
+        pub fn split_prev_current_mut(&mut self) -> Option<(&mut T, &mut T)> {
+            self.check_invariants("split_prev_current_mut");
+            self.vec.split_prev_mut(self.index.get())
         }
 
         #[allow(clippy::must_use_candidate)]
         /// Remove the current element, and return it as owned.
         /// Consumes self, as the contract is now invalid (index could be larger than or equal to vec length, especially if we repeat discarding.)
-        pub fn discard(self) -> T {
-            *self.next_index -= 1;
-            self.vec.remove(self.index)
+        pub fn discard(mut self) -> T {
+            self.check_invariants("discard");
+            self.remove_current()
+        }
+
+        /// Remove the current element, and push it onto `dest`, e.g. to sort elements into buckets during a pass.
+        /// See [`route_vec_by_handles`](crate::route_vec_by_handles) for moving many elements at once.
+        pub fn move_current_to(mut self, dest: &mut Vec<T>) {
+            self.check_invariants("move_current_to");
+            dest.push(self.remove_current());
+        }
+
+        /// Remove the current element, and hand it to the runner, if it collects discarded elements
+        /// (see [`mutate_vec_by_handles_collecting`](crate::mutate_vec_by_handles_collecting)). Otherwise, it is dropped.
+        pub fn discard_into_sink(mut self) {
+            self.check_invariants("discard_into_sink");
+            let current = self.remove_current();
+            self.push_to_sink(current);
+        }
+
+        /// Remove the current element like `discard_into_sink`, and do not process any more elements.
+        pub fn discard_into_sink_and_stop_iteration(mut self) {
+            self.check_invariants("discard_into_sink_and_stop_iteration");
+            let current = self.remove_current();
+            self.stop();
+            self.push_to_sink(current);
+        }
+
+        fn push_to_sink(&mut self, t: T) {
+            if let Some(sink) = self.sink.as_deref_mut() {
+                sink.push(t);
+            }
         }
 
         /// Insert a new element AFTER the current one, and process it in the next iteration (specifically, do not shift the index to ignore this element).
         pub fn insert_and_process(&mut self, t: T) {
-            // This looks weird, accessing index + 1. But insert allows the length as an index, in that case inserting after all other elements.
-            self.vec.insert(self.index + 1, t);
+            self.check_invariants("insert_and_process");
+            self.insert_after(t, !self.skip_insertions);
+            if self.skip_insertions {
+                self.skip_inserted(1);
+            }
+            self.check_invariants("insert_and_process");
         }
 
-        /// Skip a certain amount of the next elements.
-        pub fn skip_forward(&mut self, steps_to_skip: usize) {
-            *self.next_index += steps_to_skip;
+        /// Insert each element of an iterator AFTER the current one, in the same order as the iterator. Process the inserted elements afterwards.
+        pub fn insert_and_process_iter(&mut self, iter: impl IntoIterator<Item = T>) {
+            self.check_invariants("insert_and_process_iter");
+            let count = self.insert_iter_after(iter, !self.skip_insertions);
+            if self.skip_insertions {
+                self.skip_inserted(count);
+            }
+            self.check_invariants("insert_and_process_iter");
         }
 
-        /// Do not process any more elements (equivalent to `skip_forward` more elements than remain in the vector)
-        /// Please note, this does not affect the call-site like the `break` keyword. This method does return, and executation continues from the call-site.
-        /// The index reference is set to `usize::MAX` to achieve this.
-        pub fn stop_iteration(self) {
-            *self.next_index = usize::MAX; // If your vector is larger than usize::MAX, then you have another problem anyway...
+        /// Insert a new element AFTER the current one, but do not process it in the next iteration (specifically, shift the index as to ignore this element).
+        pub fn insert_and_skip(&mut self, t: T) {
+            self.check_invariants("insert_and_skip");
+            self.insert_after(t, false);
+            self.skip_inserted(1);
+            self.check_invariants("insert_and_skip");
         }
 
-        /// Discards the current element, and returns it as owned. Does not process any more elements.
-        /// Both the `discard` and `stop_iteration` methods consume ownership of the handle, so this is provided if you want to do both.
-        #[allow(clippy::must_use_candidate)]
-        pub fn discard_and_stop_iteration(self) -> T {
-            *self.next_index = usize::MAX;
-            self.vec.remove(self.index)
+        /// Insert each element of an iterator AFTER the current one, in the same order as the iterator. Do not process the inserted elements afterwards.
+        ///
+        /// The elements are counted while they are inserted, and the index is shifted past all of them afterwards.
+        pub fn insert_and_skip_iter(&mut self, iter: impl IntoIterator<Item = T>) {
+            self.check_invariants("insert_and_skip_iter");
+            let count = self.insert_iter_after(iter, false);
+            self.skip_inserted(count);
+            self.check_invariants("insert_and_skip_iter");
         }
 
-        /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
-        #[must_use]
-        pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
-        where
-            I: SliceIndex<[T]>,
-        {
-            self.vec.get(self.index..)?.get(slice)
+        /// Insert a new element BEFORE the current one. It is not processed, as the iteration has already passed it.
+        /// The handle stays on the current element, which moves one place forward, along with the rest of the iteration.
+        pub fn insert_before(&mut self, t: T) {
+            self.check_invariants("insert_before");
+            self.vec.insert(self.index.get(), t);
+            self.track_insertion(self.index.get(), 1, false);
+            self.index.shift_for_insertion_before();
+            // The next element moves too, also when pinned by `set_next_offset`.
+            *self.next_index = self.next_index.saturating_add(1);
+            self.check_invariants("insert_before");
         }
 
-        /// "Peek" a mutable reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
-        #[must_use]
-        pub fn peek_forward_slice_mut<I>(&mut self, slice: I) -> Option<&mut I::Output>
-        where
-            I: SliceIndex<[T]>,
-        {
-            self.vec.get_mut(self.index..)?.get_mut(slice)
+        // `processed` tells observers whether the elements are inserted to be processed, see `HandleEvent::Inserted`.
+        fn insert_after(&mut self, t: T, processed: bool) {
+            // This looks weird, accessing index + 1. But insert allows the length as an index, in that case inserting after all other elements.
+            self.vec.insert(self.index.get() + 1, t);
+            self.track_insertion(self.index.get() + 1, 1, processed);
         }
-    }
-}
 
-impl<'a, 'b, T> VecMutationHandle<'a, 'b, T> {
-    /// Insert a new element AFTER the current one, but do not process it in the next iteration (specifically, shift the index as to ignore this element).
-    pub fn insert_and_skip(&mut self, t: T) {
-        self.insert_and_process(t);
-        self.skip_forward(1);
-    }
+        // Returns the number of inserted elements.
+        fn insert_iter_after(
+            &mut self,
+            iter: impl IntoIterator<Item = T>,
+            processed: bool,
+        ) -> usize {
+            // A single splice, so the remainder of the vector is only shifted once.
+            let len = self.vec.len();
+            self.vec.insert_iter(self.index.get() + 1, iter);
+            let count = self.vec.len() - len;
+            self.track_insertion(self.index.get() + 1, count, processed);
+            count
+        }
 
-    /// Assign a new value to this element.
-    pub fn set(&mut self, t: T) {
-        *self.get_mut() = t;
-    }
+        /// Push an element onto the end of the vector, and do not process it in this pass.
+        ///
+        /// Only handles created by the runners of this crate (or by `new_bounded`) know where the pass ends.
+        /// Handles created with `new` will process appended elements like any other.
+        pub fn append_and_skip(&mut self, t: T) {
+            self.check_invariants("append_and_skip");
+            self.push_after_pass(t);
+            self.check_invariants("append_and_skip");
+        }
 
-    /// Replace the current element with another, and get ownership of the value currently there.
-    pub fn replace(&mut self, t: T) -> T {
-        let curr = self.get_mut();
-        std::mem::replace(curr, t)
-    }
+        /// Push each element of a vec onto the end of the vector, in order, and do not process them in this pass.
+        pub fn append_and_skip_vec(&mut self, vec: Vec<T>) {
+            self.check_invariants("append_and_skip_vec");
+            let count = vec.len();
+            let position = self.rest_end();
+            self.vec.insert_iter(position, vec);
+            self.record(|summary| summary.inserted += count);
+            self.notify_insertion(position, count, false);
+            self.check_invariants("append_and_skip_vec");
+        }
 
-    /// Insert each element in a vec, ordering the elements with the same order as the vec. Process the vector elements afterwards.
-    pub fn insert_and_process_vec(&mut self, vec: Vec<T>) {
-        // Reversed for preserving correct order
-        for t in vec.into_iter().rev() {
-            self.insert_and_process(t);
+        /// Append an element to the end of the pass, and process it when the iteration reaches it.
+        ///
+        /// Elements appended with `append_and_skip` are not part of the pass, so they stay after elements appended with this method.
+        /// Without such elements, this pushes onto the end of the vector.
+        pub fn append_and_process(&mut self, t: T) {
+            self.check_invariants("append_and_process");
+            self.push_to_pass(t);
+            self.check_invariants("append_and_process");
         }
-    }
 
-    /// Insert each element in a vec, ordering the elements with the same order as the vec. Do not process the vector elements afterwards.
-    pub fn insert_and_skip_vec(&mut self, vec: Vec<T>) {
-        let steps_to_skip = vec.len();
-        self.insert_and_process_vec(vec);
-        self.skip_forward(steps_to_skip);
-    }
-}
+        /// Append each element of a vec to the end of the pass, in order, and process them when the iteration reaches them.
+        /// See `append_and_process`.
+        pub fn append_and_process_vec(&mut self, vec: Vec<T>) {
+            self.check_invariants("append_and_process_vec");
+            let position = self.pass_end();
+            let count = vec.len();
+            self.vec.insert_iter(position, vec);
+            self.track_insertion(position, count, true);
+            self.check_invariants("append_and_process_vec");
+        }
 
-/// Mutate a vec using index-style looping, but without thinking about the indices.
-///
-/// See crate documentation for examples and more context.
-pub fn mutate_vec_by_handles<T>(vec: &mut Vec<T>, mut op: impl FnMut(VecMutationHandle<T>)) {
-    let mut curr_index = 0;
+        /// Move the current element to the end of the pass, and continue the iteration with the element after it.
+        /// The deferred element is processed again when the iteration reaches it, like with `append_and_process`.
+        ///
+        /// Note that if the closure defers every element, it keeps processing the deferred elements forever.
+        pub fn defer_current(mut self) {
+            self.check_invariants("defer_current");
+            let current = self.remove_current();
+            self.push_to_pass(current);
+        }
 
-    while let Some(handle) = VecMutationHandle::new(vec, &mut curr_index) {
-        op(handle);
-    }
-}
+        /// Move the current element to the end of the vector, and continue the iteration with the element after it.
+        /// The deferred element is not processed again in this pass, like with `append_and_skip`.
+        pub fn defer_current_and_skip(mut self) {
+            self.check_invariants("defer_current_and_skip");
+            let current = self.remove_current();
+            self.push_after_pass(current);
+        }
 
-/// Trait for adding vector mutation by handles as an extension trait to vec.
-pub trait VecMutateByHandles<T>: Sized {
-    /// Mutate a vec using index-style looping, but without thinking about the indices.
-    ///
-    /// See crate documentation for examples and more context.
-    fn mutate_vec_by_handles(&mut self, op: impl FnMut(VecMutationHandle<T>));
-}
+        // Insert an element at the end of the pass. Does not rely on the current index, so it may be used after removing the current element.
+        fn push_to_pass(&mut self, t: T) {
+            let position = self.pass_end();
+            self.vec.insert(position, t);
+            self.track_insertion(position, 1, true);
+        }
 
-impl<T> VecMutateByHandles<T> for Vec<T> {
-    fn mutate_vec_by_handles(&mut self, op: impl FnMut(VecMutationHandle<T>)) {
-        mutate_vec_by_handles(self, op);
-    }
-}
+        // Insert an element at the end of the vector, outside of the pass, or right after the pass in range passes.
+        // Does not rely on the current index, like `push_to_pass`.
+        fn push_after_pass(&mut self, t: T) {
+            let position = self.rest_end();
+            self.vec.insert(position, t);
+            self.record(|summary| summary.inserted += 1);
+            self.notify_insertion(position, 1, false);
+        }
+
+        // Remove the current element, continuing with the element after it. Afterwards `index` might not be valid anymore.
+        fn remove_current(&mut self) -> T {
+            self.track_current_removal();
+            self.index.remove(self.vec)
+        }
+
+        // Keep the index for the next iteration on the same element, when the current element is removed.
+        // After `reprocess_current`, the element taking the place of the current one is processed next.
+        fn track_current_removal(&mut self) {
+            *self.next_index = self.next_index.saturating_sub(1).max(self.index.get());
+            self.track_removal(self.index.get(), self.index.get() + 1);
+        }
+
+        /// Replace the current element with `f` applied to it, by value. This works without `Clone` or `Default`, and costs O(1).
+        ///
+        /// If `f` panics, the current element is lost (it was moved into `f`), but all other elements are left in the vector, in order,
+        /// and the state of the iteration points to the element after it.
+        pub fn replace_with(&mut self, f: impl FnOnce(T) -> T) {
+            self.check_invariants("replace_with");
+            self.try_replace_with(|t| Ok(f(t)));
+            self.check_invariants("replace_with");
+        }
+
+        /// Replace the current element with the result of `f` applied to it, by value. If `f` returns `Err`, the value in it is put
+        /// back instead, which should usually be the original value. Returns whether `f` returned `Ok`.
+        ///
+        /// If `f` panics, the current element is lost (it was moved into `f`), but all other elements are left in the vector, in order,
+        /// and the state of the iteration points to the element after it.
+        pub fn try_replace_with(&mut self, f: impl FnOnce(T) -> Result<T, T>) -> bool {
+            self.check_invariants("try_replace_with");
+            let current = self.take_out_current();
+
+            let guard = ForgetCurrentOnUnwind { handle: self };
+            let (value, replaced) = match f(current) {
+                Ok(value) => (value, true),
+                Err(value) => (value, false),
+            };
+            std::mem::forget(guard);
+
+            self.put_back_current(value);
+            if replaced {
+                self.track_replacement();
+            }
+            replaced
+        }
+
+        // Take the current element out of the vector in O(1), by swapping it with the last element and removing it.
+        // Until it is put back with `put_back_current` (or `forget_taken_current` is called), `index` might not be valid,
+        // and no other methods may be called.
+        pub(crate) fn take_out_current(&mut self) -> T {
+            self.index.take_out(self.vec)
+        }
+
+        // Put a value in the place of the element taken out with `take_out_current`.
+        pub(crate) fn put_back_current(&mut self, value: T) {
+            self.vec.insert(self.vec.len(), value);
+            self.swap_with_last();
+        }
+
+        // Remove the place of the element taken out with `take_out_current` for good, like `discard`.
+        pub(crate) fn forget_taken_current(&mut self) {
+            if let Some(previously_last) = self.vec.remove(self.index.get()) {
+                self.vec.insert(self.vec.len(), previously_last);
+            }
+            self.track_current_removal();
+        }
+
+        fn swap_with_last(&mut self) {
+            let last = self.vec.len() - 1;
+            self.vec.swap(self.index.get(), last);
+        }
+
+        /// Remove the current element, and put the pieces `f` splits it into in its place, in order.
+        /// The pieces are processed next. Any skipping done earlier on this handle is overridden, so elements inserted with
+        /// `insert_and_skip` before calling this are processed after the pieces.
+        /// If `f` returns no pieces, this is equivalent to `discard`.
+        pub fn split_current(mut self, f: impl FnOnce(T) -> Vec<T>) {
+            self.check_invariants("split_current");
+            // Removed first, so if `f` panics, the iteration continues with the element after the current one.
+            let current = self.remove_current();
+            let pieces = f(current);
+            self.track_insertion(self.index.get(), pieces.len(), true);
+            self.vec.insert_iter(self.index.get(), pieces);
+            *self.next_index = self.index.get();
+        }
+
+        /// Remove the current element, and put the pieces `f` splits it into in its place, in order.
+        /// The pieces are not processed, and any skipping done earlier on this handle is kept.
+        /// If `f` returns no pieces, this is equivalent to `discard`.
+        pub fn split_current_and_skip(mut self, f: impl FnOnce(T) -> Vec<T>) {
+            self.check_invariants("split_current_and_skip");
+            // Removed first, so if `f` panics, the iteration continues with the element after the current one.
+            let current = self.remove_current();
+            let pieces = f(current);
+            self.track_insertion(self.index.get(), pieces.len(), false);
+            // The pieces take the place of the current element, and are skipped along with whatever was skipped before.
+            self.skip_inserted(pieces.len());
+            self.vec.insert_iter(self.index.get(), pieces);
+        }
+
+        /// Merge the current element with the next one, by removing both from the vector, and putting `f(current, next)` in the place of the current element.
+        /// The merged element is still the current element, so it can be merged again with the element that now follows it.
+        ///
+        /// Returns `false`, and does nothing, if the current element is the last one.
+        /// Note that this moves the remainder of the vector a few times, similar to a `discard` and an insertion.
+        pub fn merge_with_next(&mut self, f: impl FnOnce(T, T) -> T) -> bool {
+            self.check_invariants("merge_with_next");
+            let Some(next) = self.remove_forward(self.index.get() + 1) else {
+                return false;
+            };
+            let current = self.take_out_current();
+            let guard = ForgetCurrentOnUnwind { handle: self };
+            let merged = f(current, next);
+            std::mem::forget(guard);
+            self.put_back_current(merged);
+            true
+        }
+
+        /// Remove the maximal run of elements right after the current one for which `pred` returns `true`, and return them in order.
+        /// The iteration continues with the first element after the run, or whatever it would have continued with, if that was later.
+        ///
+        /// The run is removed at once, so the remainder of the vector is only moved once, instead of once per element like with
+        /// repeated `take_forward(1)`.
+        pub fn discard_forward_while(&mut self, pred: impl FnMut(&T) -> bool) -> Vec<T> {
+            self.check_invariants("discard_forward_while");
+            let start = self.index.get() + 1;
+            let end = start + self.forward_run_len(pred);
+            self.track_forward_removal(start, end);
+            self.vec.remove_range(start, end)
+        }
+
+        /// Remove the current element, and the maximal run of elements right after it for which `pred` returns `true`, and return
+        /// them in order, the current element first. The iteration continues with the first element after the run.
+        /// Like `discard_forward_while`, the elements are removed at once.
+        #[allow(clippy::must_use_candidate)]
+        pub fn discard_current_and_forward_while(mut self, pred: impl FnMut(&T) -> bool) -> Vec<T> {
+            self.check_invariants("discard_current_and_forward_while");
+            let end = self.index.get() + 1 + self.forward_run_len(pred);
+            self.track_forward_removal(self.index.get() + 1, end);
+            self.track_current_removal();
+            self.vec.remove_range(self.index.get(), end)
+        }
+
+        // The number of elements right after the current one for which `pred` returns `true`, up to the first one for which it doesn't.
+        fn forward_run_len(&self, mut pred: impl FnMut(&T) -> bool) -> usize {
+            self.rest_from(self.index.get() + 1)
+                .iter()
+                .take_while(|t| pred(t))
+                .count()
+        }
+
+        /// Remove up to `n` elements immediately after the current one, and return them in order.
+        /// If fewer than `n` elements follow the current one, all of them are returned.
+        /// The iteration continues with whatever follows the current element afterwards.
+        pub fn take_forward(&mut self, n: usize) -> Vec<T> {
+            self.check_invariants("take_forward");
+            let start = self.index.get() + 1;
+            let end = start.saturating_add(n).min(self.rest_end());
+            self.track_forward_removal(start, end);
+            self.vec.remove_range(start, end)
+        }
+
+        /// Remove the element `offset` positions after the current one, and return it, or `None` if there is no such element.
+        /// The handle stays on the current element, and the iteration continues with the element it would have continued with,
+        /// or the one after it, if that was the one removed.
+        pub fn discard_forward_at(&mut self, offset: NonZeroUsize) -> Option<T> {
+            self.check_invariants("discard_forward_at");
+            let position = self.index.get().checked_add(offset.get())?;
+            self.remove_forward(position)
+        }
+
+        /// Replace the element `offset` positions after the current one, and return the value that was there, or `None`, and drop
+        /// `t`, if there is no such element. The iteration is not affected.
+        pub fn replace_forward_at(&mut self, offset: NonZeroUsize, t: T) -> Option<T> {
+            self.check_invariants("replace_forward_at");
+            let position = self
+                .index
+                .get()
+                .checked_add(offset.get())
+                .filter(|&position| position < self.rest_end())?;
+            let old = std::mem::replace(self.vec.get_mut(position)?, t);
+            self.record(|summary| summary.set_or_replaced += 1);
+            self.notify(HandleEvent::Replaced { index: position });
+            Some(old)
+        }
+
+        /// Remove the current element and all elements after it, and return them in order. Does not process any more elements.
+        #[allow(clippy::must_use_candidate)]
+        pub fn discard_rest(mut self) -> Vec<T> {
+            self.check_invariants("discard_rest");
+            *self.next_index = self.index.get();
+            self.stop();
+            let end = self.rest_end();
+            self.track_removal(self.index.get(), end);
+            self.vec.remove_range(self.index.get(), end)
+        }
+
+        /// Remove all elements after the current one, and return them in order. The current element is kept.
+        /// As no elements remain after the current one, no more elements are processed.
+        #[allow(clippy::must_use_candidate)]
+        pub fn split_off_rest(mut self) -> Vec<T> {
+            self.check_invariants("split_off_rest");
+            let end = self.rest_end();
+            self.track_removal(self.index.get() + 1, end);
+            self.vec.remove_range(self.index.get() + 1, end)
+        }
+
+        /// Replace all elements after the current one with `new_tail`, and return the removed elements in order.
+        /// The iteration continues with the first element of `new_tail`, also if elements were skipped on this handle.
+        ///
+        /// Moves each old and new element once, with no per-element insertion.
+        pub fn replace_rest(&mut self, new_tail: Vec<T>) -> Vec<T> {
+            self.check_invariants("replace_rest");
+            let old_tail = self.swap_rest(new_tail, true);
+            *self.next_index = self.index.get() + 1;
+            self.check_invariants("replace_rest");
+            old_tail
+        }
+
+        /// Replace all elements after the current one with `new_tail` like `replace_rest`, and return the removed elements in order.
+        /// Does not process any more elements, so the new elements are not processed either.
+        #[allow(clippy::must_use_candidate)]
+        pub fn replace_rest_and_stop(mut self, new_tail: Vec<T>) -> Vec<T> {
+            self.check_invariants("replace_rest_and_stop");
+            let old_tail = self.swap_rest(new_tail, false);
+            self.stop();
+            old_tail
+        }
+
+        fn swap_rest(&mut self, new_tail: Vec<T>, processed: bool) -> Vec<T> {
+            let start = self.index.get() + 1;
+            let end = self.rest_end();
+            self.track_removal(start, end);
+            let old_tail = self.vec.remove_range(start, end);
+            self.track_insertion(start, new_tail.len(), processed);
+            self.vec.insert_iter(start, new_tail);
+            old_tail
+        }
+
+        /// Drop every element more than `n` positions after the current one, keeping the current element and the `n` elements after it.
+        /// Does nothing if there are not more than `n` elements after the current one.
+        ///
+        /// Any skipping past the new end of the vector simply ends the iteration.
+        pub fn truncate_after(&mut self, n: usize) {
+            self.check_invariants("truncate_after");
+            let end = self.rest_end();
+            let new_end = (self.index.get() + 1).saturating_add(n).min(end);
+            self.track_removal(new_end, end);
+            self.drop_range(new_end, end);
+            *self.next_index = (*self.next_index).min(new_end);
+            self.check_invariants("truncate_after");
+        }
+
+        /// Keep only the elements after the current one for which `pred` returns `true`, removing the others in a single pass.
+        /// Returns the number of removed elements.
+        ///
+        /// If elements were skipped on this handle, the iteration continues with the same element as before, or the first kept
+        /// element after it, if it was removed.
+        pub fn retain_rest(&mut self, mut pred: impl FnMut(&T) -> bool) -> usize {
+            self.check_invariants("retain_rest");
+            self.retain_rest_mut(|t| pred(t))
+        }
+
+        /// Keep only the elements after the current one for which `pred` returns `true`, removing the others in a single pass.
+        /// Like `retain_rest`, but `pred` may also mutate the elements.
+        /// Returns the number of removed elements.
+        pub fn retain_rest_mut(&mut self, mut pred: impl FnMut(&mut T) -> bool) -> usize {
+            self.check_invariants("retain_rest_mut");
+            let ((), removed) = self.remove_rest_where(
+                |t| !pred(t),
+                |vec, start, remove| vec.retain_from(start, |t| !remove(t)),
+            );
+            removed
+        }
+
+        /// Remove the elements after the current one for which `pred` returns `true`, in a single pass, and return them in order.
+        /// The current element is never given to `pred`.
+        ///
+        /// Like `retain_rest`, the iteration continues with the same element as before, or the first element after it which is
+        /// not removed.
+        pub fn drain_rest_matching(&mut self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+            self.check_invariants("drain_rest_matching");
+            let (drained, _) = self.remove_rest_where(
+                |t| pred(t),
+                |vec, start, remove| vec.extract_from(start, remove),
+            );
+            self.check_invariants("drain_rest_matching");
+            drained
+        }
+
+        // Removes the elements after the current one for which `remove` returns `true`, using `apply` on the storage with the start of the
+        // elements after the current one. Keeps the state on the same elements, and returns the result of `apply` and the number of
+        // removed elements.
+        fn remove_rest_where<R>(
+            &mut self,
+            mut remove: impl FnMut(&mut T) -> bool,
+            apply: impl FnOnce(&mut S, usize, &mut dyn FnMut(&mut T) -> bool) -> R,
+        ) -> (R, usize) {
+            let start = self.index.get() + 1;
+            let next_index = *self.next_index;
+            let pass_end = self.pass_end();
+            let rest_end = self.rest_end();
+            let mut position = start;
+            let mut removed = 0;
+            let mut removed_before_next = 0;
+            let mut removed_before_end = 0;
+            // Only collected for observers and provenance, with the positions the elements had before any of them were removed.
+            let mut removed_positions = Vec::new();
+            let observed = self.observer.is_some()
+                || self
+                    .inserted
+                    .as_deref()
+                    .is_some_and(|inserted| !inserted.is_empty());
+
+            let result = apply(self.vec, start, &mut |t| {
+                let is_removed = position < rest_end && remove(t);
+                if is_removed {
+                    removed += 1;
+                    if observed {
+                        removed_positions.push(position);
+                    }
+                    if position < next_index {
+                        removed_before_next += 1;
+                    }
+                    if position < pass_end {
+                        removed_before_end += 1;
+                    }
+                }
+                position += 1;
+                is_removed
+            });
+
+            *self.next_index -= removed_before_next;
+            if let Some(end) = self.end.as_deref_mut() {
+                *end -= removed_before_end;
+            }
+            self.record(|summary| summary.discarded += removed);
+            // Each earlier removal shifts the later elements down by one, so the live index of each is one less per removal before it.
+            for (earlier, position) in removed_positions.into_iter().enumerate() {
+                self.notify_removal(position - earlier, position - earlier + 1);
+            }
+            (result, removed)
+        }
+
+        /// Insert an element into the elements after the current one, which are assumed to be sorted by `compare`, after any equal ones.
+        /// Returns the position it was inserted at, with 0 being the current element, like `peek_forward_slice`.
+        /// It is processed when the iteration reaches it, unless it lands among elements skipped on this handle.
+        pub fn insert_into_rest_sorted_by(
+            &mut self,
+            t: T,
+            compare: impl FnMut(&T, &T) -> Ordering,
+        ) -> usize {
+            self.check_invariants("insert_into_rest_sorted_by");
+            self.insert_into_rest_sorted_with(t, compare, true)
+        }
+
+        /// Insert an element into the sorted elements after the current one like `insert_into_rest_sorted_by`, but without processing it
+        /// in this pass. Handles created with `new` or `new_bounded` only skip it if it lands at or before the next element.
+        pub fn insert_into_rest_sorted_and_skip_by(
+            &mut self,
+            t: T,
+            compare: impl FnMut(&T, &T) -> Ordering,
+        ) -> usize {
+            self.check_invariants("insert_into_rest_sorted_and_skip_by");
+            self.insert_into_rest_sorted_with(t, compare, false)
+        }
+
+        fn insert_into_rest_sorted_with(
+            &mut self,
+            t: T,
+            mut compare: impl FnMut(&T, &T) -> Ordering,
+            process: bool,
+        ) -> usize {
+            let offset = 1 + self
+                .rest_from(self.index.get() + 1)
+                .partition_point(|other| compare(other, &t) != Ordering::Greater);
+            let position = self.index.get() + offset;
+            self.vec.insert(position, t);
+            let processed = process && position >= *self.next_index;
+            // Right at the next element, skipping it is the same as for `insert_and_skip`.
+            if position < *self.next_index || (!process && position == *self.next_index) {
+                self.skip_inserted(1);
+            }
+            self.track_insertion(position, 1, processed);
+            if !process && position > *self.next_index {
+                if let Some(inserted) = self.inserted.as_deref_mut() {
+                    inserted.skip(position);
+                }
+            }
+            offset
+        }
+
+        // Remove an element after the current one, keeping the index for the next iteration on the same element.
+        // Requires `position > self.index.get()`. Returns `None`, without changing anything, if there is no element at `position`.
+        fn remove_forward(&mut self, position: usize) -> Option<T> {
+            if position >= self.vec.len() {
+                return None;
+            }
+            self.track_forward_removal(position, position + 1);
+            self.vec.remove(position)
+        }
+
+        // Keep the index for the next iteration and the end of the pass on the same elements, when the elements in `start..end` are removed.
+        // Requires `start > self.index.get()`.
+        fn track_forward_removal(&mut self, start: usize, end: usize) {
+            *self.next_index -= end.min(*self.next_index).saturating_sub(start);
+            self.track_removal(start, end);
+        }
+
+        // Keep the end of the pass on the same element, when the elements in `start..end` are removed.
+        // Called right before the removal, while the elements are still in place.
+        fn track_removal(&mut self, start: usize, end: usize) {
+            self.record(|summary| summary.discarded += end.saturating_sub(start));
+            if let Some(pass_end) = self.end.as_deref_mut() {
+                *pass_end -= end.min(*pass_end).saturating_sub(start);
+            }
+            self.notify_removal(start, end);
+        }
+
+        fn notify_removal(&mut self, start: usize, end: usize) {
+            if let Some(inserted) = self.inserted.as_deref_mut() {
+                inserted.remove(start, end);
+            }
+            // The elements are removed together, so each of them is at `start` when it goes.
+            for _ in start..end {
+                self.notify(HandleEvent::Discarded { index: start });
+            }
+        }
+
+        // Keep the end of the pass on the same element, when `count` elements are inserted at `position`.
+        // Insertions right at the end of the pass are part of the pass.
+        fn track_insertion(&mut self, position: usize, count: usize, processed: bool) {
+            self.record(|summary| summary.inserted += count);
+            if let Some(pass_end) = self.end.as_deref_mut() {
+                if position <= *pass_end {
+                    *pass_end += count;
+                }
+            }
+            self.notify_insertion(position, count, processed);
+        }
+
+        fn notify_insertion(&mut self, position: usize, count: usize, processed: bool) {
+            if let Some(inserted) = self.inserted.as_deref_mut() {
+                inserted.insert(position, count);
+            }
+            for index in position..position + count {
+                self.notify(HandleEvent::Inserted { index, processed });
+            }
+        }
+
+        // Count a new value for the current element, given by `set`, `replace` or `replace_with`.
+        pub(crate) fn track_replacement(&mut self) {
+            self.record(|summary| summary.set_or_replaced += 1);
+            self.notify(HandleEvent::Replaced {
+                index: self.index.get(),
+            });
+        }
+
+        // Tell the observer of the pass about an event, if the pass is observed.
+        fn notify(&mut self, event: HandleEvent) {
+            if let Some(Observer(observer)) = self.observer.as_mut() {
+                observer(event);
+            }
+        }
+
+        // Update the summary of the pass, if it is being summarized.
+        pub(crate) fn record(&mut self, f: impl FnOnce(&mut MutationSummary)) {
+            if let Some(summary) = self.summary.as_deref_mut() {
+                f(summary);
+            }
+        }
+
+        // Collect the elements discarded with `discard_into_sink` into `sink`.
+        pub(crate) fn with_sink(mut self, sink: &'b mut Vec<T>) -> Self {
+            self.sink = Some(sink);
+            self
+        }
+
+        // Keep the positions of inserted elements in `inserted`, which the runner uses for every handle of the pass.
+        pub(crate) fn with_inserted(mut self, inserted: &'b mut InsertedPositions) -> Self {
+            self.inserted = Some(inserted);
+            self
+        }
+
+        // Summarize the operations done by this handle into `summary`.
+        pub(crate) fn with_summary(mut self, summary: &'b mut MutationSummary) -> Self {
+            self.summary = Some(summary);
+            self
+        }
+
+        // Collect the actions scheduled with `defer` into `deferred`, which the runner runs after the pass.
+        pub(crate) fn with_deferred(mut self, deferred: &'b mut DeferredActions<S>) -> Self {
+            self.deferred = Some(deferred);
+            self
+        }
+
+        pub(crate) fn deferred_actions(&mut self) -> Option<&mut DeferredActions<S>> {
+            self.deferred.as_deref_mut()
+        }
+
+        /// Whether the current element was inserted during this pass, as opposed to being in the vector when the pass started.
+        /// Useful to avoid expanding the result of an expansion again.
+        ///
+        /// Only tracked when asked for, e.g. with [`HandlePass::track_insertions`](crate::HandlePass::track_insertions), and `false`
+        /// otherwise. Elements moved by hand through `peek_forward_slice_mut` are not followed.
+        #[must_use]
+        pub fn was_inserted_this_pass(&self) -> bool {
+            self.check_invariants("was_inserted_this_pass");
+            self.inserted
+                .as_deref()
+                .is_some_and(|inserted| inserted.contains(self.index.get()))
+        }
+
+        // Tell `observer` about the operations done by this handle, starting with the visit of the current element.
+        pub(crate) fn with_observer(mut self, observer: &'b mut dyn FnMut(HandleEvent)) -> Self {
+            self.observer = Some(Observer(observer));
+            self.notify(HandleEvent::Visited {
+                index: self.index.get(),
+            });
+            self
+        }
+
+        /// Run an inner pass over the elements after the current one, like `mutate_vec_by_handles` on that part of the vector.
+        /// Inner handles can never reach the current element. Afterwards, the iteration goes on with whatever the inner pass left.
+        /// Actions scheduled with `defer` by the inner handles only see the elements after the current one.
+        pub fn mutate_rest_by_handles(&mut self, op: impl FnMut(crate::VecMutationHandle<T>)) {
+            self.check_invariants("mutate_rest_by_handles");
+            let start = self.index.get() + 1;
+            let old_len = self.vec.len();
+            let rest = self.vec.remove_range(start, self.rest_end());
+            // The inner pass tracks the positions of inserted elements relative to its own start.
+            let rest_inserted = self
+                .inserted
+                .as_deref_mut()
+                .map_or_else(InsertedPositions::new, |inserted| inserted.split_off(start));
+            let mut detached = DetachedRest {
+                vec: &mut *self.vec,
+                start,
+                rest,
+            };
+            let (inner, rest_inserted) =
+                crate::summary::summarize_pass(&mut detached.rest, rest_inserted, op);
+            drop(detached);
+            if let Some(inserted) = self.inserted.as_deref_mut() {
+                inserted.append(rest_inserted, start);
+            }
+
+            let new_len = self.vec.len();
+            self.record(|summary| {
+                summary.discarded += inner.discarded;
+                summary.inserted += inner.inserted;
+                summary.set_or_replaced += inner.set_or_replaced;
+            });
+            if let Some(pass_end) = self.end.as_deref_mut() {
+                *pass_end = (*pass_end + new_len).saturating_sub(old_len).max(start);
+            }
+            self.check_invariants("mutate_rest_by_handles");
+        }
+
+        // Panics with a description of the violated part of the contract, if any, when the `debug-invariants` feature is enabled.
+        // Called at the start of every public method, and at the end of those which keep the handle and return nothing.
+        #[cfg(feature = "debug-invariants")]
+        fn check_invariants(&self, context: &str) {
+            let len = self.vec.len();
+            let index = self.index.get();
+            let next_index = *self.next_index;
+            assert!(
+                index < len,
+                "handlevec invariant violated in `{context}`: index {index} is not below the vector length {len}"
+            );
+            assert!(
+                next_index >= index,
+                "handlevec invariant violated in `{context}`: next index {next_index} is before index {index} (vector length {len})"
+            );
+            if let Some(end) = self.end.as_deref() {
+                assert!(
+                    index < *end && *end <= len,
+                    "handlevec invariant violated in `{context}`: end of pass {end} is not after index {index} and within the vector length {len}"
+                );
+            }
+        }
+
+        #[cfg(not(feature = "debug-invariants"))]
+        #[allow(clippy::unused_self)]
+        #[inline]
+        fn check_invariants(&self, _context: &str) {}
+
+        // The end of the pass, which is the length of the vector if it is not tracked.
+        fn pass_end(&self) -> usize {
+            self.end.as_deref().map_or(self.vec.len(), |end| *end)
+        }
+
+        // The end of the elements the methods working on the rest of the vector may touch: the end of the pass in range passes,
+        // and the end of the vector otherwise.
+        fn rest_end(&self) -> usize {
+            if self.windowed {
+                self.pass_end()
+            } else {
+                self.vec.len()
+            }
+        }
+
+        // The elements from `start` up to `rest_end`.
+        fn rest_from(&self, start: usize) -> &[T] {
+            let end = self.rest_end();
+            self.vec
+                .tail(start)
+                .and_then(|rest| rest.get(..end.saturating_sub(start)))
+                .unwrap_or(&[])
+        }
+
+        // Drop the elements in `start..end`, which must already be tracked as removed, truncating if they reach the end of the vector.
+        fn drop_range(&mut self, start: usize, end: usize) {
+            if end == self.vec.len() {
+                self.vec.truncate(start);
+            } else {
+                drop(self.vec.remove_range(start, end));
+            }
+        }
+
+        /// Swap the current element with the element `offset` positions after it. Returns `false`, and does nothing, if there is no such element.
+        /// An offset of 0 does nothing, and returns `true`.
+        ///
+        /// Afterwards, `get` and `get_mut` refer to the newly promoted element. The demoted element is now ahead of the current position,
+        /// so it will be processed again when the iteration reaches it.
+        pub fn swap_with_forward(&mut self, offset: usize) -> bool {
+            self.check_invariants("swap_with_forward");
+            match self.index.get().checked_add(offset) {
+                Some(other) if other < self.rest_end() => {
+                    self.vec.swap(self.index.get(), other);
+                    if let Some(inserted) = self.inserted.as_deref_mut() {
+                        inserted.swap(self.index.get(), other);
+                    }
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        /// Rotate the elements after the current one to the left, such that the element `mid` positions after the next one becomes the next one.
+        /// `mid` is taken modulo the number of elements after the current one, and nothing happens if there are none.
+        ///
+        /// This rotates the whole remainder of the vector, so elements appended with `append_and_skip` may be moved into the pass.
+        /// In range passes, only the elements up to the end of the range are rotated.
+        pub fn rotate_rest_left(&mut self, mid: usize) {
+            self.check_invariants("rotate_rest_left");
+            let rest_len = self.rest_end() - self.index.get() - 1;
+            if rest_len > 0 {
+                self.rotate_rest(mid % rest_len);
+            }
+            self.check_invariants("rotate_rest_left");
+        }
+
+        /// Rotate the elements after the current one to the right, such that the element `k` positions before the end becomes the next one.
+        /// `k` is taken modulo the number of elements after the current one, and nothing happens if there are none.
+        ///
+        /// This rotates the whole remainder of the vector, so elements appended with `append_and_skip` may be moved into the pass.
+        /// In range passes, only the elements up to the end of the range are rotated.
+        pub fn rotate_rest_right(&mut self, k: usize) {
+            self.check_invariants("rotate_rest_right");
+            let rest_len = self.rest_end() - self.index.get() - 1;
+            if rest_len > 0 {
+                self.rotate_rest((rest_len - k % rest_len) % rest_len);
+            }
+            self.check_invariants("rotate_rest_right");
+        }
+
+        // Rotate the elements after the current one to the left by `mid`, which must be less than their number.
+        fn rotate_rest(&mut self, mid: usize) {
+            let start = self.index.get() + 1;
+            let end = self.rest_end();
+            let rest_len = end - start;
+            self.vec.rotate_range_left(start, end, mid);
+            if let Some(inserted) = self.inserted.as_deref_mut() {
+                inserted.rotate_left(start, rest_len, mid);
+            }
+        }
+
+        /// Process the current element again in the next iteration, instead of advancing. Overrides any skipping done earlier on this handle.
+        ///
+        /// Note that calling this unconditionally makes the iteration process the same element forever.
+        pub fn reprocess_current(&mut self) {
+            self.check_invariants("reprocess_current");
+            *self.next_index = self.index.get();
+            self.check_invariants("reprocess_current");
+        }
+
+        /// Skip a certain amount of the next elements.
+        ///
+        /// Skipping past the end of the vector simply ends the pass, also for huge values like `usize::MAX`, and further calls
+        /// on the handle (like `insert_and_skip`) keep working.
+        ///
+        /// In a pass visiting every `step`-th element (see `mutate_vec_by_handles_step_by`), this skips `steps_to_skip` visits,
+        /// i.e. `steps_to_skip * step` elements.
+        pub fn skip_forward(&mut self, steps_to_skip: usize) {
+            self.check_invariants("skip_forward");
+            let from = *self.next_index;
+            *self.next_index = self
+                .next_index
+                .saturating_add(steps_to_skip.saturating_mul(self.stride));
+            self.notify(HandleEvent::Skipped {
+                from,
+                count: *self.next_index - from,
+            });
+            self.check_invariants("skip_forward");
+        }
+
+        /// Make the element `offset` positions after the current one the next to be processed, overriding the effect of any
+        /// insertions and skips done so far by this handle. `skip_forward` and `skip_until` may still be used afterwards.
+        ///
+        /// The offset counts positions in the vector as it is when the closure returns: elements inserted after the current one
+        /// afterwards (with any of the insert methods) do not move the target, so they are counted. Discarding the current
+        /// element keeps it counted, so the same element is processed next.
+        pub fn set_next_offset(&mut self, offset: NonZeroUsize) {
+            self.check_invariants("set_next_offset");
+            *self.next_index = self.index.get().saturating_add(offset.get());
+            self.next_pinned = true;
+            self.check_invariants("set_next_offset");
+        }
+
+        /// The number of positions from the current element to the next element to be processed, as things are now.
+        /// This is 1 unless elements were inserted, skipped, or the next offset was set.
+        ///
+        /// Stopping the iteration consumes the handle, so there is no offset to read afterwards. It does not move the next index
+        /// either: the last offset read is still where [`HandleState::position`] points after the pass, relative to this element.
+        #[must_use]
+        pub fn next_offset(&self) -> usize {
+            self.check_invariants("next_offset");
+            *self.next_index - self.index.get()
+        }
+
+        /// Skip the elements after the current one up to the first one matching `pred`, which is then processed next.
+        /// Returns whether a matching element was found. If not, all remaining elements of the pass are skipped.
+        ///
+        /// The scan starts right after the current element, and includes elements inserted after it earlier by this handle,
+        /// regardless of whether they were inserted to be skipped or processed.
+        pub fn skip_until(&mut self, pred: impl FnMut(&T) -> bool) -> bool {
+            self.check_invariants("skip_until");
+            let start = self.index.get() + 1;
+            let pass_end = self.pass_end();
+            let rest = self
+                .vec
+                .tail(start)
+                .and_then(|rest| rest.get(..pass_end.saturating_sub(start)))
+                .unwrap_or(&[]);
+            if let Some(offset) = rest.iter().position(pred) {
+                *self.next_index = start + offset;
+                true
+            } else {
+                *self.next_index = (*self.next_index).max(pass_end);
+                false
+            }
+        }
+
+        /// Skip the elements after the current one for as long as they match `pred`. The first one not matching is processed next.
+        /// Returns whether such an element was found. See `skip_until`.
+        pub fn skip_while(&mut self, mut pred: impl FnMut(&T) -> bool) -> bool {
+            self.check_invariants("skip_while");
+            self.skip_until(|t| !pred(t))
+        }
+
+        /// Skip the elements after the current one for which `pred` returns `true`, found by binary search, so the first element
+        /// for which it returns `false` is processed next. Returns the number of skipped elements.
+        ///
+        /// The elements must be partitioned by `pred`, like for `slice::partition_point`. Otherwise, the next element is unspecified,
+        /// but nothing panics. Like `skip_until`, this overrides earlier skipping on this handle.
+        pub fn skip_to_partition_point(&mut self, pred: impl FnMut(&T) -> bool) -> usize {
+            self.check_invariants("skip_to_partition_point");
+            let start = self.index.get() + 1;
+            let pass_end = self.pass_end();
+            let skipped = self
+                .vec
+                .tail(start)
+                .and_then(|rest| rest.get(..pass_end.saturating_sub(start)))
+                .map_or(0, |rest| rest.partition_point(pred));
+            *self.next_index = start + skipped;
+            self.check_invariants("skip_to_partition_point");
+            skipped
+        }
+
+        /// Skip the elements after the current one for as long as their total `cost` stays within `budget`, so the first element
+        /// which would exceed it is processed next. Returns the total cost of the skipped elements.
+        /// Like `skip_until`, this overrides earlier skipping on this handle.
+        pub fn skip_forward_by_cost(
+            &mut self,
+            budget: u64,
+            mut cost: impl FnMut(&T) -> u64,
+        ) -> u64 {
+            self.check_invariants("skip_forward_by_cost");
+            let start = self.index.get() + 1;
+            let pass_end = self.pass_end();
+            let rest = self
+                .vec
+                .tail(start)
+                .and_then(|rest| rest.get(..pass_end.saturating_sub(start)))
+                .unwrap_or(&[]);
+            let mut consumed: u64 = 0;
+            let mut skipped = 0;
+            for t in rest {
+                match consumed.checked_add(cost(t)) {
+                    Some(total) if total <= budget => consumed = total,
+                    _ => break,
+                }
+                skipped += 1;
+            }
+            *self.next_index = start + skipped;
+            self.check_invariants("skip_forward_by_cost");
+            consumed
+        }
+
+        // Skip elements just inserted after the current one, regardless of the stride. Does nothing once `set_next_offset` was called.
+        pub(crate) fn skip_inserted(&mut self, count: usize) {
+            if !self.next_pinned {
+                // Saturating, as a wrapped index could point before the current element, processing those elements again.
+                *self.next_index = self.next_index.saturating_add(count);
+            }
+        }
+
+        // Make `insert_and_process` and `insert_and_process_iter` skip the inserted elements, like their `_and_skip` counterparts.
+        pub(crate) fn with_insertions_skipped(mut self) -> Self {
+            self.skip_insertions = true;
+            self
+        }
+
+        // Make the methods working on the rest of the vector stop at the end of the pass, for passes over a sub-range.
+        pub(crate) fn within_window(mut self) -> Self {
+            self.windowed = true;
+            self
+        }
+
+        // Make `skip_forward` skip `stride` elements per step, for passes visiting every `stride`-th element.
+        pub(crate) fn with_stride(mut self, stride: usize) -> Self {
+            self.stride = stride;
+            self
+        }
+
+        // The position of the current element in the vector.
+        pub(crate) fn index(&self) -> usize {
+            self.index.get()
+        }
+
+        // From the new method, we are always within bounds. The discard method consumes ownership.
+        #[cfg(not(feature = "unchecked"))]
+        fn current(&self) -> &T {
+            self.index.current(self.vec).0
+        }
+
+        #[cfg(not(feature = "unchecked"))]
+        fn current_mut(&mut self) -> &mut T {
+            self.index.current_mut(self.vec).0
+        }
+
+        // The current element and all elements after it.
+        #[cfg(not(feature = "unchecked"))]
+        fn rest(&self) -> Option<&[T]> {
+            let len = self.rest_end() - self.index.get();
+            self.vec.tail(self.index.get())?.get(..len)
+        }
+
+        #[cfg(not(feature = "unchecked"))]
+        fn rest_mut(&mut self) -> Option<&mut [T]> {
+            let len = self.rest_end() - self.index.get();
+            self.vec.tail_mut(self.index.get())?.get_mut(..len)
+        }
+
+        #[cfg(feature = "unchecked")]
+        fn current(&self) -> &T {
+            // SAFETY: By the contract of this module, `index < vec.len()` whenever a method can be called on the handle.
+            // Methods removing the current element consume the handle.
+            unsafe { self.vec.get_unchecked(self.index.get()) }
+        }
+
+        #[cfg(feature = "unchecked")]
+        fn current_mut(&mut self) -> &mut T {
+            // SAFETY: As for `current`.
+            unsafe { self.vec.get_unchecked_mut(self.index.get()) }
+        }
+
+        #[cfg(feature = "unchecked")]
+        #[allow(clippy::unnecessary_wraps)]
+        fn rest(&self) -> Option<&[T]> {
+            let len = self.rest_end() - self.index.get();
+            // SAFETY: As for `current`, which also gives `index <= vec.len()`. The end of the pass is within the vector, so
+            // `index + len <= vec.len()`.
+            Some(unsafe {
+                self.vec
+                    .tail_unchecked(self.index.get())
+                    .get_unchecked(..len)
+            })
+        }
+
+        #[cfg(feature = "unchecked")]
+        #[allow(clippy::unnecessary_wraps)]
+        fn rest_mut(&mut self) -> Option<&mut [T]> {
+            let len = self.rest_end() - self.index.get();
+            // SAFETY: As for `rest`.
+            Some(unsafe {
+                self.vec
+                    .tail_unchecked_mut(self.index.get())
+                    .get_unchecked_mut(..len)
+            })
+        }
+
+        /// Do not process any more elements (similar to `skip_forward` more elements than remain in the vector, but the state also
+        /// remembers that the iteration was stopped).
+        /// Please note, this does not affect the call-site like the `break` keyword. This method does return, and executation continues from the call-site.
+        pub fn stop_iteration(mut self) {
+            self.check_invariants("stop_iteration");
+            self.stop();
+        }
+
+        fn stop(&mut self) {
+            match self.stopped.as_deref_mut() {
+                Some(stopped) => *stopped = true,
+                None => *self.next_index = usize::MAX, // If your vector is larger than usize::MAX, then you have another problem anyway...
+            }
+            self.notify(HandleEvent::Stopped {
+                index: self.index.get(),
+            });
+        }
+
+        /// Do not process any more elements, keeping the current element and all elements after it in the vector.
+        /// This is the same as `stop_iteration`, provided for symmetry with `stop_iteration_and_discard_rest` and `stop_iteration_and_truncate`.
+        pub fn stop_iteration_and_keep_rest(self) {
+            self.stop_iteration();
+        }
+
+        /// Remove the current element and all elements after it, and return them in order. Does not process any more elements.
+        /// This is the same as `discard_rest`.
+        #[allow(clippy::must_use_candidate)]
+        pub fn stop_iteration_and_discard_rest(self) -> Vec<T> {
+            self.discard_rest()
+        }
+
+        /// Drop the current element and all elements after it. Does not process any more elements.
+        /// Like `stop_iteration_and_discard_rest`, but does not allocate a vector for the dropped elements.
+        pub fn stop_iteration_and_truncate(mut self) {
+            self.check_invariants("stop_iteration_and_truncate");
+            *self.next_index = self.index.get();
+            self.stop();
+            let end = self.rest_end();
+            self.track_removal(self.index.get(), end);
+            self.drop_range(self.index.get(), end);
+        }
+
+        /// Discards the current element, and returns it as owned. Does not process any more elements.
+        /// Both the `discard` and `stop_iteration` methods consume ownership of the handle, so this is provided if you want to do both.
+        #[allow(clippy::must_use_candidate)]
+        pub fn discard_and_stop_iteration(mut self) -> T {
+            self.check_invariants("discard_and_stop_iteration");
+            let current = self.remove_current();
+            self.stop();
+            current
+        }
+
+        /// Get a reference to the current element, and to all elements after it.
+        #[must_use]
+        pub fn split_current_and_rest(&self) -> (&T, &[T]) {
+            self.check_invariants("split_current_and_rest");
+            let len = self.rest_end() - self.index.get() - 1;
+            let (current, rest) = self.index.current(self.vec);
+            (current, rest.split_at(len.min(rest.len())).0)
+        }
+
+        /// Get a mutable reference to the current element, and to all elements after it, at the same time.
+        /// This allows e.g. moving data from the next element into the current one without cloning it.
+        #[must_use]
+        pub fn split_current_and_rest_mut(&mut self) -> (&mut T, &mut [T]) {
+            self.check_invariants("split_current_and_rest_mut");
+            let len = self.rest_end() - self.index.get() - 1;
+            let (current, rest) = self.index.current_mut(self.vec);
+            (current, rest.split_at_mut(len.min(rest.len())).0)
+        }
+
+        /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+        #[must_use]
+        pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+        where
+            I: SliceIndex<[T]>,
+        {
+            self.check_invariants("peek_forward_slice");
+            self.rest()?.get(slice)
+        }
+
+        /// "Peek" a mutable reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+        #[must_use]
+        pub fn peek_forward_slice_mut<I>(&mut self, slice: I) -> Option<&mut I::Output>
+        where
+            I: SliceIndex<[T]>,
+        {
+            self.check_invariants("peek_forward_slice_mut");
+            self.rest_mut()?.get_mut(slice)
+        }
+    }
+
+    #[cfg(all(test, feature = "debug-invariants"))]
+    mod invariant_tests {
+        use super::*;
+
+        // The public API keeps the contract, so these simulate a bug by corrupting the handle from inside this module.
+
+        #[test]
+        #[should_panic(
+            expected = "invariant violated in `get`: index 7 is not below the vector length 3"
+        )]
+        fn test_invariants_index_out_of_bounds() {
+            let mut v = vec![1, 2, 3];
+            let mut state = HandleState::new();
+            while let Some(mut handle) = VecMutationHandle::new(&mut v, &mut state) {
+                handle.index = Position(7);
+                let _ = handle.get();
+            }
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "invariant violated in `insert_and_skip`: next index 0 is before index 1 (vector length 4)"
+        )]
+        fn test_invariants_next_index_before_index() {
+            let mut v = vec![1, 2, 3];
+            let mut state = HandleState::new();
+            while let Some(mut handle) = VecMutationHandle::new(&mut v, &mut state) {
+                if *handle.get() == 2 {
+                    handle.insert_and_skip(20);
+                    *handle.next_index = 0;
+                    handle.insert_and_skip(21);
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "invariant violated in `new`: end of pass 5 is not after index 0 and within the vector length 3"
+        )]
+        fn test_invariants_end_past_vector() {
+            let mut v = vec![1, 2, 3];
+            let mut state = HandleState::new();
+            let mut end = 5;
+            let _ = VecMutationHandle::start(&mut v, &mut state.position, None, None).map(
+                |mut handle| {
+                    handle.end = Some(&mut end);
+                    handle.check_invariants("new");
+                },
+            );
+        }
+
+        #[test]
+        fn test_invariants_hold_in_pass() {
+            let mut v: Vec<i32> = (0..20).collect();
+            crate::mutate_vec_by_handles(&mut v, |mut handle| match *handle.get() % 4 {
+                0 => handle.insert_and_skip(-1),
+                1 => {
+                    handle.discard();
+                }
+                2 => handle.append_and_skip(-2),
+                _ => handle.replace_with(|x| x * 10),
+            });
+            assert_eq!(v.len(), 20 + 5 - 5 + 5);
+        }
+    }
+}
+
+impl<T, S: HandleStorage<Item = T>> VecMutationHandle<'_, '_, T, S> {
+    /// Insert an element into the elements after the current one, which are assumed to be sorted, keeping them sorted.
+    /// Returns the position it was inserted at, with 0 being the current element. See `insert_into_rest_sorted_by`.
+    pub fn insert_into_rest_sorted(&mut self, t: T) -> usize
+    where
+        T: Ord,
+    {
+        self.insert_into_rest_sorted_by(t, T::cmp)
+    }
+
+    /// Insert an element into the elements after the current one, which are assumed to be sorted by the key `f` extracts, keeping them sorted.
+    /// Returns the position it was inserted at, with 0 being the current element. See `insert_into_rest_sorted_by`.
+    pub fn insert_into_rest_sorted_by_key<K: Ord>(
+        &mut self,
+        t: T,
+        mut f: impl FnMut(&T) -> K,
+    ) -> usize {
+        self.insert_into_rest_sorted_by(t, |a, b| f(a).cmp(&f(b)))
+    }
+
+    /// Insert an element into the sorted elements after the current one like `insert_into_rest_sorted`, without processing it in this pass.
+    /// Returns the position it was inserted at, with 0 being the current element. See `insert_into_rest_sorted_and_skip_by`.
+    pub fn insert_into_rest_sorted_and_skip(&mut self, t: T) -> usize
+    where
+        T: Ord,
+    {
+        self.insert_into_rest_sorted_and_skip_by(t, T::cmp)
+    }
+
+    /// Insert an element into the elements after the current one, which are assumed to be sorted by the key `f` extracts, without
+    /// processing it in this pass. Returns the position it was inserted at, with 0 being the current element.
+    /// See `insert_into_rest_sorted_and_skip_by`.
+    pub fn insert_into_rest_sorted_and_skip_by_key<K: Ord>(
+        &mut self,
+        t: T,
+        mut f: impl FnMut(&T) -> K,
+    ) -> usize {
+        self.insert_into_rest_sorted_and_skip_by(t, |a, b| f(a).cmp(&f(b)))
+    }
+
+    /// Skip the elements after the current one which are less than `key`, found by binary search, so the first element
+    /// which is not is processed next. Returns the number of skipped elements.
+    /// The elements after the current one must be sorted, see `skip_to_partition_point`.
+    pub fn skip_to_first_ge<K: Ord + ?Sized>(&mut self, key: &K) -> usize
+    where
+        T: Borrow<K>,
+    {
+        self.skip_to_partition_point(|t| t.borrow() < key)
+    }
+
+    /// Assign a new value to this element.
+    pub fn set(&mut self, t: T) {
+        *self.get_mut() = t;
+        self.track_replacement();
+    }
+
+    /// Replace the current element with another, and get ownership of the value currently there.
+    pub fn replace(&mut self, t: T) -> T {
+        self.track_replacement();
+        let curr = self.get_mut();
+        std::mem::replace(curr, t)
+    }
+
+    /// Insert each element in a vec, ordering the elements with the same order as the vec. Process the vector elements afterwards.
+    pub fn insert_and_process_vec(&mut self, vec: Vec<T>) {
+        self.insert_and_process_iter(vec);
+    }
+
+    /// Insert each element in a vec, ordering the elements with the same order as the vec. Do not process the vector elements afterwards.
+    pub fn insert_and_skip_vec(&mut self, vec: Vec<T>) {
+        self.insert_and_skip_iter(vec);
+    }
+
+    /// Insert a clone of the current element AFTER it, and process the clone in the next iteration.
+    pub fn duplicate_and_process(&mut self)
+    where
+        T: Clone,
+    {
+        let duplicate = self.get().clone();
+        self.insert_and_process(duplicate);
+    }
+
+    /// Insert a clone of the current element AFTER it, but do not process the clone in the next iteration.
+    pub fn duplicate_and_skip(&mut self)
+    where
+        T: Clone,
+    {
+        let duplicate = self.get().clone();
+        self.insert_and_skip(duplicate);
+    }
+
+    /// Run `f` on the current element, and return whether it changed, by comparing it to a clone taken before.
+    /// The change is not counted by `mutate_vec_by_handles_summarized`, like changes through `get_mut`.
+    pub fn with_change_detection(&mut self, f: impl FnOnce(&mut T)) -> bool
+    where
+        T: PartialEq + Clone,
+    {
+        let before = self.get().clone();
+        f(self.get_mut());
+        *self.get() != before
+    }
+
+    /// Replace the current element with several elements (possibly none), and get ownership of the value currently there.
+    /// The replacements are not processed, the next iteration continues with the element after the last replacement.
+    /// With no replacements, this is equivalent to `discard`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn replace_with_many(mut self, replacements: Vec<T>) -> T {
+        self.insert_and_skip_vec(replacements);
+        self.discard()
+    }
+
+    /// Replace the current element with several elements (possibly none), and get ownership of the value currently there.
+    /// The replacements are processed, the next iteration continues with the first replacement.
+    /// With no replacements, this is equivalent to `discard`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn replace_with_many_and_process(mut self, replacements: Vec<T>) -> T {
+        self.insert_and_process_vec(replacements);
+        self.discard()
+    }
+}
+
+/// Mutate a vec using index-style looping, but without thinking about the indices.
+///
+/// See crate documentation for examples and more context.
+#[cfg(not(feature = "allocator_api"))]
+pub fn mutate_vec_by_handles<T>(vec: &mut Vec<T>, op: impl FnMut(VecMutationHandle<T>)) {
+    HandlePass::new(vec).run(op);
+}
+
+/// Mutate a vec using index-style looping, but without thinking about the indices.
+/// With the `allocator_api` feature, this works for a `Vec` in any allocator.
+///
+/// See crate documentation for examples and more context.
+#[cfg(feature = "allocator_api")]
+pub fn mutate_vec_by_handles<T, A: std::alloc::Allocator>(
+    vec: &mut Vec<T, A>,
+    mut op: impl FnMut(VecMutationHandle<T, Vec<T, A>>),
+) {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        op(handle);
+    }
+}
+
+/// Mutate a vec using index-style looping, also passing the index of the current element to the closure.
+///
+/// The index is the live index of the element in the vector when the closure is called, reflecting all insertions and
+/// discards done earlier in the pass (unlike a counter maintained in the closure).
+pub fn mutate_vec_by_handles_enumerated<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(usize, VecMutationHandle<T>),
+) {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        op(handle.index(), handle);
+    }
+}
+
+/// Mutate a vec using index-style looping, and return the elements discarded with `discard_into_sink` or
+/// `discard_into_sink_and_stop_iteration`, in the order they were discarded.
+///
+/// The closure doesn't receive these elements, so there is no need to push them anywhere. Elements removed in other ways,
+/// like with `discard`, are returned to the closure instead, and not collected.
+#[must_use]
+pub fn mutate_vec_by_handles_collecting<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) -> Vec<T> {
+    let mut discarded = vec![];
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        op(handle.with_sink(&mut discarded));
+    }
+    discarded
+}
+
+/// Mutate a vec using index-style looping, with a fallible closure.
+///
+/// On the first `Err`, the iteration stops immediately, and the error is returned. All mutations done before that stay applied,
+/// including the ones done by the failing call of the closure before it returned the error. Stopping the iteration with
+/// `stop_iteration` from a call returning `Ok` ends the pass like usual, returning `Ok(())`.
+///
+/// # Errors
+/// Returns the first error returned by the closure.
+pub fn try_mutate_vec_by_handles<T, E>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T>) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        if let Err(error) = op(handle) {
+            driver.finish(vec);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Mutate a vec using index-style looping, with a fallible closure, visiting every element regardless of errors.
+///
+/// Each `Err` is recorded along with the live index the element had when the closure was called, like the index given by
+/// [`mutate_vec_by_handles_enumerated`], and the pass continues. All mutations stay applied, including the ones done by failing
+/// calls of the closure. Stopping the iteration with `stop_iteration` ends the pass like usual.
+///
+/// # Errors
+/// Returns every error returned by the closure, with the index of its element, in the order they were returned.
+pub fn mutate_vec_by_handles_accumulate_errors<T, E>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T>) -> Result<(), E>,
+) -> Result<(), Vec<(usize, E)>> {
+    let mut errors = vec![];
+    mutate_vec_by_handles_enumerated(vec, |index, handle| {
+        if let Err(error) = op(handle) {
+            errors.push((index, error));
+        }
+    });
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Mutate a vec using index-style looping, with a fallible closure, restoring the vector as it was before the pass on the first `Err`.
+///
+/// The vector is cloned before the pass. If the closure returns `Err`, the pass stops, the changes are rolled back by putting the clone
+/// back, and the error is returned. A successful pass only pays for the clone, which is dropped afterwards.
+///
+/// # Errors
+/// Returns the first error returned by the closure.
+pub fn mutate_vec_by_handles_transactional<T: Clone, E>(
+    vec: &mut Vec<T>,
+    op: impl FnMut(VecMutationHandle<T>) -> Result<(), E>,
+) -> Result<(), E> {
+    let original = vec.clone();
+    try_mutate_vec_by_handles(vec, op).inspect_err(|_| *vec = original)
+}
+
+/// Mutate a vec using index-style looping, where the closure may break out of the loop with a value.
+///
+/// Returns `Some` with the value if the closure returned `ControlFlow::Break`, which stops the iteration like `stop_iteration`,
+/// or `None` if the pass ran to completion. The handle may still be used (e.g. discarded) before breaking.
+pub fn mutate_vec_by_handles_control<T, B>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T>) -> ControlFlow<B>,
+) -> Option<B> {
+    try_mutate_vec_by_handles(vec, |handle| match op(handle) {
+        ControlFlow::Continue(()) => Ok(()),
+        ControlFlow::Break(b) => Err(b),
+    })
+    .err()
+}
+
+/// Mutate a vec using index-style looping, threading a state through the pass by value, and returning it afterwards.
+///
+/// Equivalent to capturing the state mutably in the closure, but without the borrow lingering, and allowing reusable
+/// passes to be written as functions taking `(&mut S, VecMutationHandle<T>)`.
+pub fn mutate_vec_by_handles_with_state<T, S>(
+    vec: &mut Vec<T>,
+    mut state: S,
+    mut op: impl FnMut(&mut S, VecMutationHandle<T>),
+) -> S {
+    mutate_vec_by_handles(vec, |handle| op(&mut state, handle));
+    state
+}
+
+/// Mutate a vec using index-style looping, starting at the element at `start`, and return the index of the first unprocessed element.
+///
+/// That is the length of the vector if the pass ran to the end, or wherever the iteration continued from if it was stopped,
+/// taking all insertions and discards into account. This allows splitting a pass into several budgeted chunks:
+/// `position = mutate_vec_by_handles_from(&mut vec, position, ...)`, stopping the iteration after a number of elements.
+/// Note that each chunk is a pass of its own, so elements appended with `append_and_skip` are processed by a later chunk.
+pub fn mutate_vec_by_handles_from<T>(
+    vec: &mut Vec<T>,
+    start: usize,
+    op: impl FnMut(VecMutationHandle<T>),
+) -> usize {
+    HandlePass::new(vec).start_at(start).run(op).position
+}
+
+/// Mutate a vec using index-style looping, visiting only every `step`-th element, starting with the element at `offset`.
+/// E.g. with a step of 2, this visits one lane of interleaved data like `[a0, b0, a1, b1, ...]`.
+///
+/// After each visit, the iteration continues `step - 1` elements after where it would otherwise have continued, so discards and
+/// `insert_and_skip` keep the lanes intact. `skip_forward(n)` skips `n` visits.
+pub fn mutate_vec_by_handles_step_by<T>(
+    vec: &mut Vec<T>,
+    step: NonZeroUsize,
+    offset: usize,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    let mut driver = PassDriver::new(vec).starting_at(offset).step_by(step.get());
+    while let Some(handle) = driver.next(vec) {
+        op(handle);
+    }
+}
+
+/// Mutate a sub-range of a vec using index-style looping, leaving the elements outside of the range untouched and unvisited.
+///
+/// The end of the range moves with insertions and discards inside it. The methods working on the elements after the current one
+/// stop at the end of the range, and `append_and_skip` inserts right after it. Ranges reaching outside the vector are clamped.
+pub fn mutate_vec_range_by_handles<T>(
+    vec: &mut Vec<T>,
+    range: impl RangeBounds<usize>,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => vec.len(),
+    };
+    let mut driver = PassDriver::new(vec).starting_at(start).within(end);
+    while let Some(handle) = driver.next(vec) {
+        op(handle);
+    }
+}
+
+/// Mutate a vec using index-style looping, where only the elements matching `pred` are given to the closure.
+///
+/// Elements not matching are passed over by the runner, as if the closure returned right away. Elements inserted with
+/// `insert_and_process` (or appended with `append_and_process`) are processed like any other element, so they are also only given to
+/// the closure if they match. Elements inserted with `insert_and_skip` are never checked.
+pub fn mutate_matching_by_handles<T>(
+    vec: &mut Vec<T>,
+    mut pred: impl FnMut(&T) -> bool,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        if pred(handle.get()) {
+            op(handle);
+        }
+    }
+}
+
+/// Give a handle to the first element matching `pred` to the closure, and return whether there was one.
+///
+/// The closure is called at most once, so it can consume the handle, e.g. with `discard`. Elements it inserts are not processed.
+pub fn mutate_first_match_by_handles<T>(
+    vec: &mut Vec<T>,
+    pred: impl FnMut(&T) -> bool,
+    op: impl FnOnce(VecMutationHandle<T>),
+) -> bool {
+    let position = vec.iter().position(pred);
+    mutate_at_by_handle(vec, position, op)
+}
+
+/// Give a handle to the last element matching `pred` to the closure, and return whether there was one.
+/// Like [`mutate_first_match_by_handles`], but scanning from the back.
+pub fn mutate_last_match_by_handles<T>(
+    vec: &mut Vec<T>,
+    pred: impl FnMut(&T) -> bool,
+    op: impl FnOnce(VecMutationHandle<T>),
+) -> bool {
+    let position = vec.iter().rposition(pred);
+    mutate_at_by_handle(vec, position, op)
+}
+
+fn mutate_at_by_handle<T>(
+    vec: &mut Vec<T>,
+    position: Option<usize>,
+    op: impl FnOnce(VecMutationHandle<T>),
+) -> bool {
+    let Some(position) = position else {
+        return false;
+    };
+    let mut driver = PassDriver::new(vec).starting_at(position);
+    if let Some(handle) = driver.next(vec) {
+        op(handle);
+    }
+    driver.finish(vec);
+    true
+}
+
+/// Mutate several vecs using index-style looping, as if they were one long vec, with the same closure.
+///
+/// Each handle operates on the vec its element belongs to, so discards and insertions apply to that vec only.
+/// Stopping the iteration stops the whole traversal, not only the current vec. Peeks are limited to the current vec,
+/// so e.g. the last element of a vec never sees the first element of the next one.
+pub fn mutate_vecs_by_handles<T>(vecs: &mut [Vec<T>], op: impl FnMut(VecMutationHandle<T>)) {
+    mutate_chained_by_handles(vecs.iter_mut(), op);
+}
+
+/// Mutate several vecs using index-style looping, as if they were one long vec, with the same closure.
+///
+/// Equivalent to [`mutate_vecs_by_handles`], for when the vecs are not stored next to each other.
+pub fn mutate_vec_refs_by_handles<T>(
+    vecs: &mut [&mut Vec<T>],
+    op: impl FnMut(VecMutationHandle<T>),
+) {
+    mutate_chained_by_handles(vecs.iter_mut().map(|vec| &mut **vec), op);
+}
+
+fn mutate_chained_by_handles<'v, T: 'v>(
+    vecs: impl IntoIterator<Item = &'v mut Vec<T>>,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    for vec in vecs {
+        // Actions only touch the vec they were scheduled on, so they run as soon as it is done.
+        let mut driver = PassDriver::new(vec);
+        while let Some(handle) = driver.next(vec) {
+            op(handle);
+        }
+
+        // Stopping the iteration also ends the traversal of the remaining vecs.
+        if driver.is_stopped() {
+            return;
+        }
+    }
+}
+
+/// Trait for adding vector mutation by handles as an extension trait to vec.
+pub trait VecMutateByHandles<T>: Sized {
+    /// Mutate a vec using index-style looping, but without thinking about the indices.
+    ///
+    /// See crate documentation for examples and more context.
+    fn mutate_vec_by_handles(&mut self, op: impl FnMut(VecMutationHandle<T>));
+
+    /// Mutate a vec using index-style looping, also passing the index of the current element to the closure.
+    /// See [`mutate_vec_by_handles_enumerated`].
+    fn mutate_vec_by_handles_enumerated(&mut self, op: impl FnMut(usize, VecMutationHandle<T>));
+
+    /// Mutate a vec using index-style looping, with a fallible closure. See [`try_mutate_vec_by_handles`].
+    ///
+    /// # Errors
+    /// Returns the first error returned by the closure.
+    fn try_mutate_vec_by_handles<E>(
+        &mut self,
+        op: impl FnMut(VecMutationHandle<T>) -> Result<(), E>,
+    ) -> Result<(), E>;
+
+    /// Mutate a vec using index-style looping, where the closure may break out of the loop with a value.
+    /// See [`mutate_vec_by_handles_control`].
+    fn mutate_vec_by_handles_control<B>(
+        &mut self,
+        op: impl FnMut(VecMutationHandle<T>) -> ControlFlow<B>,
+    ) -> Option<B>;
+
+    /// Mutate a vec using index-style looping, threading a state through the pass by value, and returning it afterwards.
+    /// See [`mutate_vec_by_handles_with_state`].
+    fn mutate_vec_by_handles_with_state<S>(
+        &mut self,
+        state: S,
+        op: impl FnMut(&mut S, VecMutationHandle<T>),
+    ) -> S;
+
+    /// Get a cursor creating one handle per element, like a lending iterator. See [`HandleCursor`].
+    fn handles(&mut self) -> HandleCursor<'_, T>;
+
+    /// Mutate a vec using index-style looping from the last element towards the first. See [`mutate_vec_by_handles_rev`].
+    fn mutate_vec_by_handles_rev(&mut self, op: impl FnMut(VecMutationHandleRev<T>));
+
+    /// Mutate a sub-range of a vec using index-style looping. See [`mutate_vec_range_by_handles`].
+    fn mutate_vec_range_by_handles(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        op: impl FnMut(VecMutationHandle<T>),
+    );
+
+    /// Mutate a vec using index-style looping, only for the elements matching `pred`. See [`mutate_matching_by_handles`].
+    fn mutate_matching_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnMut(VecMutationHandle<T>),
+    );
+
+    /// Give a handle to the first element matching `pred` to the closure, and return whether there was one.
+    /// See [`mutate_first_match_by_handles`].
+    fn mutate_first_match_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnOnce(VecMutationHandle<T>),
+    ) -> bool;
+
+    /// Give a handle to the last element matching `pred` to the closure, and return whether there was one.
+    /// See [`mutate_last_match_by_handles`].
+    fn mutate_last_match_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnOnce(VecMutationHandle<T>),
+    ) -> bool;
+}
+
+impl<T> VecMutateByHandles<T> for Vec<T> {
+    fn mutate_vec_by_handles(&mut self, op: impl FnMut(VecMutationHandle<T>)) {
+        HandlePass::new(self).run(op);
+    }
+
+    fn mutate_vec_by_handles_enumerated(&mut self, op: impl FnMut(usize, VecMutationHandle<T>)) {
+        mutate_vec_by_handles_enumerated(self, op);
+    }
+
+    fn try_mutate_vec_by_handles<E>(
+        &mut self,
+        op: impl FnMut(VecMutationHandle<T>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        try_mutate_vec_by_handles(self, op)
+    }
+
+    fn mutate_vec_by_handles_control<B>(
+        &mut self,
+        op: impl FnMut(VecMutationHandle<T>) -> ControlFlow<B>,
+    ) -> Option<B> {
+        mutate_vec_by_handles_control(self, op)
+    }
+
+    fn mutate_vec_by_handles_with_state<S>(
+        &mut self,
+        state: S,
+        op: impl FnMut(&mut S, VecMutationHandle<T>),
+    ) -> S {
+        mutate_vec_by_handles_with_state(self, state, op)
+    }
+
+    fn handles(&mut self) -> HandleCursor<'_, T> {
+        HandleCursor::new(self)
+    }
+
+    fn mutate_vec_by_handles_rev(&mut self, op: impl FnMut(VecMutationHandleRev<T>)) {
+        mutate_vec_by_handles_rev(self, op);
+    }
+
+    fn mutate_vec_range_by_handles(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        op: impl FnMut(VecMutationHandle<T>),
+    ) {
+        mutate_vec_range_by_handles(self, range, op);
+    }
+
+    fn mutate_matching_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnMut(VecMutationHandle<T>),
+    ) {
+        mutate_matching_by_handles(self, pred, op);
+    }
+
+    fn mutate_first_match_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnOnce(VecMutationHandle<T>),
+    ) -> bool {
+        mutate_first_match_by_handles(self, pred, op)
+    }
+
+    fn mutate_last_match_by_handles(
+        &mut self,
+        pred: impl FnMut(&T) -> bool,
+        op: impl FnOnce(VecMutationHandle<T>),
+    ) -> bool {
+        mutate_last_match_by_handles(self, pred, op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec_mut_handle_new() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let handle = VecMutationHandle::new(&mut v, &mut state);
+        assert!(handle.is_some());
+        assert_eq!(handle.unwrap().get(), &1);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_set() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.set(10);
+        assert_eq!(handle.get(), &10);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_discard() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.discard(), 1);
+        assert_eq!(v, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_and_process() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_process(10);
+        assert_eq!(v, vec![1, 10, 2, 3]);
+        assert_eq!(state.position(), 1);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_forward() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(2);
+        assert_eq!(state.position(), 3);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_peek_forward_slice() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.peek_forward_slice(1..), Some(&[2, 3][..]));
+        let handle_two = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle_two.peek_forward_slice(1..), Some(&[3][..]));
+        assert_eq!(handle_two.peek_forward_slice(2), None);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_peek_forward_slice_mut() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.peek_forward_slice_mut(1..), Some(&mut [2, 3][..]));
+        handle.peek_forward_slice_mut(1..).unwrap()[1] = 70;
+        assert_eq!(v[2], 70);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_and_skip() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_skip(10);
+        assert_eq!(v, vec![1, 2, 10, 3]);
+        assert_eq!(state.position(), 3);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_and_process_vec_large_batch() {
+        let batch: Vec<usize> = (100..600).collect();
+
+        let mut spliced: Vec<usize> = (0..10).collect();
+        let mut state = HandleState::starting_at(4);
+        let mut handle = VecMutationHandle::new(&mut spliced, &mut state).unwrap();
+        handle.insert_and_process_vec(batch.clone());
+        assert_eq!(state.position(), 5);
+
+        // The old implementation, inserting one element at a time in reverse.
+        let mut repeated: Vec<usize> = (0..10).collect();
+        let mut state = HandleState::starting_at(4);
+        let mut handle = VecMutationHandle::new(&mut repeated, &mut state).unwrap();
+        for t in batch.into_iter().rev() {
+            handle.insert_and_process(t);
+        }
+
+        assert_eq!(spliced, repeated);
+        assert_eq!(spliced[5..505], (100..600).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_and_skip_vec_large_batch() {
+        let mut v: Vec<usize> = (0..10).collect();
+        let mut state = HandleState::starting_at(4);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_skip_vec((100..600).collect());
+        assert_eq!(state.position(), 505);
+        assert_eq!(v.len(), 510);
+        assert_eq!(v[505..], [5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_iter_empty() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_process_iter(std::iter::empty());
+        handle.insert_and_skip_iter(Vec::new());
+        assert_eq!(v, vec![1, 2, 3]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_iter_single() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_skip_iter(std::iter::once(10));
+        handle.insert_and_process_iter(Some(20));
+        assert_eq!(v, vec![1, 20, 10, 2, 3]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_mutate_vec_insert_iter_map_chain() {
+        let mut v = vec![3, 100, 2];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            let x = *handle.get();
+            visited.push(x);
+            if x < 10 {
+                handle.insert_and_skip_iter(
+                    (1..=x)
+                        .map(|i| i * 1000)
+                        .filter(|i| i % 2000 != 0)
+                        .map(|i| i + x),
+                );
+            } else if x == 100 {
+                handle.insert_and_process_iter((0..3).map(|i| i + x + 1).rev().skip(1));
+            }
+        });
+        assert_eq!(visited, vec![3, 100, 102, 101, 2]);
+        assert_eq!(v, vec![3, 1003, 3003, 100, 102, 101, 2, 1002]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_replace_with_many_none() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(1);
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.replace_with_many(vec![]), 2);
+        assert_eq!(v, vec![1, 3]);
+        assert_eq!(state.position(), 1);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_replace_with_many_one() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(1);
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.replace_with_many(vec![20]), 2);
+        assert_eq!(v, vec![1, 20, 3]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_mutate_vec_replace_with_many_three() {
+        let mut v = vec![1, 2, 3];
+        let mut replaced = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            if *handle.get() == 2 {
+                replaced.push(handle.replace_with_many(vec![2, 2, 2]));
+            }
+        });
+        assert_eq!(v, vec![1, 2, 2, 2, 3]);
+        assert_eq!(replaced, vec![2]);
+    }
+
+    #[test]
+    fn test_mutate_vec_replace_with_many_last_index() {
+        let mut v = vec![1, 2, 3];
+        v.mutate_vec_by_handles(|handle| {
+            if *handle.get() == 3 {
+                handle.replace_with_many(vec![4, 5, 6]);
+            }
+        });
+        assert_eq!(v, vec![1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_mutate_vec_replace_with_many_and_process() {
+        let mut v = vec![8, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            let x = *handle.get();
+            visited.push(x);
+            if x > 2 {
+                handle.replace_with_many_and_process(vec![x / 2, x / 2]);
+            }
+        });
+        assert_eq!(visited, vec![8, 4, 2, 2, 4, 2, 2, 1]);
+        assert_eq!(v, vec![2, 2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_merge_with_next() {
+        let mut v = vec![String::from("a"), String::from("b"), String::from("c")];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert!(handle.merge_with_next(|a, b| a + &b));
+        assert_eq!(handle.get(), "bc");
+        assert!(!handle.merge_with_next(|a, b| a + &b));
+        assert_eq!(v, vec![String::from("a"), String::from("bc")]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_mutate_vec_merge_with_next_collapse_runs() {
+        let mut v = vec![1, 1, 1, 2, 3, 3, 1, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            while handle.peek_forward_slice(1) == Some(handle.get()) {
+                handle.merge_with_next(|a, _| a);
+            }
+        });
+        assert_eq!(v, vec![1, 2, 3, 1]);
+        assert_eq!(visited, v);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_current_empty() {
+        let mut v = vec![1, 2, 3];
+        v.mutate_vec_by_handles(|handle| {
+            if *handle.get() == 2 {
+                handle.split_current(|_| vec![]);
+            }
+        });
+        assert_eq!(v, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_current_single() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 2 {
+                handle.split_current(|x| vec![x * 10]);
+            }
+        });
+        assert_eq!(v, vec![1, 20, 3]);
+        assert_eq!(visited, vec![1, 2, 20, 3]);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_current_multiple() {
+        let mut v = vec![String::from("a b"), String::from("c d e")];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            visited.push(handle.get().clone());
+            if handle.get().contains(' ') {
+                handle.split_current(|s| s.split(' ').map(String::from).collect());
+            }
+        });
+        assert_eq!(v, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(visited, vec!["a b", "a", "b", "c d e", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_current_and_skip_multiple() {
+        let mut v = vec![5, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 5 {
+                handle.split_current_and_skip(|x| vec![x, x, x]);
+            }
+        });
+        assert_eq!(v, vec![5, 5, 5, 1]);
+        assert_eq!(visited, vec![5, 1]);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_current_after_insert_and_skip() {
+        let mut v = vec![5, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 5 {
+                handle.insert_and_skip(0);
+                handle.split_current(|x| vec![x - 1, x + 1]);
+            }
+        });
+        assert_eq!(v, vec![4, 6, 0, 1]);
+        assert_eq!(visited, vec![5, 4, 6, 0, 1]);
+
+        let mut v = vec![5, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 5 {
+                handle.insert_and_skip(0);
+                handle.split_current_and_skip(|x| vec![x - 1, x + 1]);
+            }
+        });
+        assert_eq!(v, vec![4, 6, 0, 1]);
+        assert_eq!(visited, vec![5, 1]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_duplicate_and_skip() {
+        let mut v = vec![vec![1], vec![2]];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.get_mut().push(10);
+        handle.duplicate_and_skip();
+        handle.get_mut().push(20);
+        handle.set(vec![3]);
+        assert_eq!(v, vec![vec![3], vec![1, 10], vec![2]]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_mutate_vec_duplicate_and_process() {
+        let mut v = vec![String::from("a"), String::from("b")];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(handle.get().clone());
+            if handle.get().len() < 3 {
+                handle.get_mut().push('!');
+                handle.duplicate_and_process();
+                handle.get_mut().push('?');
+            }
+        });
+        assert_eq!(visited, vec!["a", "a!", "a!!", "b", "b!", "b!!"]);
+        assert_eq!(v, vec!["a!?", "a!!?", "a!!", "b!?", "b!!?", "b!!"]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_take_forward_more_than_remaining() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.take_forward(10), vec![3, 4]);
+        assert_eq!(handle.take_forward(10), Vec::<i32>::new());
+        assert_eq!(v, vec![1, 2]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_take_forward_zero() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.take_forward(0), Vec::<i32>::new());
+        assert_eq!(v, vec![1, 2, 3]);
+        assert_eq!(state.position(), 1);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_take_forward_after_skip() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(2);
+        assert_eq!(handle.take_forward(1), vec![2]);
+        assert_eq!(v[state.position()], 4);
+    }
+
+    #[test]
+    fn test_mutate_vec_take_forward_absorb() {
+        let mut v: Vec<usize> = vec![2, 10, 20, 1, 30, 0, 40];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.insert_and_process(100);
+            }
+            let n = *handle.get();
+            if n < 10 {
+                let absorbed: usize = handle.take_forward(n).into_iter().sum();
+                *handle.get_mut() += absorbed;
+            }
+        });
+        assert_eq!(visited, vec![2, 1, 30, 0, 40]);
+        assert_eq!(v, vec![32, 101, 30, 0, 40]);
+    }
+
+    #[test]
+    fn test_mutate_vecs_stop_across_shards() {
+        let mut shards = vec![vec![1, 2], vec![3, 4, 5], vec![6]];
+        let mut sum = 0;
+        mutate_vecs_by_handles(&mut shards, |handle| {
+            sum += *handle.get();
+            if *handle.get() == 4 {
+                handle.stop_iteration();
+            } else {
+                handle.discard();
+            }
+        });
+        assert_eq!(sum, 10);
+        assert_eq!(shards, vec![vec![], vec![4, 5], vec![6]]);
+    }
+
+    #[test]
+    fn test_mutate_vecs_per_shard_insertion() {
+        let mut first = vec![1, 2];
+        let mut second = vec![3];
+        let mut visited = vec![];
+        mutate_vec_refs_by_handles(&mut [&mut first, &mut second], |mut handle| {
+            let remaining_in_shard = handle.peek_forward_slice(1..).unwrap().len();
+            visited.push((*handle.get(), remaining_in_shard));
+            if *handle.get() % 2 == 0 {
+                handle.insert_and_process(*handle.get() + 1);
+            } else {
+                handle.insert_and_skip(0);
+            }
+        });
+        assert_eq!(visited, vec![(1, 1), (2, 0), (3, 0), (3, 0)]);
+        assert_eq!(first, vec![1, 0, 2, 3, 0]);
+        assert_eq!(second, vec![3, 0]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_discard_rest_last() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(2);
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.discard_rest(), vec![3]);
+        assert_eq!(v, vec![1, 2]);
+        assert!(VecMutationHandle::new(&mut v, &mut state).is_none());
+    }
+
+    #[test]
+    fn test_vec_mut_handle_split_off_rest_last() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(2);
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.split_off_rest(), Vec::<i32>::new());
+        assert_eq!(v, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mutate_vec_discard_rest_first() {
+        let mut v = vec![1, 2, 3];
+        let mut rest = vec![];
+        v.mutate_vec_by_handles(|handle| rest = handle.discard_rest());
+        assert_eq!(v, Vec::<i32>::new());
+        assert_eq!(rest, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mutate_vec_discard_rest_terminator() {
+        let mut v = vec![1, 2, 0, 3, 4];
+        let mut visited = vec![];
+        let mut rest = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 0 {
+                rest = handle.discard_rest();
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 0]);
+        assert_eq!(v, vec![1, 2]);
+        assert_eq!(rest, vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn test_mutate_vec_split_off_rest_first() {
+        let mut v = vec![1, 2, 3];
+        let mut rest = vec![];
+        v.mutate_vec_by_handles(|handle| rest = handle.split_off_rest());
+        assert_eq!(v, vec![1]);
+        assert_eq!(rest, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_truncate_after_zero() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(2);
+        handle.truncate_after(0);
+        assert_eq!(v, vec![1, 2]);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_truncate_after_larger_than_tail() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(1);
+        handle.truncate_after(10);
+        assert_eq!(v, vec![1, 2, 3, 4]);
+        assert_eq!(state.position(), 3);
+    }
+
+    #[test]
+    fn test_mutate_vec_truncate_after_then_insert() {
+        let mut v = vec![5, 4, 3, 2, 1];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 4 {
+                handle.truncate_after(1);
+                handle.insert_and_process(10);
+            }
+        });
+        assert_eq!(visited, vec![5, 4, 10, 3]);
+        assert_eq!(v, vec![5, 4, 10, 3]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_retain_rest_after_skip() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(2); // Next is 4
+        assert_eq!(handle.retain_rest(|x| x % 2 == 0), 2);
+        assert_eq!(v, vec![1, 2, 4, 6]);
+        assert_eq!(v[state.position()], 4);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_retain_rest_skipped_to_removed() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.skip_forward(2); // Next is 4
+        assert_eq!(handle.retain_rest(|x| *x != 4 && *x != 5), 2);
+        assert_eq!(v, vec![1, 2, 3]);
+        assert_eq!(state.position(), 3);
+    }
+
+    #[test]
+    fn test_mutate_vec_retain_rest_supersede() {
+        let mut v = vec![(1, 'a'), (2, 'b'), (1, 'c'), (3, 'd'), (2, 'e'), (1, 'f')];
+        let mut removed = 0;
+        v.mutate_vec_by_handles(|mut handle| {
+            let key = handle.get().0;
+            removed += handle.retain_rest(|(k, _)| *k != key);
+        });
+        assert_eq!(removed, 3);
+        assert_eq!(v, vec![(1, 'a'), (2, 'b'), (3, 'd')]);
+    }
+
+    #[test]
+    fn test_mutate_vec_retain_rest_mut() {
+        let mut v = vec![1, 2, 3, 4];
+        v.mutate_vec_by_handles(|mut handle| {
+            if *handle.get() == 1 {
+                handle.retain_rest_mut(|x| {
+                    *x *= 10;
+                    *x != 30
+                });
+            }
+        });
+        assert_eq!(v, vec![1, 20, 40]);
+    }
+
+    #[test]
+    fn test_mutate_vec_append_and_skip() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            handle.append_and_skip(*handle.get() * 10);
+            if *handle.get() == 2 {
+                handle.append_and_skip_vec(vec![7, 8]);
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 3]);
+        assert_eq!(v, vec![1, 2, 3, 10, 20, 7, 8, 30]);
+    }
+
+    #[test]
+    fn test_mutate_vec_append_and_process() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            let x = *handle.get();
+            visited.push(x);
+            if x < 10 {
+                handle.append_and_skip(x * 100);
+                handle.append_and_process(x * 10);
+            }
+            if x == 2 {
+                handle.append_and_process_vec(vec![4, 5]);
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 3, 10, 20, 4, 5, 30, 40, 50]);
+        assert_eq!(
+            v,
+            vec![1, 2, 3, 10, 20, 4, 5, 30, 40, 50, 100, 200, 300, 400, 500]
+        );
+    }
+
+    #[test]
+    fn test_mutate_vec_append_and_skip_with_discards() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            handle.append_and_skip(*handle.get() + 10);
+            if *handle.get() % 2 == 0 {
+                handle.discard();
+            } else {
+                handle.insert_and_process(0);
+            }
+        });
+        assert_eq!(visited, vec![1, 0, 2, 3, 0, 4]);
+        assert_eq!(v, vec![1, 3, 11, 10, 12, 13, 10, 14]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_new_bounded() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        let mut end = 2;
+        let mut handle = VecMutationHandle::new_bounded(&mut v, &mut state, &mut end).unwrap();
+        handle.insert_and_process(5);
+        handle.append_and_skip(6);
+        assert_eq!(end, 3);
+        let handle = VecMutationHandle::new_bounded(&mut v, &mut state, &mut end).unwrap();
+        handle.discard();
+        assert_eq!(end, 2);
+        assert!(VecMutationHandle::new_bounded(&mut v, &mut state, &mut end).is_some());
+        assert!(VecMutationHandle::new_bounded(&mut v, &mut state, &mut end).is_none());
+        assert_eq!(v, vec![1, 2, 3, 6]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_swap_with_forward_out_of_bounds() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert!(!handle.swap_with_forward(2));
+        assert!(handle.swap_with_forward(0));
+        assert!(handle.swap_with_forward(1));
+        assert_eq!(handle.get(), &3);
+        assert_eq!(v, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_mutate_vec_swap_with_forward_double_visit() {
+        let mut v = vec![5, 1, 7, 2, 9];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            // Promote the smallest remaining element.
+            let rest = handle.peek_forward_slice(0..).unwrap();
+            let (offset, _) = rest.iter().enumerate().min_by_key(|(_, x)| **x).unwrap();
+            handle.swap_with_forward(offset);
+            visited.push(*handle.get());
+        });
+        assert_eq!(v, vec![1, 2, 5, 7, 9]);
+        assert_eq!(visited, vec![1, 2, 5, 7, 9]);
+    }
+
+    #[test]
+    fn test_mutate_vec_swap_with_forward_demoted_visited_again() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.swap_with_forward(2);
+            }
+        });
+        assert_eq!(v, vec![3, 2, 1]);
+        assert_eq!(visited, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_mutate_vec_defer_current_alternating() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut visited = vec![];
+        let mut deferred = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            let x = *handle.get();
+            visited.push(x);
+            if x % 2 == 0 && !deferred.contains(&x) {
+                deferred.push(x);
+                handle.defer_current();
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 3, 4, 5, 2, 4]);
+        assert_eq!(v, vec![1, 3, 5, 2, 4]);
+    }
+
+    #[test]
+    fn test_mutate_vec_defer_current_and_skip_alternating() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            visited.push(*handle.get());
+            if *handle.get() % 2 == 0 {
+                handle.defer_current_and_skip();
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 3, 4, 5]);
+        assert_eq!(v, vec![1, 3, 5, 2, 4]);
+    }
+
+    #[test]
+    fn test_mutate_vec_defer_current_and_skip_everything() {
+        let mut v = vec![1, 2, 3];
+        let mut visits = 0;
+        v.mutate_vec_by_handles(|handle| {
+            visits += 1;
+            handle.defer_current_and_skip();
+        });
+        assert_eq!(visits, 3);
+        assert_eq!(v, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mutate_vec_reprocess_current_three_times() {
+        let mut v = vec![1, 100, 2];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() < 8 {
+                *handle.get_mut() *= 2;
+                handle.reprocess_current();
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 4, 8, 100, 2, 4, 8]);
+        assert_eq!(v, vec![8, 100, 8]);
+    }
+
+    #[test]
+    fn test_mutate_vec_reprocess_current_then_insert_and_process() {
+        let mut v = vec![1, 2];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.set(3);
+                handle.reprocess_current();
+                handle.insert_and_process(4);
+            }
+        });
+        assert_eq!(visited, vec![1, 3, 4, 2]);
+        assert_eq!(v, vec![3, 4, 2]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_rotate_rest_modulo() {
+        let mut v = vec![0, 1, 2, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.rotate_rest_left(4);
+        assert_eq!(handle.peek_forward_slice(1..), Some(&[2, 3, 1][..]));
+        handle.rotate_rest_right(5);
+        assert_eq!(v, vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_rotate_rest_empty() {
+        let mut v = vec![0];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.rotate_rest_left(3);
+        handle.rotate_rest_right(3);
+        assert_eq!(v, vec![0]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rotate_rest_then_skip() {
+        let mut v = vec![0, 1, 2, 3, 4, 5];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 0 {
+                handle.rotate_rest_left(2);
+                handle.skip_forward(1);
+            } else if *handle.get() == 4 {
+                handle.rotate_rest_right(1);
+                handle.skip_forward(2);
+            }
+        });
+        assert_eq!(visited, vec![0, 4, 1]);
+        assert_eq!(v, vec![0, 3, 4, 2, 5, 1]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Name(String);
+
+    #[test]
+    fn test_mutate_vec_replace_with_non_clone() {
+        let mut v = vec![Name("a".into()), Name("b".into()), Name("c".into())];
+        v.mutate_vec_by_handles(|mut handle| {
+            handle.replace_with(|Name(name)| Name(name.to_uppercase()));
+        });
+        assert_eq!(
+            v,
+            vec![Name("A".into()), Name("B".into()), Name("C".into())]
+        );
+    }
+
+    #[test]
+    fn test_mutate_vec_try_replace_with() {
+        let mut v = vec![Name("a".into()), Name("bb".into()), Name("c".into())];
+        let mut results = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            results.push(handle.try_replace_with(|Name(name)| {
+                if name.len() == 1 {
+                    Ok(Name(name + "!"))
+                } else {
+                    Err(Name(name))
+                }
+            }));
+        });
+        assert_eq!(results, vec![true, false, true]);
+        assert_eq!(
+            v,
+            vec![Name("a!".into()), Name("bb".into()), Name("c!".into())]
+        );
+    }
+
+    #[test]
+    fn test_mutate_vec_replace_with_panic_keeps_others() {
+        let mut v = vec![
+            Name("a".into()),
+            Name("b".into()),
+            Name("c".into()),
+            Name("d".into()),
+        ];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            v.mutate_vec_by_handles(|mut handle| {
+                handle.replace_with(|Name(name)| {
+                    assert!(name != "b", "boom");
+                    Name(name + "!")
+                });
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(
+            v,
+            vec![Name("a!".into()), Name("c".into()), Name("d".into())]
+        );
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted() {
+        let mut v = vec![1, 3, 5, 7];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 1 {
+                assert_eq!(handle.insert_into_rest_sorted(6), 3);
+                assert_eq!(handle.insert_into_rest_sorted(9), 5);
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5, 6, 7, 9]);
+        assert_eq!(seen, vec![1, 3, 5, 6, 7, 9]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted_duplicates() {
+        let mut v = vec![(0, "current"), (2, "a"), (2, "b"), (3, "c")];
+        v.mutate_vec_by_handles(|mut handle| {
+            if handle.get().1 == "current" {
+                assert_eq!(
+                    handle.insert_into_rest_sorted_by_key((2, "new"), |x| x.0),
+                    3
+                );
+            }
+        });
+        assert_eq!(
+            v,
+            vec![(0, "current"), (2, "a"), (2, "b"), (2, "new"), (3, "c")]
+        );
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted_already_past() {
+        let mut v = vec![10, 20, 30];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 20 {
+                // Belongs before the current element, so it ends up right after it.
+                assert_eq!(handle.insert_into_rest_sorted(5), 1);
+            }
+        });
+        assert_eq!(v, vec![10, 20, 5, 30]);
+        assert_eq!(seen, vec![10, 20, 5, 30]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted_after_skip() {
+        let mut v = vec![0, 10, 20, 30];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 0 {
+                handle.skip_forward(1);
+                // Lands among the skipped elements, so it is not processed.
+                assert_eq!(handle.insert_into_rest_sorted_by(5, Ord::cmp), 1);
+                // Lands after them, so it is.
+                assert_eq!(handle.insert_into_rest_sorted_by(25, Ord::cmp), 4);
+            }
+        });
+        assert_eq!(v, vec![0, 5, 10, 20, 25, 30]);
+        assert_eq!(seen, vec![0, 20, 25, 30]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted_and_skip() {
+        let mut v = vec![10, 30, 50, 70];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                10 => {
+                    // Lands among the elements still to process, which are processed around it.
+                    assert_eq!(handle.insert_into_rest_sorted_and_skip(60), 3);
+                    // Lands right at the next element to process.
+                    assert_eq!(handle.insert_into_rest_sorted_and_skip(20), 1);
+                }
+                30 => {
+                    // Belongs before the current element, so it ends up right after it.
+                    assert_eq!(handle.insert_into_rest_sorted_and_skip_by_key(5, |x| *x), 1);
+                }
+                _ => {}
+            }
+        });
+        assert_eq!(v, vec![10, 20, 30, 5, 50, 60, 70]);
+        assert_eq!(seen, vec![10, 30, 50, 70]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_insert_into_rest_sorted_and_skip_moved() {
+        let mut v = vec![1, 2, 4, 8];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                1 => {
+                    assert_eq!(handle.insert_into_rest_sorted_and_skip_by(5, Ord::cmp), 3);
+                    // The skipped element moves with the discard.
+                    handle.discard();
+                }
+                // Lands after the skipped element, so it is processed.
+                2 => assert_eq!(handle.insert_into_rest_sorted(6), 3),
+                _ => {}
+            }
+        });
+        assert_eq!(v, vec![2, 4, 5, 6, 8]);
+        assert_eq!(seen, vec![1, 2, 4, 6, 8]);
+
+        // Handles created directly can still skip it when it lands right at the next element.
+        let mut v = vec![1, 3];
+        let mut state = HandleState::new();
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(handle.insert_into_rest_sorted_and_skip(2), 1);
+        assert_eq!(state.position(), 2);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_forward_saturates() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            handle.skip_forward(usize::MAX);
+            handle.insert_and_skip(10);
+            handle.insert_and_skip_vec(vec![20, 30]);
+            handle.skip_forward(1);
+            handle.set(0);
+        });
+        assert_eq!(seen, vec![1]);
+        assert_eq!(v, vec![0, 20, 30, 10, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_forward_does_not_wrap() {
+        // Previously, `1 + 1 + usize::MAX` wrapped around to 1 in release builds, processing the vector again from the second element.
+        let mut v = vec![1, 2, 3];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            handle.skip_forward(1);
+            handle.skip_forward(usize::MAX);
+        });
+        assert_eq!(seen, vec![1]);
+
+        let mut state = HandleState::new();
+        while let Some(mut handle) = VecMutationHandle::new(&mut v, &mut state) {
+            handle.skip_forward(usize::MAX - 1);
+            handle.insert_and_skip(0);
+        }
+        assert_eq!(state.position(), usize::MAX);
+        assert_eq!(v, vec![1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_reprocess_then_discard_first() {
+        let mut v = vec![1, 2, 3];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.reprocess_current();
+                handle.discard();
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(v, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_handle_state_stop_and_inspect_position() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut state = HandleState::new();
+        while let Some(handle) = VecMutationHandle::new(&mut v, &mut state) {
+            if *handle.get() == 3 {
+                handle.stop_iteration();
+            }
+        }
+        assert!(state.is_stopped());
+        assert_eq!(state.position(), 3);
+        assert!(VecMutationHandle::new(&mut v, &mut state).is_none());
+
+        let mut state = HandleState::new();
+        while let Some(handle) = VecMutationHandle::new(&mut v, &mut state) {
+            if *handle.get() == 3 {
+                handle.discard_and_stop_iteration();
+            }
+        }
+        assert!(state.is_stopped());
+        assert_eq!(state.position(), 2);
+        assert_eq!(v, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_handle_state_not_stopped_at_end() {
+        let mut v = vec![1, 2, 3];
+        let mut state = HandleState::new();
+        while let Some(mut handle) = VecMutationHandle::new(&mut v, &mut state) {
+            handle.skip_forward(usize::MAX);
+        }
+        assert!(!state.is_stopped());
+        assert_eq!(state.position(), usize::MAX);
+    }
+
+    #[test]
+    fn test_handle_state_reset_second_pass() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = HandleState::new();
+        while let Some(handle) = VecMutationHandle::new(&mut v, &mut state) {
+            if *handle.get() % 2 == 0 {
+                handle.discard_rest();
+            }
+        }
+        assert!(state.is_stopped());
+        assert_eq!(state.position(), 1);
+        assert_eq!(v, vec![1]);
+
+        v.extend([5, 6]);
+        state.reset();
+        assert_eq!(state, HandleState::new());
+        let mut seen = vec![];
+        while let Some(mut handle) = VecMutationHandle::new(&mut v, &mut state) {
+            seen.push(*handle.get());
+            handle.set(*handle.get() * 10);
+        }
+        assert_eq!(seen, vec![1, 5, 6]);
+        assert_eq!(v, vec![10, 50, 60]);
+        assert!(!state.is_stopped());
+        assert_eq!(state.position(), 3);
+    }
+
+    #[test]
+    fn test_handle_state_continue_after_stop() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = HandleState::new();
+        while let Some(handle) = VecMutationHandle::new(&mut v, &mut state) {
+            if *handle.get() == 2 {
+                handle.stop_iteration();
+            }
+        }
+        let mut rest = vec![];
+        let mut state = HandleState::starting_at(state.position());
+        while let Some(handle) = VecMutationHandle::new(&mut v, &mut state) {
+            rest.push(*handle.get());
+        }
+        assert_eq!(rest, vec![3, 4]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_vec_mut_handle_new_with_index() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut index = 0;
+        while let Some(handle) = VecMutationHandle::new_with_index(&mut v, &mut index) {
+            if *handle.get() == 2 {
+                handle.discard_and_stop_iteration();
+            }
+        }
+        assert_eq!(index, usize::MAX);
+        assert_eq!(v, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_mutate_vec_range() {
+        let mut v: Vec<usize> = (0..10).collect();
+        let mut seen = vec![];
+        v.mutate_vec_range_by_handles(3..6, |mut handle| {
+            seen.push(*handle.get());
+            handle.set(*handle.get() * 10);
+        });
+        assert_eq!(seen, vec![3, 4, 5]);
+        assert_eq!(v, vec![0, 1, 2, 30, 40, 50, 6, 7, 8, 9]);
+
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, ..=1, |handle| seen.push(*handle.get()));
+        assert_eq!(seen, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_mutate_vec_range_insert_at_boundary() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 1..3, |mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                2 => handle.insert_and_skip(20),
+                3 => handle.insert_and_process(30),
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![2, 3, 30]);
+        assert_eq!(v, vec![1, 2, 20, 3, 30, 4, 5]);
+    }
+
+    #[test]
+    fn test_mutate_vec_range_discard_last_in_range() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 1..3, |mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 2 {
+                handle.append_and_process(6);
+            } else {
+                handle.discard();
+            }
+        });
+        assert_eq!(seen, vec![2, 3, 6]);
+        assert_eq!(v, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_mutate_vec_range_skip_and_stop() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 1..4, |mut handle| {
+            seen.push(*handle.get());
+            handle.skip_forward(5);
+        });
+        assert_eq!(seen, vec![2]);
+
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 1..4, |handle| {
+            seen.push(*handle.get());
+            handle.stop_iteration();
+        });
+        assert_eq!(seen, vec![2]);
+    }
+
+    type RangeRestOp = fn(VecMutationHandle<i32>);
+
+    // Methods working on the rest of the vector, with the vector and the visited elements after calling them on the first element
+    // of a pass over `0..3` of `[0, 1, 2, 3, 4, 5]`.
+    const RANGE_REST_OPS: &[(RangeRestOp, &[i32], &[i32])] = &[
+        (
+            |mut handle| handle.rotate_rest_left(3),
+            &[0, 2, 1, 3, 4, 5],
+            &[0, 2, 1],
+        ),
+        (
+            |mut handle| handle.rotate_rest_right(1),
+            &[0, 2, 1, 3, 4, 5],
+            &[0, 2, 1],
+        ),
+        (
+            |handle| {
+                let _ = handle.discard_rest();
+            },
+            &[3, 4, 5],
+            &[0],
+        ),
+        (
+            |handle| {
+                let _ = handle.split_off_rest();
+            },
+            &[0, 3, 4, 5],
+            &[0],
+        ),
+        (|mut handle| handle.truncate_after(0), &[0, 3, 4, 5], &[0]),
+        (
+            |handle| handle.stop_iteration_and_truncate(),
+            &[3, 4, 5],
+            &[0],
+        ),
+        (
+            |mut handle| {
+                let _ = handle.retain_rest(|t| t % 2 == 0);
+            },
+            &[0, 2, 3, 4, 5],
+            &[0, 2],
+        ),
+        (
+            |mut handle| {
+                let _ = handle.take_forward(10);
+            },
+            &[0, 3, 4, 5],
+            &[0],
+        ),
+        (
+            |mut handle| {
+                let _ = handle.discard_forward_while(|_| true);
+            },
+            &[0, 3, 4, 5],
+            &[0],
+        ),
+        (
+            |mut handle| {
+                let _ = handle.replace_rest(vec![7]);
+            },
+            &[0, 7, 3, 4, 5],
+            &[0, 7],
+        ),
+        (
+            |mut handle| assert!(!handle.swap_with_forward(3)),
+            &[0, 1, 2, 3, 4, 5],
+            &[0, 1, 2],
+        ),
+        (
+            |mut handle| handle.append_and_skip(8),
+            &[0, 1, 2, 8, 3, 4, 5],
+            &[0, 1, 2],
+        ),
+        (
+            |mut handle| {
+                let _ = handle.insert_into_rest_sorted(10);
+            },
+            &[0, 1, 2, 10, 3, 4, 5],
+            &[0, 1, 2, 10],
+        ),
+        (
+            |mut handle| handle.peek_forward_slice_mut(..).unwrap().fill(9),
+            &[9, 9, 9, 3, 4, 5],
+            &[0, 9, 9],
+        ),
+        (
+            |mut handle| {
+                handle.mutate_rest_by_handles(|handle| {
+                    let _ = handle.discard();
+                });
+            },
+            &[0, 3, 4, 5],
+            &[0],
+        ),
+    ];
+
+    #[test]
+    fn test_mutate_vec_range_rest_methods_stay_in_range() {
+        for (i, &(op, expected, expected_seen)) in RANGE_REST_OPS.iter().enumerate() {
+            let mut v = vec![0, 1, 2, 3, 4, 5];
+            let mut seen = vec![];
+            mutate_vec_range_by_handles(&mut v, 0..3, |handle| {
+                seen.push(*handle.get());
+                if seen.len() == 1 {
+                    op(handle);
+                }
+            });
+            assert_eq!(
+                (v.as_slice(), seen.as_slice()),
+                (expected, expected_seen),
+                "{i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_mutate_vec_range_clamped() {
+        let mut v = vec![1, 2, 3];
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 1..100, |handle| seen.push(*handle.get()));
+        assert_eq!(seen, vec![2, 3]);
+
+        let mut seen = vec![];
+        mutate_vec_range_by_handles(&mut v, 5..100, |handle| seen.push(*handle.get()));
+        mutate_vec_range_by_handles(&mut v, (Bound::Excluded(1), Bound::Excluded(2)), |handle| {
+            seen.push(*handle.get());
+        });
+        assert!(seen.is_empty());
+    }
+
+    #[test]
+    fn test_mutate_vec_from_in_chunks() {
+        fn process(mut handle: VecMutationHandle<usize>, stop: bool) {
+            match *handle.get() % 4 {
+                0 if stop => {
+                    handle.discard_and_stop_iteration();
+                    return;
+                }
+                0 => {
+                    handle.discard();
+                    return;
+                }
+                1 => handle.insert_and_skip(100),
+                2 => handle.insert_and_process(3),
+                _ => {}
+            }
+            if stop {
+                handle.stop_iteration();
+            }
+        }
+
+        let original: Vec<usize> = (0..20).collect();
+        let mut full = original.clone();
+        full.mutate_vec_by_handles(|handle| process(handle, false));
+
+        let mut chunked = original;
+        let mut position = 0;
+        let mut chunks = 0;
+        while position < chunked.len() {
+            let mut budget = 10;
+            position = mutate_vec_by_handles_from(&mut chunked, position, |handle| {
+                budget -= 1;
+                process(handle, budget == 0);
+            });
+            chunks += 1;
+        }
+        assert_eq!(chunks, 3);
+        assert_eq!(position, chunked.len());
+        assert_eq!(chunked, full);
+    }
+
+    #[test]
+    fn test_mutate_vec_from_position_after_stop() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let position = mutate_vec_by_handles_from(&mut v, 1, |mut handle| {
+            if *handle.get() == 3 {
+                handle.skip_forward(usize::MAX);
+            }
+        });
+        assert_eq!(position, 5);
+
+        let position = mutate_vec_by_handles_from(&mut v, 0, |handle| {
+            if *handle.get() == 3 {
+                handle.discard_and_stop_iteration();
+            }
+        });
+        assert_eq!(position, 2);
+        assert_eq!(v[position], 4);
+        assert_eq!(mutate_vec_by_handles_from(&mut v, 10, |_| {}), 4);
+    }
+
+    #[test]
+    fn test_mutate_vec_step_by_lanes() {
+        let two = NonZeroUsize::new(2).unwrap();
+        let mut v = vec![0, 100, 1, 101, 2, 102, 3, 103];
+
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, two, 1, |handle| seen.push(*handle.get()));
+        assert_eq!(seen, vec![100, 101, 102, 103]);
+
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, NonZeroUsize::MIN, 0, |handle| {
+            seen.push(*handle.get());
+        });
+        assert_eq!(seen, v);
+    }
+
+    #[test]
+    fn test_mutate_vec_step_by_discard() {
+        let mut v = vec![0, 100, 1, 101, 2, 102, 3, 103];
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, NonZeroUsize::new(2).unwrap(), 0, |handle| {
+            seen.push(*handle.get());
+            if *handle.get() % 2 == 0 {
+                handle.discard();
+            }
+        });
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+        assert_eq!(v, vec![100, 1, 101, 102, 3, 103]);
+    }
+
+    #[test]
+    fn test_mutate_vec_step_by_insert() {
+        let mut v = vec![0, 100, 1, 101, 2, 102];
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, NonZeroUsize::new(2).unwrap(), 0, |mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                // The next visit is a full step after the inserted element, so it stays in the same lane.
+                0 => handle.insert_and_skip(9),
+                // The inserted element is part of the next step, so the next visit is in the other lane.
+                1 => handle.insert_and_process(7),
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![0, 1, 101, 102]);
+        assert_eq!(v, vec![0, 9, 100, 1, 7, 101, 2, 102]);
+    }
+
+    #[test]
+    fn test_mutate_vec_step_by_skip_and_stop() {
+        let three = NonZeroUsize::new(3).unwrap();
+        let mut v: Vec<usize> = (0..20).collect();
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, three, 0, |mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 3 {
+                handle.skip_forward(2);
+            }
+        });
+        assert_eq!(seen, vec![0, 3, 12, 15, 18]);
+
+        let mut seen = vec![];
+        mutate_vec_by_handles_step_by(&mut v, three, 2, |handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 8 {
+                handle.stop_iteration();
+            }
+        });
+        assert_eq!(seen, vec![2, 5, 8]);
+    }
+
+    #[test]
+    fn test_try_mutate_vec_error_stops() {
+        let mut v = vec!["1", "2", "x", "4", "y"];
+        let mut parsed = vec![];
+        let result = v.try_mutate_vec_by_handles(|mut handle| {
+            let n: u32 = handle.get().parse().map_err(|_| *handle.get())?;
+            parsed.push(n);
+            handle.set("ok");
+            Ok(())
+        });
+        assert_eq!(result, Err("x"));
+        assert_eq!(parsed, vec![1, 2]);
+        assert_eq!(v, vec!["ok", "ok", "x", "4", "y"]);
+    }
+
+    #[test]
+    fn test_try_mutate_vec_error_after_mutation() {
+        let mut v = vec![1, 2, 3, 4];
+        let result = try_mutate_vec_by_handles(&mut v, |mut handle| {
+            handle.set(*handle.get() * 10);
+            if *handle.get() == 30 {
+                handle.discard();
+                return Err("three");
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err("three"));
+        assert_eq!(v, vec![10, 20, 4]);
+    }
+
+    #[test]
+    fn test_try_mutate_vec_ok_matches_infallible() {
+        let mut tried = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        let mut infallible = tried.clone();
+
+        let result: Result<(), ()> = tried.try_mutate_vec_by_handles(|elem| {
+            if *elem.get() > 10 {
+                elem.stop_iteration();
+            } else {
+                elem.discard();
+            }
+            Ok(())
+        });
+        infallible.mutate_vec_by_handles(|elem| {
+            if *elem.get() > 10 {
+                elem.stop_iteration();
+            } else {
+                elem.discard();
+            }
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(tried, infallible);
+        assert_eq!(tried, vec![11, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_mutate_vec_control_break_on_first_match() {
+        let mut v = vec![1, 2, 13, 4, 15];
+        let mut seen = vec![];
+        let found = v.mutate_vec_by_handles_control(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() > 10 {
+                return ControlFlow::Break(handle.replace(0));
+            }
+            ControlFlow::Continue(())
+        });
+        assert_eq!(found, Some(13));
+        assert_eq!(seen, vec![1, 2, 13]);
+        assert_eq!(v, vec![1, 2, 0, 4, 15]);
+    }
+
+    #[test]
+    fn test_mutate_vec_control_break_after_discard() {
+        let mut v = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        let found = mutate_vec_by_handles_control(&mut v, |handle| {
+            if *handle.get() > 10 {
+                ControlFlow::Break(handle.discard())
+            } else {
+                handle.discard();
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(found, Some(11));
+        assert_eq!(v, vec![1, 5, 7]);
+    }
+
+    #[test]
+    fn test_mutate_vec_control_never_break() {
+        let mut v = vec![1, 2, 3];
+        let found: Option<()> = v.mutate_vec_by_handles_control(|mut handle| {
+            handle.set(*handle.get() * 2);
+            if *handle.get() == 4 {
+                handle.insert_and_skip(5);
+            }
+            ControlFlow::Continue(())
+        });
+        assert_eq!(found, None);
+        assert_eq!(v, vec![2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_mutate_vec_with_state_count() {
+        let mut my_vec = vec![2, 3, 4, 5, 6, 7, 1];
+
+        let my_count = my_vec.mutate_vec_by_handles_with_state(0, |my_count, mut elem| {
+            let val = *elem.get();
+            *my_count += val;
+
+            if val > 6 {
+                elem.discard();
+            } else if val < 3 {
+                let x = elem.peek_forward_slice(0..).unwrap().len();
+                elem.set(x);
+            } else if val == 4 {
+                elem.insert_and_process(7);
+            }
+        });
+
+        assert_eq!(my_count, 35);
+        assert_eq!(my_vec, vec![7, 3, 4, 5, 6, 1]);
+    }
+
+    #[test]
+    fn test_mutate_vec_with_state_records_discards() {
+        #[derive(Default)]
+        struct Discards {
+            elements: Vec<i32>,
+            kept: usize,
+        }
+
+        fn discard_negative(state: &mut Discards, handle: VecMutationHandle<i32>) {
+            if *handle.get() < 0 {
+                state.elements.push(handle.discard());
+            } else {
+                state.kept += 1;
+            }
+        }
+
+        let mut v = vec![1, -2, 3, -4, -5, 6];
+        let discards =
+            mutate_vec_by_handles_with_state(&mut v, Discards::default(), discard_negative);
+        assert_eq!(discards.elements, vec![-2, -4, -5]);
+        assert_eq!(discards.kept, 3);
+        assert_eq!(v, vec![1, 3, 6]);
+    }
+
+    #[test]
+    fn test_mutate_vec_enumerated() {
+        let mut v = vec![10, 20, 30, 40, 50];
+        let mut observed = vec![];
+        v.mutate_vec_by_handles_enumerated(|index, mut handle| {
+            observed.push((index, *handle.get()));
+            match *handle.get() {
+                20 => {
+                    handle.discard();
+                }
+                30 => handle.insert_and_process(35),
+                40 => handle.insert_and_skip(45),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            observed,
+            vec![(0, 10), (1, 20), (1, 30), (2, 35), (3, 40), (5, 50)]
+        );
+        assert_eq!(v, vec![10, 30, 35, 40, 45, 50]);
+    }
+
+    #[test]
+    fn test_mutate_vec_enumerated_matches_peek() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut len = v.len();
+        mutate_vec_by_handles_enumerated(&mut v, |index, handle| {
+            assert_eq!(index + handle.peek_forward_slice(0..).unwrap().len(), len);
+            if *handle.get() % 2 == 0 {
+                handle.discard();
+                len -= 1;
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_mutate_vec_collecting_vec_example() {
+        let mut my_vecs = vec![
+            vec![1, 2, 3, 4, 5],
+            vec![1, 2, 3],
+            vec![1, 2, 3, 13],
+            vec![5],
+        ];
+
+        let deleted_vecs = mutate_vec_by_handles_collecting(&mut my_vecs, |mut handle| {
+            if handle.get().len() != 5 {
+                handle.get_mut().resize(5, 0);
+            }
+
+            if handle.get().contains(&13) {
+                handle.discard_into_sink();
+            }
+        });
+
+        assert_eq!(deleted_vecs, vec![vec![1, 2, 3, 13, 0]]);
+        assert_eq!(
+            my_vecs,
+            vec![
+                vec![1, 2, 3, 4, 5],
+                vec![1, 2, 3, 0, 0],
+                vec![5, 0, 0, 0, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mutate_vec_collecting_order_and_stop() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7];
+        let discarded =
+            mutate_vec_by_handles_collecting(&mut v, |mut handle| match *handle.get() {
+                2 | 30 => handle.discard_into_sink(),
+                3 => handle.insert_and_process(30),
+                4 => {
+                    handle.discard();
+                }
+                6 => handle.discard_into_sink_and_stop_iteration(),
+                _ => {}
+            });
+        assert_eq!(discarded, vec![2, 30, 6]);
+        assert_eq!(v, vec![1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_discard_into_sink_without_sink() {
+        let mut v = vec![1, 2, 3];
+        v.mutate_vec_by_handles(|handle| {
+            if *handle.get() == 2 {
+                handle.discard_into_sink();
+            }
+        });
+        assert_eq!(v, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_mutate_rest_dedup() {
+        let original: Vec<u32> = (0..200).map(|i| (i * 7919) % 23).collect();
+
+        let mut seen = std::collections::HashSet::new();
+        let reference: Vec<u32> = original
+            .iter()
+            .copied()
+            .filter(|t| seen.insert(*t))
+            .collect();
+
+        let mut v = original;
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            let current = *handle.get();
+            handle.mutate_rest_by_handles(|inner| {
+                if *inner.get() == current {
+                    inner.discard();
+                }
+            });
+        });
+        assert_eq!(v, reference);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_mutate_rest_keeps_outer_pass() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut seen = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.append_and_skip(100);
+                handle.mutate_rest_by_handles(|mut inner| match *inner.get() {
+                    2 => {
+                        inner.discard();
+                    }
+                    3 => inner.insert_and_skip_vec(vec![30, 31]),
+                    _ => {}
+                });
+            }
+        });
+        assert_eq!(seen, vec![1, 3, 30, 31, 4]);
+        assert_eq!(v, vec![1, 3, 30, 31, 4, 100]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_mutate_rest_restores_on_panic() {
+        let mut v = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mutate_vec_by_handles(&mut v, |mut handle| {
+                handle.mutate_rest_by_handles(|inner| {
+                    assert!(*inner.get() != 3, "boom");
+                    inner.discard();
+                });
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_until() {
+        let mut v = vec![1, 10, 2, 3, 20, 4, 5];
+        let mut seen = vec![];
+        let mut found = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            seen.push(*handle.get());
+            found.push(handle.skip_until(|t| *t >= 10));
+        });
+        assert_eq!(seen, vec![1, 10, 20]);
+        assert_eq!(found, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_until_found_immediately_and_at_end() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                1 => assert!(handle.skip_until(|t| *t == 2)),
+                2 => assert!(handle.skip_until(|t| *t == 5)),
+                _ => assert!(!handle.skip_until(|_| true)),
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 5]);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_vec_mut_handle_skip_until_after_insert() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut seen = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.insert_and_process(100);
+                handle.append_and_skip(200);
+                assert!(handle.skip_while(|t| *t < 3));
+                assert!(!handle.skip_until(|t| *t == 200));
+                assert!(handle.skip_until(|t| *t == 100));
+            } else if *handle.get() == 100 {
+                handle.insert_and_skip(101);
+                assert!(handle.skip_until(|t| *t == 101));
+            }
+        });
+        assert_eq!(seen, vec![1, 100, 101, 2, 3, 4]);
+        assert_eq!(v, vec![1, 100, 101, 2, 3, 4, 200]);
+    }
 
     #[test]
-    fn test_vec_mut_handle_new() {
-        let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let handle = VecMutationHandle::new(&mut v, &mut index);
-        assert!(handle.is_some());
-        assert_eq!(handle.unwrap().get(), &1);
+    fn test_mutate_vec_matching() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut seen = vec![];
+        v.mutate_matching_by_handles(
+            |t| t % 2 == 0,
+            |mut handle| {
+                seen.push(*handle.get());
+                match *handle.get() {
+                    2 => handle.insert_and_process_vec(vec![10, 11, 12]),
+                    4 => handle.insert_and_skip(14),
+                    6 => {
+                        handle.discard();
+                    }
+                    8 => handle.stop_iteration(),
+                    _ => handle.set(*handle.get() + 1),
+                }
+            },
+        );
+        assert_eq!(seen, vec![2, 10, 12, 4, 6, 8]);
+        assert_eq!(v, vec![1, 2, 11, 11, 13, 3, 4, 14, 5, 7, 8]);
     }
 
     #[test]
-    fn test_vec_mut_handle_set() {
-        let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let mut handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        handle.set(10);
-        assert_eq!(handle.get(), &10);
+    fn test_mutate_vec_matching_none() {
+        let mut v = vec![1, 3, 5];
+        mutate_matching_by_handles(&mut v, |t| t % 2 == 0, |_| panic!("no matches"));
+        assert_eq!(v, vec![1, 3, 5]);
     }
 
     #[test]
-    fn test_vec_mut_handle_discard() {
+    fn test_stop_iteration_and_discard_rest() {
+        for (stop_at, kept, tail) in [
+            (1, vec![], vec![1, 2, 3, 4]),
+            (3, vec![1, 2], vec![3, 4]),
+            (4, vec![1, 2, 3], vec![4]),
+        ] {
+            let mut v = vec![1, 2, 3, 4];
+            let mut rest = vec![];
+            let mut visited = 0;
+            mutate_vec_by_handles(&mut v, |handle| {
+                visited += 1;
+                if *handle.get() == stop_at {
+                    rest = handle.stop_iteration_and_discard_rest();
+                }
+            });
+            assert_eq!(v, kept);
+            assert_eq!(rest, tail);
+            assert_eq!(visited, stop_at);
+        }
+    }
+
+    #[test]
+    fn test_stop_iteration_and_truncate_and_keep_rest() {
+        for (stop_at, kept) in [(1, vec![]), (3, vec![1, 2]), (4, vec![1, 2, 3])] {
+            let mut v = vec![1, 2, 3, 4];
+            let mut summary = mutate_vec_by_handles_summarized(&mut v, |handle| {
+                if *handle.get() == stop_at {
+                    handle.stop_iteration_and_truncate();
+                }
+            });
+            assert_eq!(v, kept);
+            assert!(summary.stopped);
+            assert_eq!(summary.discarded, 5 - stop_at);
+
+            let mut v = vec![1, 2, 3, 4];
+            summary = mutate_vec_by_handles_summarized(&mut v, |handle| {
+                if *handle.get() == stop_at {
+                    handle.stop_iteration_and_keep_rest();
+                }
+            });
+            assert_eq!(v, vec![1, 2, 3, 4]);
+            assert_eq!((summary.visited, summary.discarded), (stop_at, 0));
+        }
+    }
+
+    #[test]
+    fn test_split_current_and_rest_merges_strings() {
+        let mut v: Vec<String> = ["ab", "cd", "", "ef", "gh"].map(String::from).to_vec();
+        let mut seen = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            let (current, rest) = handle.split_current_and_rest();
+            seen.push((current.clone(), rest.len()));
+            if handle.get() == "cd" {
+                let (current, rest) = handle.split_current_and_rest_mut();
+                current.push_str(&std::mem::take(&mut rest[1]));
+                rest[2].push('!');
+            }
+        });
+        assert_eq!(v, ["ab", "cdef", "", "", "gh!"]);
+        assert_eq!(
+            seen,
+            [("ab", 4), ("cd", 3), ("", 2), ("", 1), ("gh!", 0)].map(|(s, n)| (String::from(s), n))
+        );
+    }
+
+    #[test]
+    fn test_transactional_rollback() {
+        let original: Vec<String> = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+        let mut v = original.clone();
+        let mut visited = 0;
+        let result = mutate_vec_by_handles_transactional(&mut v, |mut handle| {
+            visited += 1;
+            match handle.get().clone().as_str() {
+                "a" => drop(handle.discard()),
+                "b" => handle.insert_and_skip(String::from("x")),
+                "c" => {
+                    handle.get_mut().push('!');
+                    return Err(visited);
+                }
+                _ => handle.set(String::new()),
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err(3));
+        assert_eq!(v, original);
+    }
+
+    #[test]
+    fn test_transactional_success() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut expected = v.clone();
+        let op = |mut handle: VecMutationHandle<i32>| {
+            if handle.get() % 2 == 0 {
+                handle.discard();
+            } else {
+                let x = *handle.get();
+                handle.insert_and_skip(x * 10);
+            }
+        };
+        mutate_vec_by_handles(&mut expected, op);
+        let result: Result<(), ()> = mutate_vec_by_handles_transactional(&mut v, |handle| {
+            op(handle);
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(v, expected);
+        assert_eq!(v, vec![1, 10, 3, 30, 5, 50]);
+    }
+
+    #[test]
+    fn test_first_and_last_match() {
+        let mut v = vec![1, 2, 3, 2, 1];
+        assert!(mutate_first_match_by_handles(
+            &mut v,
+            |t| *t == 1,
+            |handle| {
+                let _ = handle.discard();
+            }
+        ));
+        assert_eq!(v, vec![2, 3, 2, 1]);
+        assert!(mutate_last_match_by_handles(
+            &mut v,
+            |t| *t == 1,
+            |handle| {
+                let _ = handle.discard();
+            }
+        ));
+        assert_eq!(v, vec![2, 3, 2]);
+        assert!(mutate_last_match_by_handles(
+            &mut v,
+            |t| *t == 2,
+            |mut handle| {
+                handle.set(20);
+            }
+        ));
+        assert!(mutate_first_match_by_handles(
+            &mut v,
+            |t| *t == 2,
+            |mut handle| {
+                handle.set(10);
+            }
+        ));
+        assert_eq!(v, vec![10, 3, 20]);
+    }
+
+    #[test]
+    fn test_first_match_none_and_insertions() {
         let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        assert_eq!(handle.discard(), 1);
-        assert_eq!(v, vec![2, 3]);
+        assert!(!mutate_first_match_by_handles(
+            &mut v,
+            |t| *t > 3,
+            |_| panic!("no match")
+        ));
+        assert!(!mutate_last_match_by_handles(
+            &mut Vec::<i32>::new(),
+            |_| true,
+            |_| panic!("empty")
+        ));
+
+        let mut visits = 0;
+        assert!(mutate_first_match_by_handles(
+            &mut v,
+            |t| *t == 2,
+            |mut handle| {
+                visits += 1;
+                handle.insert_and_process_vec(vec![20, 21, 22]);
+            }
+        ));
+        assert_eq!(visits, 1);
+        assert_eq!(v, vec![1, 2, 20, 21, 22, 3]);
     }
 
+    const THREE: NonZeroUsize = NonZeroUsize::new(3).unwrap();
+
     #[test]
-    fn test_vec_mut_handle_insert_and_process() {
+    fn test_set_next_offset_with_insertions() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            match *handle.get() {
+                1 => {
+                    handle.insert_and_skip(10);
+                    handle.set_next_offset(THREE);
+                    assert_eq!(handle.next_offset(), 3);
+                    handle.insert_and_process(11);
+                    handle.insert_and_skip(12);
+                    assert_eq!(handle.next_offset(), 3);
+                }
+                3 => {
+                    handle.set_next_offset(NonZeroUsize::MIN);
+                    handle.insert_and_skip(30);
+                }
+                _ => {}
+            }
+        });
+        // After 1, the vector is [1, 12, 11, 10, 2, 3, ...], so 3 positions after it is 10.
+        assert_eq!(visited, vec![1, 10, 2, 3, 30, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_next_offset_kept_by_stop() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut state = HandleState::starting_at(1);
+        let mut handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        handle.insert_and_skip(20);
+        handle.skip_forward(1);
+        assert_eq!(handle.next_offset(), 3);
+        handle.stop_iteration();
+        assert!(state.is_stopped());
+        // The handle was on index 1, so the pass would have continued 3 positions after it.
+        assert_eq!(state.position(), 1 + 3);
+        assert!(VecMutationHandle::new(&mut v, &mut state).is_none());
+    }
+
+    #[test]
+    fn test_set_next_offset_with_discard() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            assert_eq!(handle.next_offset(), 1);
+            if *handle.get() == 2 {
+                handle.skip_forward(3);
+                handle.set_next_offset(THREE);
+                handle.skip_forward(0);
+                let _ = handle.discard();
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 5, 6]);
+        assert_eq!(v, vec![1, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_drain_rest_matching() {
+        let mut v = vec![1, 2, 4, 6, 3, 8];
+        let mut visited = vec![];
+        let mut drained = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                drained = handle.drain_rest_matching(|t| t % 2 == 0);
+            }
+        });
+        assert_eq!(drained, vec![2, 4, 6, 8]);
+        assert_eq!(v, vec![1, 3]);
+        assert_eq!(visited, vec![1, 3]);
+
+        let mut v = vec![2, 4, 6];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            assert!(handle.drain_rest_matching(|t| t % 2 == 1).is_empty());
+        });
+        assert_eq!(v, vec![2, 4, 6]);
+        assert_eq!(visited, vec![2, 4, 6]);
+
+        let mut v = vec![5, 1, 2, 3];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            if *handle.get() == 5 {
+                let all = handle.drain_rest_matching(|_| true);
+                handle.set(all.iter().sum());
+            }
+        });
+        assert_eq!(v, vec![6]);
+    }
+
+    #[test]
+    fn test_drain_rest_matching_skip_target() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut visited = vec![];
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.skip_forward(2);
+                assert_eq!(
+                    handle.drain_rest_matching(|t| *t == 2 || *t == 4),
+                    vec![2, 4]
+                );
+            }
+            if *handle.get() == 5 {
+                handle.skip_forward(5);
+                assert_eq!(handle.drain_rest_matching(|t| *t == 6), vec![6]);
+            }
+        });
+        // 4 was the target of the skip, so 5 is processed next.
+        assert_eq!(visited, vec![1, 5]);
+        assert_eq!(v, vec![1, 3, 5]);
+        assert_eq!(summary.discarded, 3);
+    }
+
+    #[test]
+    fn test_replace_rest() {
+        let mut v = vec![0, 1, 2, 3];
+        let mut visited = vec![];
+        let mut old = vec![];
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 0 {
+                handle.skip_forward(2);
+                old = handle.replace_rest(vec![10, 11, 12, 13, 14]);
+            } else if *handle.get() == 12 {
+                assert_eq!(handle.replace_rest(vec![]), vec![13, 14]);
+            }
+        });
+        assert_eq!(old, vec![1, 2, 3]);
+        assert_eq!(v, vec![0, 10, 11, 12]);
+        assert_eq!(visited, vec![0, 10, 11, 12]);
+        assert_eq!((summary.discarded, summary.inserted), (5, 5));
+    }
+
+    #[test]
+    fn test_replace_rest_and_stop() {
+        let mut v = vec![0, 1, 2];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                assert_eq!(handle.replace_rest_and_stop(vec![7, 8, 9]), vec![2]);
+            }
+        });
+        assert_eq!(v, vec![0, 1, 7, 8, 9]);
+        assert_eq!(visited, vec![0, 1]);
+    }
+
+    // Expands 1 and 3 twice over, discarding the results of the last expansion.
+    fn expand_nested<S: HandleStorage<Item = i32>>(
+        visited: &mut Vec<(i32, bool)>,
+        mut handle: VecMutationHandle<i32, S>,
+    ) {
+        visited.push((*handle.get(), handle.was_inserted_this_pass()));
+        match *handle.get() {
+            1 => handle.insert_and_process(10),
+            10 => handle.insert_and_process(100),
+            3 => handle.append_and_process(5),
+            5 => handle.insert_and_process(50),
+            2 | 100 => {
+                handle.discard();
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_mutate_vec_was_inserted_this_pass() {
+        type Runner = fn(&mut Vec<i32>, &mut Vec<(i32, bool)>);
+        let expected_visits = vec![
+            (1, false),
+            (10, true),
+            (100, true),
+            (2, false),
+            (3, false),
+            (4, false),
+            (5, true),
+            (50, true),
+        ];
+        let runners: [Runner; 4] = [
+            |v, visited| {
+                HandlePass::new(v)
+                    .track_insertions(true)
+                    .run(|handle| expand_nested(visited, handle));
+            },
+            |v, visited| {
+                HandleCursor::new(v)
+                    .track_insertions(true)
+                    .for_each(|handle| expand_nested(visited, handle));
+            },
+            |v, visited| {
+                let mut cursor = HandleCursor::new(v).track_insertions(true);
+                while let Some(handle) = cursor.next() {
+                    expand_nested(visited, handle);
+                }
+            },
+            |v, visited| {
+                let mut state = PassState::tracking_insertions();
+                mutate_vec_by_handles_resumable(v, &mut state, |handle| {
+                    expand_nested(visited, handle);
+                });
+            },
+        ];
+        for (i, runner) in runners.iter().enumerate() {
+            let mut v = vec![1, 2, 3, 4];
+            let mut visited = vec![];
+            runner(&mut v, &mut visited);
+            assert_eq!(v, vec![1, 10, 3, 4, 5, 50], "runner {i}");
+            assert_eq!(visited, expected_visits, "runner {i}");
+        }
+
+        // Without asking for it, nothing is tracked.
+        let untracked: [Runner; 4] = [
+            |v, visited| mutate_vec_by_handles(v, |handle| expand_nested(visited, handle)),
+            |v, visited| {
+                mutate_vec_by_handles_compacting(v, |handle| expand_nested(visited, handle));
+            },
+            |v, visited| {
+                mutate_vec_by_handles_observed(v, |_| {}, |handle| expand_nested(visited, handle));
+            },
+            |v, visited| {
+                mutate_vec_by_handles_with_origin(v, |_, handle| expand_nested(visited, handle));
+            },
+        ];
+        for (i, runner) in untracked.iter().enumerate() {
+            let mut v = vec![1, 2, 3, 4];
+            let mut visited = vec![];
+            runner(&mut v, &mut visited);
+            assert_eq!(v, vec![1, 10, 3, 4, 5, 50], "runner {i}");
+            assert!(visited.iter().all(|&(_, inserted)| !inserted), "runner {i}");
+        }
+    }
+
+    #[test]
+    fn test_mutate_vec_was_inserted_this_pass_moved() {
         let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let mut handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        handle.insert_and_process(10);
+        let mut visited = vec![];
+        HandlePass::new(&mut v)
+            .track_insertions(true)
+            .run(|mut handle| {
+                visited.push((*handle.get(), handle.was_inserted_this_pass()));
+                if *handle.get() == 1 {
+                    handle.insert_and_skip(10);
+                    handle.swap_with_forward(1);
+                    assert!(handle.was_inserted_this_pass());
+                    handle.swap_with_forward(1);
+                    handle.mutate_rest_by_handles(|mut inner| {
+                        if *inner.get() == 2 {
+                            inner.insert_and_skip(20);
+                        }
+                    });
+                    handle.rotate_rest_left(1);
+                }
+            });
+        assert_eq!(v, vec![1, 2, 20, 3, 10]);
+        assert_eq!(
+            visited,
+            vec![(1, false), (20, true), (3, false), (10, true)]
+        );
+
+        // Handles created directly don't track insertions.
+        let mut state = HandleState::new();
+        VecMutationHandle::new(&mut v, &mut state)
+            .unwrap()
+            .insert_and_process(0);
+        let handle = VecMutationHandle::new(&mut v, &mut state).unwrap();
+        assert_eq!(*handle.get(), 0);
+        assert!(!handle.was_inserted_this_pass());
+    }
+
+    #[test]
+    fn test_mutate_vec_accumulate_errors() {
+        let mut v = vec![1, -2, 3, 4, -5, 6, -7, 8];
+        let result = mutate_vec_by_handles_accumulate_errors(&mut v, |mut handle| {
+            let x = *handle.get();
+            if x == 3 {
+                handle.discard();
+                return Ok(());
+            }
+            if x == 4 {
+                handle.insert_and_skip(40);
+            }
+            if x < 0 {
+                handle.set(0);
+                return Err(format!("negative {x}"));
+            }
+            Ok(())
+        });
+        assert_eq!(v, vec![1, 0, 4, 40, 0, 6, 0, 8]);
+        assert_eq!(
+            result,
+            Err(vec![
+                (1, "negative -2".to_string()),
+                (4, "negative -5".to_string()),
+                (6, "negative -7".to_string()),
+            ])
+        );
+
+        let result: Result<(), Vec<(usize, ())>> =
+            mutate_vec_by_handles_accumulate_errors(&mut v, |_| Ok(()));
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_mutate_vec_accumulate_errors_stop() {
+        let mut v = vec![-1, 2, -3, -4];
+        let result = mutate_vec_by_handles_accumulate_errors(&mut v, |handle| {
+            let x = *handle.get();
+            if x == -3 {
+                handle.stop_iteration();
+            }
+            if x < 0 {
+                Err(x)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err(vec![(0, -1), (2, -3)]));
+    }
+
+    #[test]
+    fn test_mutate_vec_discard_forward_while() {
+        let mut v = vec![1, 0, 0, 0, 2, 3, 0, 4, 0, 0];
+        let mut visited = vec![];
+        let mut runs = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            runs.push(handle.discard_forward_while(|x| *x == 0).len());
+        });
+        assert_eq!(v, vec![1, 2, 3, 4]);
+        assert_eq!(visited, vec![1, 2, 3, 4]);
+        // The runs after 2 and 3 are empty, and the run after 4 reaches the end of the vector.
+        assert_eq!(runs, vec![3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_mutate_vec_discard_current_and_forward_while() {
+        let mut v = vec![1, 5, 6, 7, 2, 5, 3];
+        let mut visited = vec![];
+        let mut discarded = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            match *handle.get() {
+                1 => handle.insert_and_skip(10),
+                5 => discarded.push(handle.discard_current_and_forward_while(|x| *x > 4)),
+                _ => {}
+            }
+        });
         assert_eq!(v, vec![1, 10, 2, 3]);
-        assert_eq!(index, 1);
+        assert_eq!(visited, vec![1, 5, 2, 5, 3]);
+        assert_eq!(discarded, vec![vec![5, 6, 7], vec![5]]);
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[derive(Default)]
+    struct CountingAllocator(std::cell::Cell<usize>);
+
+    // SAFETY: Every call is forwarded to the global allocator.
+    #[cfg(feature = "allocator_api")]
+    #[allow(unsafe_code)]
+    unsafe impl std::alloc::Allocator for CountingAllocator {
+        fn allocate(
+            &self,
+            layout: std::alloc::Layout,
+        ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+            self.0.set(self.0.get() + 1);
+            std::alloc::Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+            // SAFETY: Allocated by `Global` in `allocate`, with the same layout.
+            unsafe { std::alloc::Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[cfg(feature = "allocator_api")]
+    fn allocator_scenario<S: HandleStorage<Item = i32>>(mut handle: VecMutationHandle<i32, S>) {
+        match *handle.get() {
+            1 => handle.insert_and_process_iter([10, 11]),
+            2 => {
+                let _ = handle.discard();
+            }
+            3 => handle.set(30),
+            4 => {
+                let _ = handle.take_forward(1);
+            }
+            6 => handle.append_and_skip(60),
+            7 => handle.replace_with(|x| x * 100),
+            11 => {
+                let _ = handle.retain_rest(|x| x % 2 == 1);
+            }
+            _ => {}
+        }
     }
 
     #[test]
-    fn test_vec_mut_handle_skip_forward() {
-        let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let mut handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        handle.skip_forward(2);
-        assert_eq!(index, 3);
+    #[cfg(feature = "allocator_api")]
+    fn test_mutate_vec_custom_allocator() {
+        let allocator = CountingAllocator::default();
+        let mut v = Vec::new_in(&allocator);
+        v.extend(1..=9);
+        let mut expected: Vec<i32> = (1..=9).collect();
+
+        mutate_vec_by_handles(&mut v, allocator_scenario);
+        mutate_vec_by_handles(&mut expected, allocator_scenario);
+        assert_eq!(v.as_slice(), expected.as_slice());
+        assert_eq!(expected, vec![1, 10, 11, 30, 5, 700, 9]);
+        assert!(allocator.0.get() > 0);
+
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 30 {
+                handle.stop_iteration();
+            }
+        });
+        assert_eq!(visited, vec![1, 10, 11, 30]);
+    }
+
+    // Lines starting with a space continue the line before them. Lines starting with `#` are comments, and dropped.
+    fn join_lines_backward(lines: &mut Vec<String>) {
+        mutate_vec_by_handles(lines, |mut handle| {
+            if handle.get().starts_with('#') {
+                handle.discard();
+            } else if handle.get().starts_with(' ') {
+                if let Some((prev, current)) = handle.split_prev_current_mut() {
+                    prev.push_str(current.trim_start());
+                    handle.discard();
+                }
+            }
+        });
+    }
+
+    fn join_lines_forward(lines: &mut Vec<String>) {
+        mutate_vec_by_handles(lines, |mut handle| {
+            if handle.get().starts_with('#') {
+                handle.discard();
+                return;
+            }
+            loop {
+                match handle.peek_forward_slice(1) {
+                    Some(next) if next.starts_with('#') => {
+                        let _ = handle.take_forward(1);
+                    }
+                    Some(next) if next.starts_with(' ') => {
+                        handle.merge_with_next(|a, b| a + b.trim_start());
+                    }
+                    _ => break,
+                }
+            }
+        });
     }
 
     #[test]
-    fn test_vec_mut_handle_peek_forward_slice() {
-        let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        assert_eq!(handle.peek_forward_slice(1..), Some(&[2, 3][..]));
-        let handle_two = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        assert_eq!(handle_two.peek_forward_slice(1..), Some(&[3][..]));
-        assert_eq!(handle_two.peek_forward_slice(2), None);
+    fn test_mutate_vec_merge_backward() {
+        let original: Vec<String> = [
+            " leading",
+            "a",
+            " b",
+            "# comment",
+            " c",
+            "d",
+            "#",
+            "e",
+            " f",
+            " g",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        let mut backward = original.clone();
+        join_lines_backward(&mut backward);
+        let mut forward = original;
+        join_lines_forward(&mut forward);
+        assert_eq!(backward, forward);
+        assert_eq!(backward, vec![" leading", "abc", "d", "efg"]);
     }
 
     #[test]
-    fn test_vec_mut_handle_peek_forward_slice_mut() {
+    fn test_vec_mut_handle_prev_mut() {
         let mut v = vec![1, 2, 3];
-        let mut index = 0;
-        let mut handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        assert_eq!(handle.peek_forward_slice_mut(1..), Some(&mut [2, 3][..]));
-        handle.peek_forward_slice_mut(1..).unwrap()[1] = 70;
-        assert_eq!(v[2], 70);
+        let mut prevs = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            prevs.push(handle.prev_mut().copied());
+            match *handle.get() {
+                1 => handle.insert_and_skip(10),
+                3 => *handle.prev_mut().unwrap() += 100,
+                _ => {}
+            }
+        });
+        assert_eq!(v, vec![1, 10, 102, 3]);
+        assert_eq!(prevs, vec![None, Some(10), Some(2)]);
     }
 
     #[test]
-    fn test_vec_mut_handle_insert_and_skip() {
+    fn test_vec_mut_handle_skip_to_partition_point() {
+        let mut v = vec![1, 3, 4, 4, 7, 9, 12, 20, 21];
+        let mut visited = vec![];
+        let mut skipped = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            let x = *handle.get();
+            visited.push(x);
+            // Jump to the first element at least twice the current one.
+            skipped.push(handle.skip_to_first_ge(&(x * 2)));
+            // Still skipped along with the elements jumped over.
+            if x == 3 {
+                handle.insert_and_skip(5);
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5, 4, 4, 7, 9, 12, 20, 21]);
+        assert_eq!(visited, vec![1, 3, 7, 20]);
+        // The last jump goes past the end.
+        assert_eq!(skipped, vec![0, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_to_partition_point_unpartitioned() {
+        for rest in [vec![9, 1, 9, 1, 9], vec![1, 1, 1], vec![], vec![9, 9, 1, 1]] {
+            let mut v = vec![0];
+            v.extend(rest.iter().copied());
+            let mut visited = vec![];
+            mutate_vec_by_handles(&mut v, |mut handle| {
+                visited.push(*handle.get());
+                if visited.len() == 1 {
+                    let skipped = handle.skip_to_partition_point(|t| *t < 5);
+                    assert!(skipped <= rest.len());
+                }
+            });
+            assert!(visited.len() <= rest.len() + 1);
+        }
+    }
+
+    #[test]
+    fn test_vec_mut_handle_skip_forward_by_cost() {
+        let mut v: Vec<u64> = vec![10, 3, 2, 0, 5, 4, 1, 0, 0, 6];
+        let mut visited = vec![];
+        let mut consumed = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            match *handle.get() {
+                // Lands exactly on the budget, skipping the 0 after it too.
+                10 => consumed.push(handle.skip_forward_by_cost(5, |t| *t)),
+                // The inserted element is part of the scan.
+                5 => {
+                    handle.insert_and_process(1);
+                    consumed.push(handle.skip_forward_by_cost(3, |t| *t));
+                }
+                // More than the whole tail.
+                4 => consumed.push(handle.skip_forward_by_cost(100, |t| *t)),
+                _ => {}
+            }
+        });
+        assert_eq!(visited, vec![10, 5, 4]);
+        assert_eq!(consumed, vec![5, 1, 7]);
+
+        // Only zero costs, with no budget.
+        let mut v = vec![1, 0, 0, 0];
+        let mut visits = 0;
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visits += 1;
+            assert_eq!(handle.skip_forward_by_cost(0, |_| 0), 0);
+        });
+        assert_eq!(visits, 1);
+    }
+
+    #[test]
+    fn test_mutate_vec_discard_forward_at() {
+        let one = NonZeroUsize::MIN;
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut visited = vec![];
+        let mut discarded = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            match *handle.get() {
+                // The removed element is the next one.
+                1 => discarded.extend(handle.discard_forward_at(one)),
+                // The removed element is before the next one.
+                3 => {
+                    handle.skip_forward(1);
+                    discarded.extend(handle.discard_forward_at(one));
+                }
+                // The removed element is after the next one.
+                5 => discarded.extend(handle.discard_forward_at(NonZeroUsize::new(2).unwrap())),
+                6 => assert_eq!(
+                    handle.discard_forward_at(NonZeroUsize::new(10).unwrap()),
+                    None
+                ),
+                8 => assert_eq!(handle.discard_forward_at(one), None),
+                _ => {}
+            }
+        });
+        assert_eq!(visited, vec![1, 3, 5, 6, 8]);
+        assert_eq!(discarded, vec![2, 4, 7]);
+        assert_eq!(v, vec![1, 3, 5, 6, 8]);
+    }
+
+    #[test]
+    fn test_mutate_vec_replace_forward_at() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut visited = vec![];
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                assert_eq!(
+                    handle.replace_forward_at(NonZeroUsize::new(2).unwrap(), 30),
+                    Some(3)
+                );
+                assert_eq!(
+                    handle.replace_forward_at(NonZeroUsize::new(4).unwrap(), 50),
+                    None
+                );
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 30, 4]);
+        assert_eq!(v, vec![1, 2, 30, 4]);
+        assert_eq!(summary.set_or_replaced, 1);
+    }
+
+    #[test]
+    fn test_mutate_vec_with_change_detection() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut changed = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            changed.push(handle.with_change_detection(|t| *t = (*t / 2) * 2));
+        });
+        assert_eq!(v, vec![0, 2, 2, 4]);
+        assert_eq!(changed, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_mutate_vec_insert_before() {
         let mut v = vec![1, 2, 3];
-        let mut index = 1;
-        let mut handle = VecMutationHandle::new(&mut v, &mut index).unwrap();
-        handle.insert_and_skip(10);
-        assert_eq!(v, vec![1, 2, 10, 3]);
-        assert_eq!(index, 3);
+        let mut visited = vec![];
+        let mut inserted = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 2 {
+                handle.insert_before(10);
+                handle.insert_before(20);
+                handle.insert_and_skip(30);
+                handle.set(200);
+            }
+            inserted.push(handle.was_inserted_this_pass());
+        });
+        assert_eq!(v, vec![1, 10, 20, 200, 30, 3]);
+        assert_eq!(visited, vec![1, 2, 3]);
+        assert_eq!(inserted, vec![false, false, false]);
+    }
+
+    #[test]
+    fn test_mutate_vec_move_current_to() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut even = vec![0];
+        mutate_vec_by_handles(&mut v, |handle| {
+            if *handle.get() % 2 == 0 {
+                handle.move_current_to(&mut even);
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5]);
+        assert_eq!(even, vec![0, 2, 4, 6]);
+    }
+
+    // Every method keeping the handle, and every method consuming it, as functions on handles to the given storage.
+    macro_rules! every_method {
+        ($storage:ty) => {{
+            let mut_ops: &[fn(&mut VecMutationHandle<i32, $storage>)] = &[
+                |handle| *handle.get_mut() += 100,
+                |handle| handle.set(7),
+                |handle| {
+                    let _ = handle.replace(8);
+                },
+                |handle| handle.insert_and_process(9),
+                |handle| handle.insert_and_skip(10),
+                |handle| handle.insert_and_process_iter([11, 12]),
+                |handle| handle.insert_and_skip_iter([13, 14]),
+                |handle| handle.insert_before(17),
+                |handle| handle.append_and_process(15),
+                |handle| handle.append_and_skip(16),
+                |handle| handle.duplicate_and_skip(),
+                |handle| {
+                    let _ = handle.with_change_detection(|t| *t += 1);
+                },
+                |handle| handle.replace_with(|x| x * 2),
+                |handle| {
+                    let _ = handle.try_replace_with(Err);
+                },
+                |handle| {
+                    let _ = handle.merge_with_next(|a, b| a + b);
+                },
+                |handle| {
+                    let _ = handle.take_forward(2);
+                },
+                |handle| {
+                    let _ = handle.discard_forward_while(|t| t % 2 == 0);
+                },
+                |handle| {
+                    let _ = handle.discard_forward_at(NonZeroUsize::new(2).unwrap());
+                },
+                |handle| {
+                    let _ = handle.replace_forward_at(NonZeroUsize::MIN, 3);
+                },
+                |handle| handle.truncate_after(1),
+                |handle| {
+                    let _ = handle.retain_rest(|t| t % 2 == 0);
+                },
+                |handle| {
+                    let _ = handle.insert_into_rest_sorted(4);
+                },
+                |handle| {
+                    let _ = handle.insert_into_rest_sorted_and_skip(4);
+                },
+                |handle| {
+                    let _ = handle.swap_with_forward(1);
+                },
+                |handle| handle.rotate_rest_left(1),
+                |handle| handle.rotate_rest_right(2),
+                |handle| handle.reprocess_current(),
+                |handle| handle.skip_forward(1),
+                |handle| {
+                    let _ = handle.skip_until(|t| *t == 5);
+                },
+                |handle| {
+                    let _ = handle.skip_while(|t| *t < 5);
+                },
+                |handle| {
+                    let _ = handle.skip_to_partition_point(|t| *t < 4);
+                },
+                |handle| {
+                    let _ = handle.skip_forward_by_cost(3, |t| t.unsigned_abs().into());
+                },
+                |handle| {
+                    if let Some(next) = handle.peek_forward_slice_mut(1) {
+                        *next = 0;
+                    }
+                },
+                |handle| {
+                    let (current, rest) = handle.split_current_and_rest_mut();
+                    *current += i32::try_from(rest.len()).unwrap();
+                },
+                |handle| {
+                    handle.mutate_rest_by_handles(|handle| {
+                        let _ = handle.discard();
+                    });
+                },
+            ];
+            let final_ops: &[fn(VecMutationHandle<i32, $storage>)] = &[
+                |_handle| {},
+                |handle| {
+                    let _ = handle.discard();
+                },
+                |handle| handle.discard_into_sink(),
+                |handle| handle.move_current_to(&mut Vec::new()),
+                |handle| handle.defer_current(),
+                |handle| handle.defer_current_and_skip(),
+                |handle| handle.split_current(|x| vec![x, x]),
+                |handle| handle.split_current_and_skip(|x| vec![x, x]),
+                |handle| {
+                    let _ = handle.replace_with_many(vec![1, 2]);
+                },
+                |handle| {
+                    let _ = handle.discard_rest();
+                },
+                |handle| {
+                    let _ = handle.split_off_rest();
+                },
+                |handle| handle.stop_iteration(),
+                |handle| handle.stop_iteration_and_truncate(),
+                |handle| {
+                    let _ = handle.discard_current_and_forward_while(|t| *t < 4);
+                },
+                |handle| {
+                    let _ = handle.discard_and_stop_iteration();
+                },
+                |handle| {
+                    let _ = handle.take_current().into_value();
+                },
+            ];
+            (mut_ops, final_ops)
+        }};
+    }
+
+    // The vector and the visited elements after one combination of methods, indexed by the methods in `every_method`.
+    type MethodPairOutcome = ((usize, usize, usize), Vec<i32>, Vec<i32>);
+
+    // Runs every pair of methods on the same handle followed by every method consuming it, on the handles of the first two
+    // elements visited, with the given runner. With the `debug-invariants` feature, every method also checks the contract of the handle.
+    macro_rules! every_method_pair {
+        ($storage:ty, $run:expr) => {{
+            let (mut_ops, final_ops) = every_method!($storage);
+            let run: fn(&mut Vec<i32>, &mut dyn FnMut(VecMutationHandle<i32, $storage>)) = $run;
+            let mut outcomes: Vec<MethodPairOutcome> = vec![];
+            for (i, first) in mut_ops.iter().enumerate() {
+                for (j, second) in mut_ops.iter().enumerate() {
+                    for (k, last) in final_ops.iter().enumerate() {
+                        let mut v = vec![1, 2, 3, 4, 5, 6];
+                        let mut visited = vec![];
+                        run(&mut v, &mut |mut handle| {
+                            visited.push(*handle.get());
+                            if visited.len() > 2 {
+                                return;
+                            }
+                            first(&mut handle);
+                            let _ = handle.get();
+                            let _ = handle.peek_forward_slice(0..);
+                            second(&mut handle);
+                            let _ = handle.split_current_and_rest();
+                            last(handle);
+                        });
+                        outcomes.push(((i, j, k), v, visited));
+                    }
+                }
+            }
+            outcomes
+        }};
+    }
+
+    fn assert_same_outcomes(
+        runner: &str,
+        outcomes: &[MethodPairOutcome],
+        expected: &[MethodPairOutcome],
+    ) {
+        assert_eq!(outcomes.len(), expected.len());
+        for (outcome, expected) in outcomes.iter().zip(expected) {
+            assert_eq!(outcome, expected, "{runner}");
+        }
+    }
+
+    // Every combination gives exactly the same vector and visits with every storage and runner, and the combinations below, which
+    // are checked by hand, pin down the results themselves.
+    #[test]
+    fn test_every_method_after_every_other_method() {
+        let eager = every_method_pair!(Vec<i32>, |v, op| mutate_vec_by_handles(v, op));
+
+        let find = |i, j, k| {
+            let (_, v, visited) = eager.iter().find(|outcome| outcome.0 == (i, j, k)).unwrap();
+            (v.as_slice(), visited.as_slice())
+        };
+        // `get_mut`, `insert_and_skip` and `discard`: each of the first two elements is replaced by an unvisited 10.
+        assert_eq!(
+            find(0, 4, 1),
+            (
+                [10, 10, 3, 4, 5, 6].as_slice(),
+                [1, 2, 3, 4, 5, 6].as_slice()
+            )
+        );
+        // `insert_and_process` and `reprocess_current`, then nothing: 1 is visited three times, inserting a 9 on the first two.
+        assert_eq!(
+            find(3, 26, 0),
+            (
+                [1, 9, 9, 2, 3, 4, 5, 6].as_slice(),
+                [1, 1, 1, 9, 9, 2, 3, 4, 5, 6].as_slice()
+            )
+        );
+        // `set` twice, then `stop_iteration`: only the first element is visited.
+        assert_eq!(
+            find(1, 1, 11),
+            ([7, 2, 3, 4, 5, 6].as_slice(), [1].as_slice())
+        );
+        // `skip_forward` and `merge_with_next`, then `discard_rest`: 1 and 2 are merged, and discarded along with the rest.
+        assert_eq!(find(27, 14, 9), ([].as_slice(), [1].as_slice()));
+
+        let cursor = every_method_pair!(Vec<i32>, |v, op| {
+            let mut cursor = HandleCursor::new(v);
+            while let Some(handle) = cursor.next() {
+                op(handle);
+            }
+        });
+        assert_same_outcomes("cursor", &cursor, &eager);
+        let compacting = every_method_pair!(CompactingStorage<i32>, |v, op| {
+            mutate_vec_by_handles_compacting(v, op);
+        });
+        assert_same_outcomes("compacting", &compacting, &eager);
+        let origin = every_method_pair!(OriginStorage<i32>, |v, op| {
+            mutate_vec_by_handles_with_origin(v, |_, handle| op(handle));
+        });
+        assert_same_outcomes("origin", &origin, &eager);
     }
 
     #[test]
@@ -494,9 +5733,9 @@ mod tests {
     fn test_mutate_vec_handrolled_complex_break_loop() {
         let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
 
-        let mut my_index = 0;
+        let mut my_state = HandleState::new();
 
-        while let Some(elem) = VecMutationHandle::new(&mut my_vec, &mut my_index) {
+        while let Some(elem) = VecMutationHandle::new(&mut my_vec, &mut my_state) {
             if *elem.get() > 10 {
                 elem.stop_iteration();
             } else {
@@ -511,9 +5750,9 @@ mod tests {
     fn test_mutate_vec_handrolled_complex_break_loop_with_final_discard() {
         let mut my_vec = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
 
-        let mut my_index = 0;
+        let mut my_state = HandleState::new();
 
-        while let Some(mut elem) = VecMutationHandle::new(&mut my_vec, &mut my_index) {
+        while let Some(mut elem) = VecMutationHandle::new(&mut my_vec, &mut my_state) {
             if *elem.get() > 10 {
                 elem.discard_and_stop_iteration();
             } else {
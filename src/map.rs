@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::slice::SliceIndex;
+
+/// A handle to an element of the input of [`map_vec_by_handles`], which emits elements of the output.
+///
+/// The input is consumed as the iteration goes, so handles can take ownership of the current and following elements.
+#[derive(Debug)]
+pub struct MapHandle<'a, T, U> {
+    input: &'a mut VecDeque<T>, // The current element is at the front. Always kept contiguous, for peeks.
+    output: &'a mut Vec<U>,
+    consume: &'a mut usize, // The number of elements to drop from the front of the input afterwards.
+    stopped: &'a mut bool,
+}
+
+impl<T, U> MapHandle<'_, T, U> {
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.input[0] // The runner only creates handles for non-empty input, and taking the current element consumes the handle.
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.input[0]
+    }
+
+    /// "Peek" a reference to a slice of the remaining input, with 0 being the index of the current element. E.g. `1` is the next element,
+    /// and `0..` is a slice of the remaining elements, including this one.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.input.as_slices().0.get(slice)
+    }
+
+    /// Push an element onto the output. May be called any number of times per input element.
+    pub fn emit(&mut self, u: U) {
+        self.output.push(u);
+    }
+
+    /// Take ownership of the current element, and emit `f` applied to it. Converts the element by value, without cloning.
+    /// Afterwards, the iteration continues as usual.
+    pub fn map_current(self, f: impl FnOnce(T) -> U) {
+        // The runner only creates handles for non-empty input, so this always takes the current element.
+        if let Some(current) = self.input.pop_front() {
+            *self.consume -= 1;
+            self.output.push(f(current));
+        }
+    }
+
+    /// Remove up to `n` elements immediately after the current one from the input, and return them in order.
+    /// The iteration continues with whatever follows them.
+    pub fn take_forward(&mut self, n: usize) -> Vec<T> {
+        let end = n.saturating_add(1).min(self.input.len());
+        let taken = self.input.drain(1..end).collect();
+        self.input.make_contiguous();
+        taken
+    }
+
+    /// Skip (and drop) a certain amount of the elements after the current one, without processing them.
+    /// Skipping past the end of the input simply ends the pass.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        *self.consume = self.consume.saturating_add(steps_to_skip);
+    }
+
+    /// Do not process any more elements. The remaining input is dropped.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Consume a vec using index-style looping, producing a vec of another type, where each element may emit any number of output elements.
+///
+/// Like a `flat_map` with lookahead: handles can peek at the remaining input, take ownership of the following elements, skip them,
+/// or stop the iteration. Input elements which are not taken by a handle are dropped once the iteration moves past them,
+/// and any remaining input is dropped when the iteration stops.
+pub fn map_vec_by_handles<T, U>(vec: Vec<T>, mut op: impl FnMut(MapHandle<T, U>)) -> Vec<U> {
+    let mut input = VecDeque::from(vec);
+    let mut output = Vec::new();
+    let mut stopped = false;
+
+    while !stopped && !input.is_empty() {
+        let mut consume = 1;
+        op(MapHandle {
+            input: &mut input,
+            output: &mut output,
+            consume: &mut consume,
+            stopped: &mut stopped,
+        });
+        input.drain(..consume.min(input.len()));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        Word(String),
+        Number(u32),
+    }
+
+    #[test]
+    fn test_map_vec_tokenizer() {
+        let input: Vec<char> = "ab12 c 345de ".chars().collect();
+        let tokens = map_vec_by_handles(input, |mut handle| {
+            let current = *handle.get();
+            if current.is_whitespace() {
+                return;
+            }
+            let run = handle
+                .peek_forward_slice(1..)
+                .unwrap()
+                .iter()
+                .take_while(|c| {
+                    !c.is_whitespace() && c.is_ascii_digit() == current.is_ascii_digit()
+                })
+                .count();
+            let rest = handle.take_forward(run);
+            handle.map_current(|first| {
+                let text: String = std::iter::once(first).chain(rest).collect();
+                match text.parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => Token::Word(text),
+                }
+            });
+        });
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("ab".into()),
+                Token::Number(12),
+                Token::Word("c".into()),
+                Token::Number(345),
+                Token::Word("de".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_vec_emit_skip_stop() {
+        let input = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let output = map_vec_by_handles(input, |mut handle| {
+            let current = *handle.get();
+            match current {
+                1 => {
+                    handle.emit(current);
+                    handle.emit(current * 10);
+                }
+                2 => handle.skip_forward(2),
+                6 => handle.stop_iteration(),
+                _ => handle.emit(current * 100),
+            }
+        });
+        assert_eq!(output, vec![1, 10, 500]);
+    }
+
+    #[test]
+    fn test_map_vec_drops_unconsumed() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let input: Vec<Rc<()>> = (0..5).map(|_| Rc::clone(&counter)).collect();
+        let mut visited = 0;
+        let output: Vec<Rc<()>> = map_vec_by_handles(input, |handle| {
+            visited += 1;
+            match visited {
+                1 => handle.map_current(|rc| rc),
+                2 => {}
+                _ => handle.stop_iteration(),
+            }
+        });
+        assert_eq!(visited, 3);
+        assert_eq!(output.len(), 1);
+        assert_eq!(Rc::strong_count(&counter), 2);
+    }
+}
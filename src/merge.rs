@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+use std::ops::{Deref, DerefMut};
+use std::vec;
+
+use crate::VecMutationHandle;
+
+/// A [`VecMutationHandle`] which can also take items from a second vector, to merge them into the vector of the pass.
+/// Created by [`merge_vec_by_handles`].
+///
+/// Derefs to the handle, so all methods borrowing the handle can be called directly. For the methods consuming it, like
+/// `discard`, take or insert the items first, and then use `into_handle`.
+#[derive(Debug)]
+pub struct MergeHandle<'a, 'b, 'o, T> {
+    handle: VecMutationHandle<'a, 'b, T>,
+    other: &'o mut vec::IntoIter<T>,
+    inserted_after: usize, // The number of items inserted after the current element by `insert_other_after_and_skip`.
+}
+
+impl<'a, 'b, T> MergeHandle<'a, 'b, '_, T> {
+    /// Get a reference to the next item of the other vector, without taking it.
+    #[must_use]
+    pub fn peek_other(&self) -> Option<&T> {
+        self.other.as_slice().first()
+    }
+
+    /// Take the next item of the other vector, without inserting it.
+    pub fn take_other(&mut self) -> Option<T> {
+        self.other.next()
+    }
+
+    /// The number of items of the other vector not taken yet.
+    #[must_use]
+    pub fn other_remaining(&self) -> usize {
+        self.other.len()
+    }
+
+    /// Take the next item of the other vector, and insert it BEFORE the current element, see `VecMutationHandle::insert_before`.
+    /// Returns whether there was an item to insert.
+    pub fn insert_other_before(&mut self) -> bool {
+        self.other
+            .next()
+            .map(|t| self.handle.insert_before(t))
+            .is_some()
+    }
+
+    /// Take the next item of the other vector, and insert it AFTER the current element, without processing it. Items inserted
+    /// this way by the same handle keep the order they were taken in, after each other. Returns whether there was an item to insert.
+    pub fn insert_other_after_and_skip(&mut self) -> bool {
+        let Some(t) = self.other.next() else {
+            return false;
+        };
+        self.handle.insert_and_skip(t);
+        self.inserted_after += 1;
+        // The new item is right after the current element, so move it after the ones inserted before it.
+        if let Some(inserted) = self.handle.peek_forward_slice_mut(1..=self.inserted_after) {
+            inserted.rotate_left(1);
+        }
+        true
+    }
+
+    /// Give up taking items for this element, and get the handle, e.g. to `discard` the element.
+    #[must_use]
+    pub fn into_handle(self) -> VecMutationHandle<'a, 'b, T> {
+        self.handle
+    }
+}
+
+impl<'a, 'b, T> Deref for MergeHandle<'a, 'b, '_, T> {
+    type Target = VecMutationHandle<'a, 'b, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl<T> DerefMut for MergeHandle<'_, '_, '_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), while merging the items
+/// of `other` into it. See [`MergeHandle`].
+///
+/// The items of `other` are taken in order, and only when the closure asks for them. The items not taken when the pass is over,
+/// also if it was stopped early, are appended at the end of the vector. See [`merge_vec_by_handles_leaving_rest`] to get them instead.
+pub fn merge_vec_by_handles<T>(vec: &mut Vec<T>, other: Vec<T>, op: impl FnMut(MergeHandle<T>)) {
+    let rest = merge_vec_by_handles_leaving_rest(vec, other, op);
+    vec.extend(rest);
+}
+
+/// Like [`merge_vec_by_handles`], but returns the items of `other` not taken during the pass, instead of appending them.
+pub fn merge_vec_by_handles_leaving_rest<T>(
+    vec: &mut Vec<T>,
+    other: Vec<T>,
+    mut op: impl FnMut(MergeHandle<T>),
+) -> Vec<T> {
+    let mut other = other.into_iter();
+    crate::mutate_vec_by_handles(vec, |handle| {
+        op(MergeHandle {
+            handle,
+            other: &mut other,
+            inserted_after: 0,
+        });
+    });
+    other.collect()
+}
+
+/// Merge the sorted vector `other` into the sorted vector `vec`, keeping it sorted by `cmp`, like itertools' `merge_by`.
+/// The merge is stable: of equal elements, those of `vec` come first.
+pub fn merge_sorted_by<T>(
+    vec: &mut Vec<T>,
+    other: Vec<T>,
+    mut cmp: impl FnMut(&T, &T) -> Ordering,
+) {
+    merge_vec_by_handles(vec, other, |mut handle| {
+        while handle
+            .peek_other()
+            .is_some_and(|t| cmp(t, handle.get()) == Ordering::Less)
+        {
+            handle.insert_other_before();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_sorted() {
+        let a: Vec<(i32, char)> = [1, 3, 3, 5, 8, 13, 13].map(|x| (x, 'a')).to_vec();
+        let b: Vec<(i32, char)> = [0, 3, 4, 13, 14, 20].map(|x| (x, 'b')).to_vec();
+        let mut expected = [a.clone(), b.clone()].concat();
+        expected.sort_by_key(|&(x, _)| x);
+
+        let mut v = a.clone();
+        merge_sorted_by(&mut v, b.clone(), |x, y| x.0.cmp(&y.0));
+        assert_eq!(v, expected);
+
+        let mut v = vec![];
+        merge_sorted_by(&mut v, b.clone(), |x, y| x.0.cmp(&y.0));
+        assert_eq!(v, b);
+
+        let mut v = a.clone();
+        merge_sorted_by(&mut v, vec![], |x, y| x.0.cmp(&y.0));
+        assert_eq!(v, a);
+    }
+
+    #[test]
+    fn test_merge_selective() {
+        // Merge in the item of `other` with the same index, after the element if it is even, and drop it if odd.
+        let mut v = vec![10, 20, 30, 40];
+        let mut remaining = vec![];
+        merge_vec_by_handles(&mut v, vec![1, 2, 3, 4, 5, 6], |mut handle| {
+            remaining.push(handle.other_remaining());
+            if handle.peek_other().is_some_and(|t| t % 2 == 0) {
+                handle.insert_other_after_and_skip();
+            } else {
+                assert!(handle.take_other().is_some());
+            }
+            if *handle.get() == 40 {
+                handle.insert_other_after_and_skip();
+                handle.insert_other_after_and_skip();
+                assert!(!handle.insert_other_after_and_skip());
+            }
+        });
+        assert_eq!(v, vec![10, 20, 2, 30, 40, 4, 5, 6]);
+        assert_eq!(remaining, vec![6, 5, 4, 3]);
+
+        let mut v = vec![1, 2];
+        let rest = merge_vec_by_handles_leaving_rest(&mut v, vec![7, 8, 9], |mut handle| {
+            handle.insert_other_before();
+            handle.into_handle().stop_iteration();
+        });
+        assert_eq!(v, vec![7, 1, 2]);
+        assert_eq!(rest, vec![8, 9]);
+    }
+}
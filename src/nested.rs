@@ -0,0 +1,199 @@
+use std::slice::SliceIndex;
+
+// Contract:
+// `outer < vec.len()` and `inner < vec[outer].len()`
+// `next` is in the same inner vector, after `inner`, or at the start of the inner vector after it
+// Inner vectors are only removed by this handle when they are at `outer`, after which `next` is at the start of the one following it.
+/// Represents an element of an inner vector of a `Vec<Vec<T>>`, allowing mutation of both levels with that element as a "context".
+/// Created by [`mutate_nested_by_handles`].
+#[derive(Debug)]
+pub struct NestedHandle<'a, 'b, T> {
+    vec: &'a mut Vec<Vec<T>>,
+    outer: usize, // Should not be mutated.
+    inner: usize, // Should not be mutated.
+    next: &'b mut (usize, usize),
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> NestedHandle<'a, 'b, T> {
+    fn new(
+        vec: &'a mut Vec<Vec<T>>,
+        next: &'b mut (usize, usize),
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let (mut outer, mut inner) = *next;
+        while outer < vec.len() && inner >= vec[outer].len() {
+            outer += 1;
+            inner = 0;
+        }
+        if *stopped || outer >= vec.len() {
+            return None;
+        }
+        *next = (outer, inner + 1);
+        Some(NestedHandle {
+            vec,
+            outer,
+            inner,
+            next,
+            stopped,
+        })
+    }
+
+    /// The index of the inner vector of the current element, in the outer vector.
+    #[must_use]
+    pub fn outer_index(&self) -> usize {
+        self.outer
+    }
+
+    /// The index of the current element, in its inner vector.
+    #[must_use]
+    pub fn inner_index(&self) -> usize {
+        self.inner
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.vec[self.outer][self.inner]
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.vec[self.outer][self.inner]
+    }
+
+    /// Assign a new value to the current element.
+    pub fn set(&mut self, t: T) {
+        *self.get_mut() = t;
+    }
+
+    /// "Peek" a reference to a slice of the current inner vector, with 0 being the index of the current element, like
+    /// `VecMutationHandle::peek_forward_slice`. Elements of later inner vectors are not included.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.vec[self.outer][self.inner..].get(slice)
+    }
+
+    /// Remove the current element, and return it as owned. If its inner vector becomes empty, it is removed from the outer vector.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard(self) -> T {
+        let current = self.vec[self.outer].remove(self.inner);
+        if self.vec[self.outer].is_empty() {
+            self.vec.remove(self.outer);
+            *self.next = (self.outer, 0);
+        } else {
+            // Keeps any insertions skipped by this handle skipped.
+            self.next.1 = self.next.1.saturating_sub(1).max(self.inner);
+        }
+        current
+    }
+
+    /// Insert a new element AFTER the current one, in the same inner vector, and process it in the next iteration.
+    pub fn insert_and_process(&mut self, t: T) {
+        self.vec[self.outer].insert(self.inner + 1, t);
+    }
+
+    /// Insert a new element AFTER the current one, in the same inner vector, but do not process it.
+    pub fn insert_and_skip(&mut self, t: T) {
+        self.insert_and_process(t);
+        self.next.1 += 1;
+    }
+
+    /// Remove the whole inner vector of the current element, and return it, current element included.
+    /// The iteration continues with the first element of the inner vector after it.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard_inner_vec(self) -> Vec<T> {
+        *self.next = (self.outer, 0);
+        self.vec.remove(self.outer)
+    }
+
+    /// Do not process any more elements after this one.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate the elements of the inner vectors of a `Vec<Vec<T>>` using index-style looping, visiting them in order across all
+/// inner vectors, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles) on the flattened vector. See [`NestedHandle`].
+///
+/// Inner vectors which become empty by discarding their elements are removed from the outer vector. Inner vectors which are
+/// empty from the start have no elements to visit, so they are kept.
+pub fn mutate_nested_by_handles<T>(vec: &mut Vec<Vec<T>>, mut op: impl FnMut(NestedHandle<T>)) {
+    let mut next = (0, 0);
+    let mut stopped = false;
+
+    while let Some(handle) = NestedHandle::new(vec, &mut next, &mut stopped) {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_discard_empties() {
+        let mut v = vec![vec![1, 2], vec![3], vec![], vec![4, 5, 6], vec![7]];
+        let mut visited = Vec::new();
+        mutate_nested_by_handles(&mut v, |handle| {
+            visited.push((handle.outer_index(), handle.inner_index(), *handle.get()));
+            if *handle.get() % 2 == 1 {
+                handle.discard();
+            }
+        });
+        assert_eq!(v, vec![vec![2], vec![], vec![4, 6]]);
+        assert_eq!(
+            visited,
+            vec![
+                (0, 0, 1),
+                (0, 0, 2),
+                (1, 0, 3),
+                (2, 0, 4),
+                (2, 1, 5),
+                (2, 1, 6),
+                (3, 0, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_insert_and_inner_vec() {
+        let mut v = vec![vec![1, 2], vec![0, 3], vec![4]];
+        let mut visited = Vec::new();
+        mutate_nested_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            match *handle.get() {
+                1 => handle.insert_and_process(10),
+                2 => {
+                    handle.insert_and_skip(20);
+                    handle.discard();
+                }
+                0 => {
+                    assert_eq!(handle.discard_inner_vec(), vec![0, 3]);
+                }
+                _ => {}
+            }
+        });
+        assert_eq!(v, vec![vec![1, 10, 20], vec![4]]);
+        assert_eq!(visited, vec![1, 10, 2, 0, 4]);
+    }
+
+    #[test]
+    fn test_nested_stop() {
+        let mut v = vec![vec![1, 2, 3], vec![4, 5]];
+        let mut visited = Vec::new();
+        mutate_nested_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 2 {
+                handle.set(20);
+                handle.stop_iteration();
+            }
+        });
+        assert_eq!(v, vec![vec![1, 20, 3], vec![4, 5]]);
+        assert_eq!(visited, vec![1, 2]);
+    }
+}
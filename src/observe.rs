@@ -0,0 +1,144 @@
+use crate::driver::PassDriver;
+use crate::VecMutationHandle;
+
+/// An operation done during a pass, given to the observer of [`mutate_vec_by_handles_observed`] as it happens.
+///
+/// Indices are those of the vector at the time of the event, so replaying the events in order on a copy of the original vector
+/// puts every element where the pass did. Operations removing or inserting several elements report one event per element.
+/// Inner passes of `mutate_rest_by_handles` are not observed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HandleEvent {
+    /// A handle was given to the closure, for the element at `index`.
+    Visited {
+        /// The index of the current element.
+        index: usize,
+    },
+    /// The element at `index` was removed, by `discard` or any other method removing elements.
+    Discarded {
+        /// The index the element had right before it was removed.
+        index: usize,
+    },
+    /// An element was inserted at `index`, by any method inserting or appending elements.
+    Inserted {
+        /// The index of the new element, right after it was inserted.
+        index: usize,
+        /// Whether it was inserted to be processed, like with `insert_and_process`, rather than skipped, like with `insert_and_skip`.
+        processed: bool,
+    },
+    /// The element at `index` was given a new value by `set`, `replace`, or `replace_with`.
+    /// Changes done through `get_mut` or `peek_forward_slice_mut` are not reported, as they can't be detected.
+    Replaced {
+        /// The index of the current element.
+        index: usize,
+    },
+    /// `skip_forward` moved the index of the next element to process from `from` forward by `count`.
+    Skipped {
+        /// The index of the next element to process, before skipping.
+        from: usize,
+        /// The number of elements skipped.
+        count: usize,
+    },
+    /// The iteration was stopped, e.g. with `stop_iteration`, while at the element at `index`.
+    Stopped {
+        /// The index of the current element. It may have been removed already, e.g. by `discard_and_stop_iteration`.
+        index: usize,
+    },
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), and tell `observer`
+/// about each operation as it happens. See [`HandleEvent`].
+///
+/// Handles given to the other runners have no observer, and only check for one when reporting, so this costs them nothing more.
+pub fn mutate_vec_by_handles_observed<T>(
+    vec: &mut Vec<T>,
+    mut observer: impl FnMut(HandleEvent),
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    let mut driver = PassDriver::new(vec);
+    while let Some(handle) = driver.next(vec) {
+        op(handle.with_observer(&mut observer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observed_scripted() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut events = Vec::new();
+        mutate_vec_by_handles_observed(
+            &mut v,
+            |event| events.push(event),
+            |mut handle| match *handle.get() {
+                1 => handle.set(10),
+                2 => handle.insert_and_skip(20),
+                3 => {
+                    handle.discard();
+                }
+                4 => handle.insert_and_process(40),
+                40 => handle.skip_forward(1),
+                6 => {
+                    handle.discard_and_stop_iteration();
+                }
+                _ => {}
+            },
+        );
+        assert_eq!(v, vec![10, 2, 20, 4, 40, 5]);
+        assert_eq!(
+            events,
+            vec![
+                HandleEvent::Visited { index: 0 },
+                HandleEvent::Replaced { index: 0 },
+                HandleEvent::Visited { index: 1 },
+                HandleEvent::Inserted {
+                    index: 2,
+                    processed: false
+                },
+                HandleEvent::Visited { index: 3 },
+                HandleEvent::Discarded { index: 3 },
+                HandleEvent::Visited { index: 3 },
+                HandleEvent::Inserted {
+                    index: 4,
+                    processed: true
+                },
+                HandleEvent::Visited { index: 4 },
+                HandleEvent::Skipped { from: 5, count: 1 },
+                HandleEvent::Visited { index: 6 },
+                HandleEvent::Discarded { index: 6 },
+                HandleEvent::Stopped { index: 6 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_observed_bulk_removals() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut events = Vec::new();
+        mutate_vec_by_handles_observed(
+            &mut v,
+            |event| events.push(event),
+            |mut handle| {
+                handle.drain_rest_matching(|x| x % 2 == 0);
+                handle.take_forward(2);
+                handle.stop_iteration();
+            },
+        );
+        assert_eq!(v, vec![1, 7]);
+        assert_eq!(
+            events,
+            vec![
+                HandleEvent::Visited { index: 0 },
+                HandleEvent::Discarded { index: 1 },
+                HandleEvent::Discarded { index: 2 },
+                HandleEvent::Discarded { index: 3 },
+                HandleEvent::Discarded { index: 4 },
+                HandleEvent::Discarded { index: 1 },
+                HandleEvent::Discarded { index: 1 },
+                HandleEvent::Stopped { index: 0 },
+            ]
+        );
+    }
+}
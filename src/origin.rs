@@ -0,0 +1,307 @@
+use std::iter::repeat_n;
+
+use crate::driver::PassDriver;
+use crate::vec_mut_handle_core::sealed::Sealed;
+use crate::{HandleStorage, VecMutationHandle};
+
+/// Where an element visited by [`mutate_vec_by_handles_with_origin`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The element was at this index in the vector before the pass started.
+    Original(usize),
+    /// The element was inserted during the pass.
+    Inserted,
+}
+
+/// Storage used by [`mutate_vec_by_handles_with_origin`].
+///
+/// Keeps the origin of each element in a parallel vector, applying every insertion, removal and move to both.
+#[derive(Debug)]
+pub struct OriginStorage<'v, T> {
+    vec: &'v mut Vec<T>,
+    origins: Vec<Origin>,
+}
+
+impl<'v, T> OriginStorage<'v, T> {
+    fn new(vec: &'v mut Vec<T>) -> Self {
+        let origins = (0..vec.len()).map(Origin::Original).collect();
+        OriginStorage { vec, origins }
+    }
+}
+
+impl<T> Sealed for OriginStorage<'_, T> {}
+
+impl<T> HandleStorage for OriginStorage<'_, T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.vec.get(index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.vec.get_mut(index)
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        let element = HandleStorage::remove(self.vec, index)?;
+        self.origins.remove(index);
+        Some(element)
+    }
+
+    fn insert(&mut self, index: usize, element: T) {
+        self.vec.insert(index, element);
+        self.origins.insert(index, Origin::Inserted);
+    }
+
+    fn insert_iter<I: IntoIterator<Item = T>>(&mut self, index: usize, elements: I) {
+        let len = self.vec.len();
+        self.vec.splice(index..index, elements);
+        let inserted = self.vec.len() - len;
+        self.origins
+            .splice(index..index, repeat_n(Origin::Inserted, inserted));
+    }
+
+    fn remove_range(&mut self, start: usize, end: usize) -> Vec<T> {
+        self.origins.drain(start..end);
+        self.vec.drain(start..end).collect()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.vec.truncate(len);
+        self.origins.truncate(len);
+    }
+
+    fn retain_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, mut f: F) {
+        let mut kept = vec![];
+        self.vec.retain_from(start, |t| {
+            let keep = f(t);
+            kept.push(keep);
+            keep
+        });
+        let mut kept = kept.into_iter();
+        self.origins
+            .extract_if(start.., |_| !kept.next().unwrap_or(true))
+            .for_each(drop);
+    }
+
+    fn extract_from<F: FnMut(&mut T) -> bool>(&mut self, start: usize, mut f: F) -> Vec<T> {
+        let mut extracted = vec![];
+        let extracted_elements = self.vec.extract_from(start, |t| {
+            let extract = f(t);
+            extracted.push(extract);
+            extract
+        });
+        let mut extracted = extracted.into_iter();
+        self.origins
+            .extract_if(start.., |_| extracted.next().unwrap_or(false))
+            .for_each(drop);
+        extracted_elements
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.vec.swap(a, b);
+        self.origins.swap(a, b);
+    }
+
+    fn rotate_range_left(&mut self, start: usize, end: usize, mid: usize) {
+        self.vec[start..end].rotate_left(mid);
+        self.origins[start..end].rotate_left(mid);
+    }
+
+    fn tail(&self, index: usize) -> Option<&[T]> {
+        self.vec.tail(index)
+    }
+
+    fn tail_mut(&mut self, index: usize) -> Option<&mut [T]> {
+        self.vec.tail_mut(index)
+    }
+
+    fn split_at_index(&self, index: usize) -> Option<(&T, &[T])> {
+        self.vec.split_at_index(index)
+    }
+
+    fn split_at_index_mut(&mut self, index: usize) -> Option<(&mut T, &mut [T])> {
+        self.vec.split_at_index_mut(index)
+    }
+
+    fn split_prev_mut(&mut self, index: usize) -> Option<(&mut T, &mut T)> {
+        self.vec.split_prev_mut(index)
+    }
+
+    #[cfg(feature = "unchecked")]
+    unsafe fn get_unchecked(&self, index: usize) -> &T {
+        // SAFETY: The caller guarantees `index < len`, which is the length of `vec`.
+        unsafe { HandleStorage::get_unchecked(&*self.vec, index) }
+    }
+
+    #[cfg(feature = "unchecked")]
+    unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut T {
+        // SAFETY: As for `get_unchecked`.
+        unsafe { HandleStorage::get_unchecked_mut(&mut *self.vec, index) }
+    }
+}
+
+/// Mutate a vec using index-style looping, also passing the origin of the current element to the closure: either the index it had
+/// before the pass started, or that it was inserted during the pass. Useful e.g. for error reporting in terms of the original input.
+///
+/// The origins are tracked through all handle operations, including moves like `swap_with_forward` and `rotate_rest_left`.
+/// Elements rebuilt by value (like with `replace_with` or `merge_with_next`) count as inserted, and elements moved by hand
+/// through `peek_forward_slice_mut` get the origin of the place they are moved to.
+pub fn mutate_vec_by_handles_with_origin<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(Origin, VecMutationHandle<T, OriginStorage<T>>),
+) {
+    let mut storage = OriginStorage::new(vec);
+    let mut driver = PassDriver::new(&storage);
+
+    loop {
+        let origin = driver
+            .upcoming(&storage)
+            .and_then(|position| storage.origins.get(position).copied())
+            .unwrap_or(Origin::Inserted);
+        let Some(handle) = driver.next(&mut storage) else {
+            break;
+        };
+        op(origin, handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_discard_and_insert() {
+        let mut v = vec![10, 20, 30, 40, 50];
+        let mut observed = vec![];
+        mutate_vec_by_handles_with_origin(&mut v, |origin, mut handle| {
+            observed.push((origin, *handle.get()));
+            match *handle.get() {
+                20 | 40 => {
+                    handle.discard();
+                }
+                30 => {
+                    handle.insert_and_process(31);
+                    handle.insert_and_skip(32);
+                }
+                31 => handle.append_and_process(60),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            observed,
+            vec![
+                (Origin::Original(0), 10),
+                (Origin::Original(1), 20),
+                (Origin::Original(2), 30),
+                (Origin::Inserted, 31),
+                (Origin::Original(3), 40),
+                (Origin::Original(4), 50),
+                (Origin::Inserted, 60),
+            ]
+        );
+        assert_eq!(v, vec![10, 30, 32, 31, 50, 60]);
+    }
+
+    #[test]
+    fn test_origin_bulk_operations() {
+        let mut v: Vec<usize> = (0..12).collect();
+        let mut observed = vec![];
+        let mut swapped = false;
+        mutate_vec_by_handles_with_origin(&mut v, |origin, mut handle| {
+            observed.push(origin);
+            match *handle.get() {
+                0 => {
+                    handle.take_forward(2);
+                    handle.insert_and_process_vec(vec![100, 101]);
+                }
+                3 => {
+                    handle.retain_rest(|x| x % 2 == 1);
+                    handle.rotate_rest_left(1);
+                }
+                7 if !swapped => {
+                    swapped = true;
+                    handle.swap_with_forward(1);
+                }
+                11 => handle.replace_with(|x| x * 10),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            observed,
+            vec![
+                Origin::Original(0),
+                Origin::Inserted,
+                Origin::Inserted,
+                Origin::Original(3),
+                Origin::Original(7),
+                Origin::Original(7),
+                Origin::Original(11),
+                Origin::Original(5),
+            ]
+        );
+        assert_eq!(v, vec![0, 100, 101, 3, 9, 7, 110, 5]);
+    }
+
+    #[test]
+    fn test_origin_drain_rest_matching() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut observed = vec![];
+        mutate_vec_by_handles_with_origin(&mut v, |origin, mut handle| {
+            observed.push(origin);
+            if *handle.get() == 1 {
+                assert_eq!(handle.drain_rest_matching(|t| t % 2 == 0), vec![2, 4, 6]);
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5]);
+        assert_eq!(
+            observed,
+            vec![
+                Origin::Original(0),
+                Origin::Original(2),
+                Origin::Original(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_origin_stop_and_skip() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut observed = vec![];
+        mutate_vec_by_handles_with_origin(&mut v, |origin, mut handle| {
+            observed.push(origin);
+            match *handle.get() {
+                1 => handle.skip_forward(2),
+                5 => handle.stop_iteration(),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            observed,
+            vec![
+                Origin::Original(0),
+                Origin::Original(3),
+                Origin::Original(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_origin_defer() {
+        let mut v = vec![1, 2, 3, 4];
+        mutate_vec_by_handles_with_origin(&mut v, |_, mut handle| match *handle.get() {
+            1 => handle.defer(|storage| {
+                assert_eq!(storage.len(), 5);
+                storage.remove(0);
+            }),
+            2 => handle.insert_and_skip(20),
+            3 => handle.stop_iteration(),
+            _ => {}
+        });
+        assert_eq!(v, vec![2, 20, 3, 4]);
+    }
+}
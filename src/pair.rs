@@ -0,0 +1,159 @@
+// Contract:
+// `index + 1 < vec.len()`
+// `next_index >= index`, where `next_index == index` re-pairs the current element with its new successor
+// `vec` may not be mutated at indices smaller than `index`
+/// Represents two adjacent indices in a vector, allowing mutation of the vector with that pair as a "context".
+/// Created by [`mutate_vec_by_adjacent_pairs`].
+#[derive(Debug)]
+pub struct PairHandle<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    index: usize, // The index of the current element. The next element is right after it. Should not be mutated.
+    next_index: &'b mut usize, // The index of the current element of the next pair.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> PairHandle<'a, 'b, T> {
+    fn new(vec: &'a mut Vec<T>, next_index: &'b mut usize, stopped: &'b mut bool) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index.checked_add(1)? >= vec.len() {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(PairHandle {
+            vec,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current element, the first of the pair.
+    #[must_use]
+    pub fn current(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    /// Get a reference to the next element, the second of the pair.
+    #[must_use]
+    pub fn next(&self) -> &T {
+        &self.vec[self.index + 1]
+    }
+
+    /// Get a mutable reference to the current element, the first of the pair.
+    #[must_use]
+    pub fn current_mut(&mut self) -> &mut T {
+        self.pair_mut().0
+    }
+
+    /// Get a mutable reference to the next element, the second of the pair.
+    #[must_use]
+    pub fn next_mut(&mut self) -> &mut T {
+        self.pair_mut().1
+    }
+
+    /// Get mutable references to both the current and the next element at once.
+    #[must_use]
+    pub fn pair_mut(&mut self) -> (&mut T, &mut T) {
+        let (current, rest) = self.vec[self.index..].split_at_mut(1);
+        (&mut current[0], &mut rest[0])
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the next element, and return it as owned. The current element is then paired with its new successor in the next iteration.
+    pub fn discard_next(self) -> T {
+        *self.next_index = self.index;
+        self.vec.remove(self.index + 1)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the current element, and return it as owned. The next element is then paired with its successor in the next iteration.
+    /// The element before the current one is never paired with the next element, as it was already processed.
+    pub fn discard_current(self) -> T {
+        *self.next_index = self.index;
+        self.vec.remove(self.index)
+    }
+
+    /// Insert an element between the current and the next element. The iteration continues with the next element paired with
+    /// its successor, so the inserted element is never the current element of a pair.
+    pub fn insert_between(self, t: T) {
+        self.vec.insert(self.index + 1, t);
+        *self.next_index = self.index + 2;
+    }
+
+    /// Do not process any more pairs.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a vec by looking at pairs of adjacent elements, `(0, 1)`, `(1, 2)` and so on, with both mutably accessible at once.
+///
+/// Vectors with fewer than two elements are left as they are.
+pub fn mutate_vec_by_adjacent_pairs<T>(vec: &mut Vec<T>, mut op: impl FnMut(PairHandle<T>)) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = PairHandle::new(vec, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs_coalesce_intervals() {
+        let mut intervals = vec![
+            (1, 3),
+            (2, 4),
+            (4, 5),
+            (7, 8),
+            (8, 12),
+            (9, 10),
+            (11, 13),
+            (20, 21),
+        ];
+        mutate_vec_by_adjacent_pairs(&mut intervals, |mut handle| {
+            if handle.current().1 >= handle.next().0 {
+                let (current, next) = handle.pair_mut();
+                current.1 = current.1.max(next.1);
+                handle.discard_next();
+            }
+        });
+        assert_eq!(intervals, vec![(1, 5), (7, 13), (20, 21)]);
+    }
+
+    #[test]
+    fn test_pairs_visited() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut seen = vec![];
+        mutate_vec_by_adjacent_pairs(&mut v, |handle| {
+            seen.push((*handle.current(), *handle.next()));
+        });
+        assert_eq!(seen, vec![(1, 2), (2, 3), (3, 4)]);
+
+        let mut single = vec![1];
+        mutate_vec_by_adjacent_pairs(&mut single, |_| panic!("no pairs"));
+        assert_eq!(single, vec![1]);
+    }
+
+    #[test]
+    fn test_pairs_insert_discard_stop() {
+        let mut v = vec![1, 2, 2, 5, 6, 0, 7, 8];
+        let mut seen = vec![];
+        mutate_vec_by_adjacent_pairs(&mut v, |mut handle| {
+            seen.push((*handle.current(), *handle.next()));
+            if *handle.next() == 0 {
+                handle.stop_iteration();
+            } else if handle.current() == handle.next() {
+                handle.discard_current();
+            } else if handle.next() - handle.current() > 1 {
+                *handle.next_mut() *= 10;
+                handle.insert_between(-1);
+            }
+        });
+        assert_eq!(seen, vec![(1, 2), (2, 2), (2, 5), (50, 6), (6, 0)]);
+        assert_eq!(v, vec![1, 2, -1, 50, 6, 0, 7, 8]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::slice::SliceIndex;
+
+/// Represents an element of a vector in a parallel pass, allowing it to be changed in place, with read-only lookahead.
+/// Created by [`par_mutate_vec_by_handles`].
+///
+/// There are no methods for inserting, discarding, skipping or stopping, as the elements are split between threads up front.
+#[derive(Debug)]
+pub struct ParHandle<'a, T> {
+    // Either the chunk being processed, or a copy of its last elements followed by a copy of the lookahead after it.
+    slice: &'a mut [T],
+    index: usize, // Always below `slice.len()`.
+    lookahead: usize,
+}
+
+impl<T> ParHandle<'_, T> {
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.slice[self.index]
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.slice[self.index]
+    }
+
+    /// Set the value of the current element.
+    pub fn set(&mut self, t: T) {
+        self.slice[self.index] = t;
+    }
+
+    /// Replace the value of the current element, returning the old value.
+    #[allow(clippy::must_use_candidate)]
+    pub fn replace(&mut self, t: T) -> T {
+        std::mem::replace(&mut self.slice[self.index], t)
+    }
+
+    /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+    /// Only the current element and the `lookahead` elements after it can be seen, and they have their values from before the pass.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        let end = (self.index + 1)
+            .saturating_add(self.lookahead)
+            .min(self.slice.len());
+        self.slice[self.index..end].get(slice)
+    }
+}
+
+/// Mutate a vec in place from several threads, by giving each element to the closure with read-only lookahead of `lookahead` elements.
+///
+/// Every element sees the elements after it as they were before the pass, like in a sequential pass which only changes the current
+/// element. The order in which elements are visited is not specified. A panic in the closure is propagated once all threads are done.
+pub fn par_mutate_vec_by_handles<T: Clone + Send>(
+    vec: &mut [T],
+    lookahead: usize,
+    op: impl Fn(ParHandle<T>) + Sync,
+) {
+    let threads = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+    par_mutate_in_chunks(vec, vec.len().div_ceil(threads), lookahead, &op);
+}
+
+fn par_mutate_in_chunks<T: Clone + Send>(
+    vec: &mut [T],
+    chunk_len: usize,
+    lookahead: usize,
+    op: &(impl Fn(ParHandle<T>) + Sync),
+) {
+    let chunk_len = chunk_len.max(1);
+    let following: Vec<Vec<T>> = (0..vec.len())
+        .step_by(chunk_len)
+        .map(|start| {
+            let end = (start + chunk_len).min(vec.len());
+            vec[end..end.saturating_add(lookahead).min(vec.len())].to_vec()
+        })
+        .collect();
+
+    std::thread::scope(|scope| {
+        for (chunk, following) in vec.chunks_mut(chunk_len).zip(following) {
+            scope.spawn(move || mutate_chunk(chunk, following, lookahead, op));
+        }
+    });
+}
+
+fn mutate_chunk<T: Clone>(
+    chunk: &mut [T],
+    following: Vec<T>,
+    lookahead: usize,
+    op: &impl Fn(ParHandle<T>),
+) {
+    if following.is_empty() {
+        visit(chunk, chunk.len(), lookahead, op);
+        return;
+    }
+
+    // The lookahead of the first elements stays within the chunk, so they are changed in place.
+    let body = chunk.len().saturating_sub(lookahead);
+    visit(chunk, body, lookahead, op);
+
+    // The rest need the lookahead after the chunk, so they are processed in a copy next to it, and swapped back.
+    let tail = &mut chunk[body..];
+    let mut scratch = tail.to_vec();
+    scratch.extend(following);
+    visit(&mut scratch, tail.len(), lookahead, op);
+    tail.swap_with_slice(&mut scratch[..tail.len()]);
+}
+
+fn visit<T>(slice: &mut [T], count: usize, lookahead: usize, op: &impl Fn(ParHandle<T>)) {
+    for index in 0..count {
+        op(ParHandle {
+            slice: &mut *slice,
+            index,
+            lookahead,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutate_vec_by_handles;
+
+    // Depends on the order of the lookahead, so a wrong element or a changed one gives a different result.
+    fn combine(current: u64, lookahead: &[u64]) -> u64 {
+        lookahead
+            .iter()
+            .fold(current, |acc, t| acc.wrapping_mul(31).wrapping_add(*t))
+    }
+
+    fn sequential(v: &mut Vec<u64>, lookahead: usize) {
+        mutate_vec_by_handles(v, |mut handle| {
+            let rest = handle.peek_forward_slice(1..).unwrap();
+            let new = combine(*handle.get(), &rest[..rest.len().min(lookahead)]);
+            handle.set(new);
+        });
+    }
+
+    fn parallel_op(lookahead: usize) -> impl Fn(ParHandle<u64>) + Sync {
+        move |mut handle| {
+            let window = handle.peek_forward_slice(..).unwrap();
+            assert!(window.len() <= lookahead + 1);
+            let new = combine(window[0], &window[1..]);
+            *handle.get_mut() = new;
+        }
+    }
+
+    #[test]
+    fn test_par_matches_sequential() {
+        let original: Vec<u64> = (0..200).map(|i| i * 7 % 13).collect();
+        for lookahead in [0, 1, 3, 10, 50, 300] {
+            let mut expected = original.clone();
+            sequential(&mut expected, lookahead);
+
+            for chunk_len in [1, 4, 7, 64, 1000] {
+                let mut v = original.clone();
+                par_mutate_in_chunks(&mut v, chunk_len, lookahead, &parallel_op(lookahead));
+                assert_eq!(
+                    v, expected,
+                    "lookahead {lookahead}, chunk length {chunk_len}"
+                );
+            }
+
+            let mut v = original.clone();
+            par_mutate_vec_by_handles(&mut v, lookahead, parallel_op(lookahead));
+            assert_eq!(v, expected, "lookahead {lookahead}");
+        }
+    }
+
+    #[test]
+    fn test_par_set_replace() {
+        let mut v: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut empty: Vec<String> = vec![];
+        par_mutate_in_chunks(&mut v, 3, 2, &|mut handle: ParHandle<String>| {
+            if handle.get().len() == 1 {
+                let old = handle.replace(String::new());
+                handle.set(old + "!");
+            } else {
+                handle.get_mut().push('?');
+            }
+        });
+        par_mutate_vec_by_handles(&mut empty, 2, |_| unreachable!());
+        assert_eq!(v[..2], ["0!", "1!"]);
+        assert_eq!(v[18..], ["18?", "19?"]);
+    }
+}
@@ -0,0 +1,183 @@
+use std::slice::SliceIndex;
+
+/// Whether [`partition_vec_by_handles`] keeps an element in the vector, or extracts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the element in the vector.
+    Keep,
+    /// Move the element to the returned vector of extracted elements.
+    Extract,
+}
+
+// Contract:
+// `index < vec.len()`
+// `next_index > index`
+// Elements are not moved until the pass is over, so the lookahead sees the vector as it was.
+/// Represents an element of a vector being partitioned, allowing it to be looked at along with the elements after it.
+/// Created by [`partition_vec_by_handles`].
+#[derive(Debug)]
+pub struct PartitionHandle<'a, 'b, T> {
+    vec: &'a mut [T],
+    index: usize, // Should not be mutated.
+    next_index: &'b mut usize,
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> PartitionHandle<'a, 'b, T> {
+    fn new(vec: &'a mut [T], next_index: &'b mut usize, stopped: &'b mut bool) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index >= vec.len() {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(PartitionHandle {
+            vec,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    /// Get a mutable reference to the current element. The change is kept whether the element is kept or extracted.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.vec[self.index]
+    }
+
+    /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+    /// As elements are only moved after the pass, these are in their original order.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.vec[self.index..].get(slice)
+    }
+
+    /// Skip a certain amount of elements after this one. The skipped elements are kept.
+    pub fn skip_forward(&mut self, elements_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(elements_to_skip);
+    }
+
+    /// Do not process any more elements after this one. They are kept.
+    /// The decision returned by the closure for this element is still applied.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Split a vector in two, by deciding for each element whether to keep it, with access to the elements after it.
+/// Returns the extracted elements, in their original order, while the vector retains the kept elements, in order.
+/// Elements which are not visited, because of `skip_forward` or `stop_iteration`, are kept.
+///
+/// The decisions are collected first and applied by a single compaction afterwards, so a pass is O(n).
+/// If the closure panics, no elements are moved, but changes made through `get_mut` are kept.
+pub fn partition_vec_by_handles<T>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(PartitionHandle<T>) -> Keep,
+) -> Vec<T> {
+    let mut extract = vec![false; vec.len()];
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = PartitionHandle::new(vec, &mut next_index, &mut stopped) {
+        let index = handle.index;
+        extract[index] = op(handle) == Keep::Extract;
+    }
+
+    let mut extract = extract.into_iter();
+    // `extract_if` visits every element once, in order, and `extract` has one entry per element.
+    vec.extract_if(.., |_| extract.next() == Some(true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    // Extract elements which are larger than the element after them, or equal to the sum of the two after them.
+    fn extracted(rest: &[u64]) -> bool {
+        match *rest {
+            [a, b, c, ..] => a > b || a == b + c,
+            [a, b] => a > b,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_partition_matches_two_pass_reference() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..200 {
+            let len = rng.below(40);
+            let original: Vec<u64> = (0..len).map(|_| rng.below(10)).collect();
+
+            let decisions: Vec<bool> = (0..original.len())
+                .map(|i| extracted(&original[i..]))
+                .collect();
+            let expected_kept: Vec<u64> = original
+                .iter()
+                .zip(&decisions)
+                .filter(|(_, e)| !**e)
+                .map(|(t, _)| *t)
+                .collect();
+            let expected_extracted: Vec<u64> = original
+                .iter()
+                .zip(&decisions)
+                .filter(|(_, e)| **e)
+                .map(|(t, _)| *t)
+                .collect();
+
+            let mut v = original.clone();
+            let out = partition_vec_by_handles(&mut v, |handle| {
+                if extracted(handle.peek_forward_slice(0..).unwrap()) {
+                    Keep::Extract
+                } else {
+                    Keep::Keep
+                }
+            });
+            assert_eq!(v, expected_kept, "{original:?}");
+            assert_eq!(out, expected_extracted, "{original:?}");
+        }
+    }
+
+    #[test]
+    fn test_partition_get_mut_skip_stop() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut visited = vec![];
+        let out = partition_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            *handle.get_mut() *= 10;
+            match *handle.get() {
+                20 => {
+                    handle.skip_forward(1);
+                    Keep::Extract
+                }
+                50 => {
+                    handle.stop_iteration();
+                    Keep::Extract
+                }
+                _ => Keep::Keep,
+            }
+        });
+        assert_eq!(visited, vec![1, 2, 4, 5]);
+        assert_eq!(v, vec![10, 3, 40, 6, 7, 8]);
+        assert_eq!(out, vec![20, 50]);
+    }
+}
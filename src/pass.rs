@@ -0,0 +1,558 @@
+use crate::driver::PassDriver;
+use crate::{HandleState, InsertedPositions, VecMutationHandle};
+
+/// Builder for a pass over a vec by handles, for combining options which would otherwise need their own runner each.
+///
+/// `HandlePass::new(&mut vec).run(op)` is the same as `mutate_vec_by_handles(&mut vec, op)`, but also reports how the pass ended.
+#[derive(Debug)]
+pub struct HandlePass<'v, T> {
+    vec: &'v mut Vec<T>,
+    start: usize,
+    limit_visits: Option<usize>,
+    skip_inserted: bool,
+    track_insertions: bool,
+}
+
+/// How a pass run by [`HandlePass::run`] ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PassReport {
+    /// The index of the element the pass would have continued with, at most the length of the vector.
+    pub position: usize,
+    /// Whether the pass was stopped, e.g. with `stop_iteration`. Reaching the visit limit does not count as stopping.
+    pub stopped: bool,
+    /// The number of handles given to the closure.
+    pub visits: usize,
+}
+
+impl<'v, T> HandlePass<'v, T> {
+    /// Set up a pass over the whole vec, with default options.
+    pub fn new(vec: &'v mut Vec<T>) -> Self {
+        HandlePass {
+            vec,
+            start: 0,
+            limit_visits: None,
+            skip_inserted: false,
+            track_insertions: false,
+        }
+    }
+
+    /// Start the pass at the element at index `start`, like [`mutate_vec_by_handles_from`](crate::mutate_vec_by_handles_from).
+    #[must_use]
+    pub fn start_at(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// End the pass after the closure has been called `limit` times. The report tells where the pass would have continued.
+    #[must_use]
+    pub fn limit_visits(mut self, limit: usize) -> Self {
+        self.limit_visits = Some(limit);
+        self
+    }
+
+    /// Whether `insert_and_process` and `insert_and_process_iter` (and methods built on them, like `duplicate_and_process`)
+    /// should skip the inserted elements, like their `_and_skip` counterparts. Appending is not affected.
+    #[must_use]
+    pub fn skip_inserted(mut self, skip_inserted: bool) -> Self {
+        self.skip_inserted = skip_inserted;
+        self
+    }
+
+    /// Whether to keep track of the elements inserted during the pass, for `was_inserted_this_pass`. This costs time for every
+    /// insertion and removal before inserted elements, so it is off by default, and `was_inserted_this_pass` returns `false`.
+    #[must_use]
+    pub fn track_insertions(mut self, track_insertions: bool) -> Self {
+        self.track_insertions = track_insertions;
+        self
+    }
+
+    /// Run the pass, calling `op` with a handle for each element visited.
+    pub fn run(self, mut op: impl FnMut(VecMutationHandle<T>)) -> PassReport {
+        let mut driver = PassDriver::new(self.vec)
+            .starting_at(self.start)
+            .track_insertions(self.track_insertions);
+        let mut visits = 0;
+
+        while self.limit_visits.is_none_or(|limit| visits < limit) {
+            let Some(handle) = driver.next(self.vec) else {
+                break;
+            };
+            visits += 1;
+            if self.skip_inserted {
+                op(handle.with_insertions_skipped());
+            } else {
+                op(handle);
+            }
+        }
+        driver.finish(self.vec);
+
+        PassReport {
+            position: driver.position().min(self.vec.len()),
+            stopped: driver.is_stopped(),
+            visits,
+        }
+    }
+}
+
+/// The closure of a [`Pass`], as a trait object. Unlike `impl FnMut(VecMutationHandle<T>)`, the lifetimes of the handle must be
+/// spelled out for a trait object, as it has to accept handles of any lifetimes.
+pub type PassFn<'f, T> = dyn for<'a, 'b> FnMut(VecMutationHandle<'a, 'b, T>) + 'f;
+
+/// A pass which owns its closure, to be applied to any number of vectors, like
+/// [`mutate_vec_by_handles`](crate::mutate_vec_by_handles) with the same closure each time.
+///
+/// The closure is boxed, so passes with different closures have the same type, and can be stored together and chosen at runtime.
+/// Passes may borrow from their environment for `'f`, see [`BoxedPass`] for passes which don't.
+pub struct Pass<'f, T> {
+    ops: Vec<Box<PassFn<'f, T>>>, // Run as one full pass each, in order.
+}
+
+/// A [`Pass`] which does not borrow anything, to be stored anywhere.
+pub type BoxedPass<T> = Pass<'static, T>;
+
+impl<'f, T> Pass<'f, T> {
+    /// Create a pass running `op` on each element, like `mutate_vec_by_handles`.
+    pub fn new(op: impl for<'a, 'b> FnMut(VecMutationHandle<'a, 'b, T>) + 'f) -> Self {
+        Pass {
+            ops: vec![Box::new(op)],
+        }
+    }
+
+    /// Combine two passes into one, which runs this pass over the whole vector, and then `other`.
+    #[must_use]
+    pub fn then(mut self, other: Pass<'f, T>) -> Self {
+        self.ops.extend(other.ops);
+        self
+    }
+
+    /// Apply the pass to a vector.
+    pub fn apply(&mut self, vec: &mut Vec<T>) {
+        for op in &mut self.ops {
+            crate::mutate_vec_by_handles(vec, &mut **op);
+        }
+    }
+
+    /// Apply the pass to each of the vectors, in order.
+    pub fn apply_all<'v>(&mut self, vecs: impl IntoIterator<Item = &'v mut Vec<T>>)
+    where
+        T: 'v,
+    {
+        for vec in vecs {
+            self.apply(vec);
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Pass<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pass")
+            .field("passes", &self.ops.len())
+            .finish()
+    }
+}
+
+/// The state of a pass run by [`mutate_vec_by_handles_resumable`], which survives a panic in the closure.
+///
+/// Like [`HandleState`], but it also remembers where the pass ends, so elements appended with `append_and_skip` before the panic
+/// stay outside of the pass when it is resumed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassState {
+    state: HandleState,
+    end: Option<usize>,          // The end of the pass, once it has started.
+    inserted: InsertedPositions, // Indices inserted during the pass, if tracked, and those to be skipped.
+}
+
+impl PassState {
+    /// A state for a pass which has not started yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A state for a pass which keeps track of the elements inserted during the pass, for `was_inserted_this_pass`, across
+    /// calls. See [`HandlePass::track_insertions`].
+    #[must_use]
+    pub fn tracking_insertions() -> Self {
+        let mut state = Self::new();
+        state.inserted.track_all(true);
+        state
+    }
+
+    /// The index of the next element to process. After a panic, this is the element after the one the closure panicked on.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.state.position()
+    }
+
+    /// Whether the pass was stopped, e.g. with `stop_iteration`.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.state.is_stopped()
+    }
+
+    /// Reset the state, for a new pass from the start of the vector. Whether insertions are tracked is kept.
+    pub fn reset(&mut self) {
+        let mut inserted = InsertedPositions::new();
+        inserted.track_all(self.inserted.tracks_all());
+        *self = PassState {
+            inserted,
+            ..Self::new()
+        };
+    }
+}
+
+// Puts the progress of a resumed pass back into its `PassState` when the call returns, also if the closure panics.
+struct Resumed<'s, T> {
+    driver: PassDriver<Vec<T>>,
+    state: &'s mut PassState,
+}
+
+impl<T> Drop for Resumed<'_, T> {
+    fn drop(&mut self) {
+        let (state, end, inserted) = self.driver.take_progress();
+        *self.state = PassState {
+            state,
+            end: Some(end),
+            inserted,
+        };
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), but keeping the state of the
+/// pass in `state`, so it can be resumed after the closure panics.
+///
+/// Calling this again with the same state continues after the element the closure panicked on. Once the pass is over, this does
+/// nothing until the state is reset. Actions scheduled with `defer` are dropped by a panic.
+pub fn mutate_vec_by_handles_resumable<T>(
+    vec: &mut Vec<T>,
+    state: &mut PassState,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) {
+    let end = state.end.unwrap_or(vec.len());
+    let inserted = std::mem::take(&mut state.inserted);
+    let driver = PassDriver::new(vec).resume(state.state, end, inserted);
+    let mut resumed = Resumed { driver, state };
+    while let Some(handle) = resumed.driver.next(vec) {
+        op(handle);
+    }
+}
+
+/// How far a call to [`mutate_vec_by_handles_budgeted`] got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PassProgress {
+    /// The number of handles given to the closure in this call, at most the budget.
+    pub visited: usize,
+    /// Whether the pass is over, either by reaching the end of the vector, or by being stopped, e.g. with `stop_iteration`.
+    pub finished: bool,
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), but visiting at most
+/// `budget` elements per call, starting at the index in `cursor`.
+///
+/// Afterwards, `cursor` holds the index the pass continues with, so calling this again with the same cursor resumes the pass.
+/// Elements appended in one call are visited by the next ones. Only the cursor is kept between calls, see
+/// [`mutate_vec_by_handles_resumable`] for keeping the whole state of the pass.
+pub fn mutate_vec_by_handles_budgeted<T>(
+    vec: &mut Vec<T>,
+    budget: usize,
+    cursor: &mut usize,
+    op: impl FnMut(VecMutationHandle<T>),
+) -> PassProgress {
+    let report = HandlePass::new(vec)
+        .start_at(*cursor)
+        .limit_visits(budget)
+        .run(op);
+    *cursor = report.position;
+    PassProgress {
+        visited: report.visits,
+        finished: report.stopped || report.position >= vec.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pass_default() {
+        let mut v = vec![1, 2, 3];
+        let report = HandlePass::new(&mut v).run(|mut handle| *handle.get_mut() *= 2);
+        assert_eq!(v, vec![2, 4, 6]);
+        assert_eq!(
+            report,
+            PassReport {
+                position: 3,
+                stopped: false,
+                visits: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pass_start_at_and_limit_visits() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut seen = vec![];
+        let report = HandlePass::new(&mut v)
+            .start_at(5)
+            .limit_visits(3)
+            .run(|handle| {
+                seen.push(*handle.get());
+                if *handle.get() == 6 {
+                    handle.discard();
+                }
+            });
+        assert_eq!(seen, vec![5, 6, 7]);
+        assert_eq!(v, vec![0, 1, 2, 3, 4, 5, 7, 8, 9]);
+        assert_eq!(
+            report,
+            PassReport {
+                position: 7,
+                stopped: false,
+                visits: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pass_limit_visits_and_stop_iteration() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let report = HandlePass::new(&mut v)
+            .start_at(2)
+            .limit_visits(5)
+            .run(|handle| {
+                if *handle.get() == 4 {
+                    handle.stop_iteration();
+                }
+            });
+        assert_eq!(
+            report,
+            PassReport {
+                position: 5,
+                stopped: true,
+                visits: 3,
+            }
+        );
+
+        let report = HandlePass::new(&mut v)
+            .limit_visits(0)
+            .run(|_| panic!("no visits"));
+        assert_eq!(report, PassReport::default());
+    }
+
+    #[test]
+    fn test_pass_skip_inserted() {
+        let mut v = vec![1, 2, 3];
+        let mut seen = vec![];
+        let report = HandlePass::new(&mut v)
+            .start_at(1)
+            .skip_inserted(true)
+            .run(|mut handle| {
+                seen.push(*handle.get());
+                handle.insert_and_process(*handle.get() * 10);
+                handle.insert_and_process_iter([0, 0]);
+                handle.insert_and_skip(-1);
+            });
+        assert_eq!(seen, vec![2, 3]);
+        assert_eq!(v, vec![1, 2, -1, 0, 0, 20, 3, -1, 0, 0, 30]);
+        assert_eq!(report.position, 11);
+        assert_eq!(report.visits, 2);
+    }
+
+    #[test]
+    fn test_resumable_after_panic() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut state = PassState::new();
+        let mut seen = vec![];
+        let mut op = |handle: VecMutationHandle<i32>| {
+            seen.push(*handle.get());
+            assert!(*handle.get() != 4, "boom");
+            if *handle.get() % 3 == 0 {
+                handle.discard();
+            } else if *handle.get() == 5 {
+                let mut handle = handle;
+                handle.append_and_skip(50);
+            }
+        };
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mutate_vec_by_handles_resumable(&mut v, &mut state, &mut op);
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![1, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(state.position(), 3);
+        assert!(!state.is_stopped());
+
+        mutate_vec_by_handles_resumable(&mut v, &mut state, &mut op);
+        assert_eq!(v, vec![1, 2, 4, 5, 7, 8, 50]);
+        assert_eq!(state.position(), 6);
+
+        mutate_vec_by_handles_resumable(&mut v, &mut state, &mut op);
+        assert_eq!(seen, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        state.reset();
+        assert_eq!(state, PassState::new());
+    }
+
+    #[test]
+    fn test_resumable_vec_valid_after_replace_with_panic() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut state = PassState::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mutate_vec_by_handles_resumable(&mut v, &mut state, |mut handle| {
+                handle.replace_with(|x| {
+                    assert!(x != 2, "boom");
+                    x * 10
+                });
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![10, 3, 4]);
+
+        mutate_vec_by_handles_resumable(&mut v, &mut state, |mut handle| {
+            handle.replace_with(|x| x * 10);
+        });
+        assert_eq!(v, vec![10, 30, 40]);
+    }
+
+    #[test]
+    fn test_resumable_after_split_and_merge_panics() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut state = PassState::new();
+        let mut op = |mut handle: VecMutationHandle<i32>| match *handle.get() {
+            2 => handle.split_current(|_| panic!("boom")),
+            4 => {
+                handle.merge_with_next(|_, _| panic!("boom"));
+            }
+            _ => handle.set(*handle.get() * 10),
+        };
+
+        for _ in 0..2 {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                mutate_vec_by_handles_resumable(&mut v, &mut state, &mut op);
+            }));
+            assert!(result.is_err());
+        }
+        assert_eq!(v, vec![10, 30, 6]);
+        assert_eq!(state.position(), 2);
+
+        mutate_vec_by_handles_resumable(&mut v, &mut state, &mut op);
+        assert_eq!(v, vec![10, 30, 60]);
+    }
+
+    #[test]
+    fn test_budgeted_matches_single_pass() {
+        let op = |mut handle: VecMutationHandle<i32>| {
+            let x = *handle.get();
+            if x % 3 == 0 {
+                handle.discard();
+            } else if x % 7 == 0 {
+                handle.insert_and_skip(x * 100);
+            } else {
+                handle.set(x * 2);
+            }
+        };
+        let mut expected: Vec<i32> = (0..20).collect();
+        crate::mutate_vec_by_handles(&mut expected, op);
+
+        let mut v: Vec<i32> = (0..20).collect();
+        let mut cursor = 0;
+        let mut progress = vec![];
+        loop {
+            let p = mutate_vec_by_handles_budgeted(&mut v, 5, &mut cursor, op);
+            progress.push((p.visited, cursor));
+            if p.finished {
+                break;
+            }
+        }
+        assert_eq!(v, expected);
+        assert_eq!(progress, vec![(5, 3), (5, 7), (5, 12), (5, 15)]);
+    }
+
+    #[test]
+    fn test_budgeted_stop() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut cursor = 2;
+        let progress = mutate_vec_by_handles_budgeted(&mut v, 100, &mut cursor, |handle| {
+            if *handle.get() == 4 {
+                handle.stop_iteration();
+            }
+        });
+        assert_eq!(
+            progress,
+            PassProgress {
+                visited: 3,
+                finished: true,
+            }
+        );
+        assert_eq!(cursor, 5);
+
+        let progress =
+            mutate_vec_by_handles_budgeted(&mut v, 0, &mut cursor, |_| panic!("no visits"));
+        assert_eq!(progress, PassProgress::default());
+        assert_eq!(cursor, 5);
+    }
+
+    #[test]
+    fn test_boxed_passes_chosen_at_runtime() {
+        fn double(mut handle: VecMutationHandle<i32>) {
+            *handle.get_mut() *= 2;
+        }
+        fn drop_negative(handle: VecMutationHandle<i32>) {
+            if *handle.get() < 0 {
+                handle.discard();
+            }
+        }
+        fn split_large(mut handle: VecMutationHandle<i32>) {
+            if *handle.get() > 10 {
+                let half = *handle.get() / 2;
+                handle.set(half);
+                handle.insert_and_skip(half);
+            }
+        }
+
+        let batch = vec![vec![1, -2, 3], vec![], vec![-1, -1], vec![7, 8, -9, 20]];
+        let configs = [
+            vec!["double"],
+            vec!["drop_negative", "double", "split_large"],
+            vec![],
+        ];
+
+        for config in configs {
+            let mut available: Vec<(&str, BoxedPass<i32>)> = vec![
+                ("double", Pass::new(double)),
+                ("drop_negative", Pass::new(drop_negative)),
+                ("split_large", Pass::new(split_large)),
+            ];
+            let mut pass = Pass::new(|_| {});
+            for name in &config {
+                let index = available.iter().position(|(n, _)| n == name).unwrap();
+                pass = pass.then(available.swap_remove(index).1);
+            }
+            let mut vecs = batch.clone();
+            pass.apply_all(&mut vecs);
+
+            let mut expected = batch.clone();
+            for vec in &mut expected {
+                for name in &config {
+                    match *name {
+                        "double" => crate::mutate_vec_by_handles(vec, double),
+                        "drop_negative" => crate::mutate_vec_by_handles(vec, drop_negative),
+                        _ => crate::mutate_vec_by_handles(vec, split_large),
+                    }
+                }
+            }
+            assert_eq!(vecs, expected);
+        }
+    }
+
+    #[test]
+    fn test_pass_borrowing() {
+        let mut visits = 0;
+        {
+            let mut pass = Pass::new(|_| visits += 1);
+            pass.apply(&mut vec![1, 2, 3]);
+            pass.apply_all([&mut vec![4], &mut vec![5, 6]]);
+        }
+        assert_eq!(visits, 6);
+    }
+}
@@ -0,0 +1,253 @@
+use std::slice::SliceIndex;
+
+use crate::driver::PassDriver;
+use crate::VecMutationHandle;
+
+/// An operation recorded by [`plan_vec_by_handles`]. Indices are positions in the vector at the time of the operation, after all
+/// operations recorded before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedOp<T> {
+    /// The element at `index` was given a new value.
+    Set {
+        /// The position of the element.
+        index: usize,
+        /// The value before.
+        old: T,
+        /// The value after.
+        new: T,
+    },
+    /// The element at `index` was removed.
+    Discard {
+        /// The position of the element.
+        index: usize,
+        /// The removed value.
+        value: T,
+    },
+    /// An element was inserted at `index`.
+    Insert {
+        /// The position of the new element.
+        index: usize,
+        /// The inserted value.
+        value: T,
+        /// Whether the element is processed later in the pass, as with `insert_and_process`.
+        processed: bool,
+    },
+    /// `count` elements after the element at `from` were skipped.
+    Skip {
+        /// The position of the element which skipped forward.
+        from: usize,
+        /// The number of skipped elements.
+        count: usize,
+    },
+    /// The pass was stopped at the element at `at`.
+    Stop {
+        /// The position of the element which stopped the pass.
+        at: usize,
+    },
+}
+
+/// A handle for a dry run of a pass, created by [`plan_vec_by_handles`]. It has the same methods as the corresponding ones of
+/// [`VecMutationHandle`], which change a copy of the vector, so later calls see the changes, and record what they did.
+#[derive(Debug)]
+pub struct PlanHandle<'a, 'b, T> {
+    handle: VecMutationHandle<'a, 'b, T>,
+    log: &'b mut Vec<PlannedOp<T>>,
+}
+
+impl<T: Clone> PlanHandle<'_, '_, T> {
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.handle.get()
+    }
+
+    /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.handle.peek_forward_slice(slice)
+    }
+
+    /// Assign a new value to this element.
+    pub fn set(&mut self, t: T) {
+        let index = self.handle.index();
+        let old = self.handle.replace(t.clone());
+        self.log.push(PlannedOp::Set { index, old, new: t });
+    }
+
+    /// Remove the current element, and return it as owned.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard(self) -> T {
+        let index = self.handle.index();
+        let value = self.handle.discard();
+        self.log.push(PlannedOp::Discard {
+            index,
+            value: value.clone(),
+        });
+        value
+    }
+
+    /// Insert a new element AFTER the current one, and process it in the next iteration.
+    pub fn insert_and_process(&mut self, t: T) {
+        self.log_insertion(&t, true);
+        self.handle.insert_and_process(t);
+    }
+
+    /// Insert a new element AFTER the current one, but do not process it in the next iteration.
+    pub fn insert_and_skip(&mut self, t: T) {
+        self.log_insertion(&t, false);
+        self.handle.insert_and_skip(t);
+    }
+
+    fn log_insertion(&mut self, t: &T, processed: bool) {
+        self.log.push(PlannedOp::Insert {
+            index: self.handle.index() + 1,
+            value: t.clone(),
+            processed,
+        });
+    }
+
+    /// Skip a certain amount of elements after this one.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        self.log.push(PlannedOp::Skip {
+            from: self.handle.index(),
+            count: steps_to_skip,
+        });
+        self.handle.skip_forward(steps_to_skip);
+    }
+
+    /// Do not process any more elements.
+    pub fn stop_iteration(self) {
+        self.log.push(PlannedOp::Stop {
+            at: self.handle.index(),
+        });
+        self.handle.stop_iteration();
+    }
+}
+
+/// Do a dry run of a pass, returning what it would do to the vector instead of changing it.
+///
+/// The closure is run like with [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), but on a copy of the vector, and every
+/// change is recorded. Replaying the result with [`apply_plan`] gives the same vector as running the closure on it directly.
+pub fn plan_vec_by_handles<T: Clone>(
+    vec: &[T],
+    mut op: impl FnMut(PlanHandle<T>),
+) -> Vec<PlannedOp<T>> {
+    let mut scratch = vec.to_vec();
+    let mut log = vec![];
+    let mut driver = PassDriver::new(&scratch);
+
+    while let Some(handle) = driver.next(&mut scratch) {
+        op(PlanHandle {
+            handle,
+            log: &mut log,
+        });
+    }
+    log
+}
+
+/// Replay operations recorded by [`plan_vec_by_handles`] onto a vector. Skips and stops do not change the vector.
+///
+/// # Panics
+/// Panics if an index of the plan is out of bounds, which does not happen when the plan was made from the same vector.
+pub fn apply_plan<T: Clone>(vec: &mut Vec<T>, plan: &[PlannedOp<T>]) {
+    for op in plan {
+        match op {
+            PlannedOp::Set { index, new, .. } => vec[*index].clone_from(new),
+            PlannedOp::Discard { index, .. } => drop(vec.remove(*index)),
+            PlannedOp::Insert { index, value, .. } => vec.insert(*index, value.clone()),
+            PlannedOp::Skip { .. } | PlannedOp::Stop { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_mut)] // The same closure body is used for both kinds of handles, and not every body mutates the handle.
+mod tests {
+    use super::*;
+    use crate::mutate_vec_by_handles;
+
+    // Runs the closure body both directly and as a plan, asserting that replaying the plan gives the same vector. Returns the plan.
+    macro_rules! assert_plan_matches {
+        ($vec:expr, |$handle:ident| $body:block) => {{
+            let original = $vec;
+            let mut direct = original.clone();
+            mutate_vec_by_handles(&mut direct, |mut $handle| $body);
+            let plan = plan_vec_by_handles(&original, |mut $handle| $body);
+            let mut replayed = original.clone();
+            apply_plan(&mut replayed, &plan);
+            assert_eq!(replayed, direct);
+            plan
+        }};
+    }
+
+    #[test]
+    fn test_plan_discard_and_insert() {
+        let plan = assert_plan_matches!(vec![1, 2, 3, 4, 5], |handle| {
+            match *handle.get() {
+                2 => drop(handle.discard()),
+                3 => {
+                    handle.insert_and_process(31);
+                    handle.insert_and_skip(30);
+                }
+                31 => handle.set(32),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            plan,
+            vec![
+                PlannedOp::Discard { index: 1, value: 2 },
+                PlannedOp::Insert {
+                    index: 2,
+                    value: 31,
+                    processed: true
+                },
+                PlannedOp::Insert {
+                    index: 2,
+                    value: 30,
+                    processed: false
+                },
+                PlannedOp::Set {
+                    index: 3,
+                    old: 31,
+                    new: 32
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_lookahead_skip_stop() {
+        let plan = assert_plan_matches!(vec![5, 5, 1, 2, 2, 8, 9, 3], |handle| {
+            if handle.peek_forward_slice(1) == Some(handle.get()) {
+                let next = *handle.get();
+                handle.set(next * 2);
+                handle.skip_forward(1);
+            } else if *handle.get() == 8 {
+                handle.stop_iteration();
+            } else if *handle.get() < 5 {
+                handle.insert_and_skip(0);
+            }
+        });
+        assert_eq!(plan.last(), Some(&PlannedOp::Stop { at: 6 }));
+        assert!(plan.contains(&PlannedOp::Skip { from: 0, count: 1 }));
+    }
+
+    #[test]
+    fn test_plan_strings_dedup() {
+        let v: Vec<String> = ["a", "a", "b", "", "c", "c", "c"]
+            .map(String::from)
+            .to_vec();
+        assert_plan_matches!(v, |handle| {
+            if handle.get().is_empty() || handle.peek_forward_slice(1) == Some(handle.get()) {
+                drop(handle.discard());
+            } else {
+                let upper = handle.get().to_uppercase();
+                handle.set(upper);
+            }
+        });
+    }
+}
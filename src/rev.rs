@@ -0,0 +1,270 @@
+use std::slice::SliceIndex;
+
+// Contract:
+// `index < vec.len()`
+// `remaining <= index + 1`, where the elements at indices below `remaining` are processed later, from the back
+// `vec` may not be mutated at indices larger than `index`
+/// Represents an index in a vector while iterating from the back, allowing mutation of the vector with that index as a "context".
+/// Created by [`mutate_vec_by_handles_rev`].
+///
+/// Compared to [`VecMutationHandle`](crate::VecMutationHandle), "forward" means towards the front of the vector here: elements
+/// are inserted before the current element, and peeks look at the elements before it, which are the ones that remain to be processed.
+#[derive(Debug)]
+pub struct VecMutationHandleRev<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    index: usize,             // The current index. Should not be mutated.
+    remaining: &'b mut usize, // The number of elements at the front of the vector still to be processed.
+}
+
+impl<'a, 'b, T> VecMutationHandleRev<'a, 'b, T> {
+    fn new(vec: &'a mut Vec<T>, remaining: &'b mut usize) -> Option<Self> {
+        let index = remaining.checked_sub(1)?;
+        *remaining = index;
+        Some(VecMutationHandleRev {
+            vec,
+            index,
+            remaining,
+        })
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.vec[self.index]
+    }
+
+    /// Assign a new value to this element.
+    pub fn set(&mut self, t: T) {
+        *self.get_mut() = t;
+    }
+
+    /// Replace the current element with another, and get ownership of the value currently there.
+    pub fn replace(&mut self, t: T) -> T {
+        std::mem::replace(self.get_mut(), t)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the current element, and return it as owned.
+    /// No index fixup is needed, as only the elements after it (which were already processed) are shifted.
+    pub fn discard(self) -> T {
+        self.vec.remove(self.index)
+    }
+
+    /// Insert a new element BEFORE the current one, and process it in the next iteration.
+    pub fn insert_and_process(&mut self, t: T) {
+        self.vec.insert(self.index, t);
+        *self.remaining += 1;
+    }
+
+    /// Insert a new element BEFORE the current one, but do not process it in the next iteration.
+    pub fn insert_and_skip(&mut self, t: T) {
+        self.vec.insert(self.index, t);
+    }
+
+    /// Insert each element in a vec BEFORE the current one, in the same order as the vec. Process them afterwards, from the back.
+    pub fn insert_and_process_vec(&mut self, vec: Vec<T>) {
+        *self.remaining += vec.len();
+        self.vec.splice(self.index..self.index, vec);
+    }
+
+    /// Insert each element in a vec BEFORE the current one, in the same order as the vec. Do not process them afterwards.
+    pub fn insert_and_skip_vec(&mut self, vec: Vec<T>) {
+        self.vec.splice(self.index..self.index, vec);
+    }
+
+    /// Skip a certain amount of the next elements, i.e. the elements before the current one.
+    /// Skipping past the front of the vector simply ends the pass.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        *self.remaining = self.remaining.saturating_sub(steps_to_skip);
+    }
+
+    /// Do not process any more elements.
+    /// Please note, this does not affect the call-site like the `break` keyword. This method does return, and executation continues from the call-site.
+    pub fn stop_iteration(self) {
+        *self.remaining = 0;
+    }
+
+    /// Discards the current element, and returns it as owned. Does not process any more elements.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard_and_stop_iteration(self) -> T {
+        *self.remaining = 0;
+        self.vec.remove(self.index)
+    }
+
+    /// "Peek" a reference to an element towards the front of the vector, with 0 being the current element, and 1 the element before it,
+    /// which is processed next (unless skipped).
+    #[must_use]
+    pub fn peek_back(&self, offset: usize) -> Option<&T> {
+        self.vec.get(self.index.checked_sub(offset)?)
+    }
+
+    /// "Peek" a mutable reference to an element towards the front of the vector, with 0 being the current element. See `peek_back`.
+    #[must_use]
+    pub fn peek_back_mut(&mut self, offset: usize) -> Option<&mut T> {
+        self.vec.get_mut(self.index.checked_sub(offset)?)
+    }
+
+    /// "Peek" a reference to a slice of the elements before the current one, in vector order. E.g. `..` is all of them, where the last one
+    /// is the element processed next.
+    #[must_use]
+    pub fn peek_before_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.vec[..self.index].get(slice)
+    }
+
+    /// "Peek" a mutable reference to a slice of the elements before the current one, in vector order. See `peek_before_slice`.
+    #[must_use]
+    pub fn peek_before_slice_mut<I>(&mut self, slice: I) -> Option<&mut I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.vec[..self.index].get_mut(slice)
+    }
+}
+
+/// Mutate a vec using index-style looping from the last element towards the first, but without thinking about the indices.
+///
+/// See [`VecMutationHandleRev`] for how the handle operations are oriented.
+pub fn mutate_vec_by_handles_rev<T>(vec: &mut Vec<T>, mut op: impl FnMut(VecMutationHandleRev<T>)) {
+    let mut remaining = vec.len();
+
+    while let Some(handle) = VecMutationHandleRev::new(vec, &mut remaining) {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VecMutateByHandles;
+
+    #[test]
+    fn test_mutate_vec_rev_order() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut seen = vec![];
+        mutate_vec_by_handles_rev(&mut v, |handle| seen.push(*handle.get()));
+        assert_eq!(seen, vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_discard() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles_rev(|handle| {
+            seen.push(*handle.get());
+            if *handle.get() % 2 == 0 {
+                handle.discard();
+            }
+        });
+        assert_eq!(seen, vec![6, 5, 4, 3, 2, 1]);
+        assert_eq!(v, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_insert_and_process() {
+        let mut v = vec![1, 10, 100];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles_rev(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 10 {
+                handle.insert_and_process(5);
+            }
+        });
+        assert_eq!(seen, vec![100, 10, 5, 1]);
+        assert_eq!(v, vec![1, 5, 10, 100]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_insert_and_skip() {
+        let mut v = vec![1, 10, 100];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles_rev(|mut handle| {
+            seen.push(*handle.get());
+            if *handle.get() == 10 {
+                handle.insert_and_skip(5);
+                handle.insert_and_skip(6);
+            }
+        });
+        assert_eq!(seen, vec![100, 10, 1]);
+        assert_eq!(v, vec![1, 6, 5, 10, 100]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_insert_vecs() {
+        let mut v = vec![1, 10, 100];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles_rev(|mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                100 => handle.insert_and_skip_vec(vec![50, 60]),
+                10 => handle.insert_and_process_vec(vec![2, 3]),
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![100, 10, 3, 2, 1]);
+        assert_eq!(v, vec![1, 2, 3, 10, 50, 60, 100]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_stop() {
+        let mut v = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        v.mutate_vec_by_handles_rev(|handle| {
+            if *handle.get() > 10 {
+                handle.stop_iteration();
+            } else {
+                handle.discard();
+            }
+        });
+        assert_eq!(v, vec![2, 3, 4, 5, 6, 11]);
+
+        let mut v = vec![2, 3, 4, 5, 6, 11, 1, 5, 7];
+        v.mutate_vec_by_handles_rev(|mut handle| {
+            if *handle.get() > 10 {
+                handle.discard_and_stop_iteration();
+            } else {
+                handle.set(20);
+            }
+        });
+        assert_eq!(v, vec![2, 3, 4, 5, 6, 20, 20, 20]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_skip_forward() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        v.mutate_vec_by_handles_rev(|mut handle| {
+            seen.push(*handle.get());
+            handle.skip_forward(if *handle.get() == 5 { 2 } else { usize::MAX });
+        });
+        assert_eq!(seen, vec![5, 2]);
+    }
+
+    #[test]
+    fn test_mutate_vec_rev_peek() {
+        // Propagate a running maximum from the back towards the front.
+        let mut v = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        mutate_vec_by_handles_rev(&mut v, |mut handle| {
+            let current = *handle.get();
+            if let Some(previous) = handle.peek_back_mut(1) {
+                *previous = (*previous).max(current);
+            }
+            assert_eq!(handle.peek_back(0), Some(&current));
+        });
+        assert_eq!(v, vec![9, 9, 9, 9, 9, 9, 6, 6]);
+
+        let mut v = vec![1, 2, 3];
+        let mut sums = vec![];
+        mutate_vec_by_handles_rev(&mut v, |handle| {
+            sums.push(handle.peek_before_slice(..).unwrap().iter().sum::<i32>());
+        });
+        assert_eq!(sums, vec![3, 1, 0]);
+    }
+}
@@ -0,0 +1,155 @@
+use std::slice::SliceIndex;
+
+/// Where [`route_vec_by_handles`] puts an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// Keep the element in the vector.
+    Keep,
+    /// Move the element to the bucket with this index.
+    To(usize),
+}
+
+// Contract:
+// `index < vec.len()`
+// `next_index > index`
+// Elements are not moved until the pass is over, so the lookahead sees the vector as it was.
+/// Represents an element of a vector being routed into buckets, allowing it to be looked at along with the elements after it.
+/// Created by [`route_vec_by_handles`].
+#[derive(Debug)]
+pub struct RouteHandle<'a, 'b, T> {
+    vec: &'a mut [T],
+    index: usize, // Should not be mutated.
+    next_index: &'b mut usize,
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> RouteHandle<'a, 'b, T> {
+    fn new(vec: &'a mut [T], next_index: &'b mut usize, stopped: &'b mut bool) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index >= vec.len() {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(RouteHandle {
+            vec,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    /// Get a mutable reference to the current element. The change is kept wherever the element is routed.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.vec[self.index]
+    }
+
+    /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+    /// As elements are only moved after the pass, these are in their original order.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.vec[self.index..].get(slice)
+    }
+
+    /// Skip a certain amount of elements after this one. The skipped elements are kept.
+    pub fn skip_forward(&mut self, elements_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(elements_to_skip);
+    }
+
+    /// Do not process any more elements after this one. They are kept.
+    /// The route returned by the closure for this element is still applied.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Sort the elements of a vector into buckets, by deciding for each element whether to keep it or move it to one of `buckets`, with
+/// access to the elements after it. Moved elements are pushed onto their bucket in order, and the vector keeps the others, in order.
+///
+/// Like [`partition_vec_by_handles`](crate::partition_vec_by_handles), the elements are moved in one compaction after the pass.
+/// If the closure panics, no elements are moved.
+///
+/// # Panics
+/// If the closure returns `Route::To` with an index which is not the index of a bucket, before any elements are moved.
+pub fn route_vec_by_handles<T>(
+    vec: &mut Vec<T>,
+    buckets: &mut [Vec<T>],
+    mut op: impl FnMut(RouteHandle<T>) -> Route,
+) {
+    let mut routes = vec![None; vec.len()];
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = RouteHandle::new(vec, &mut next_index, &mut stopped) {
+        let index = handle.index;
+        if let Route::To(bucket) = op(handle) {
+            assert!(
+                bucket < buckets.len(),
+                "route to bucket {bucket}, but there are only {} buckets",
+                buckets.len()
+            );
+            routes[index] = Some(bucket);
+        }
+    }
+
+    let targets: Vec<usize> = routes.iter().flatten().copied().collect();
+    let mut routes = routes.into_iter();
+    // `extract_if` visits every element once, in order, and `routes` has one entry per element.
+    let moved = vec.extract_if(.., |_| routes.next().flatten().is_some());
+    for (t, bucket) in moved.zip(targets) {
+        buckets[bucket].push(t);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_three_way_with_lookahead() {
+        // Words followed by a number go to bucket 0 along with it, other words to bucket 1, lone numbers to bucket 2.
+        // Stops at "e", keeping the rest.
+        let mut v = vec!["a", "1", "b", "c", "2", "3", "d", "e", "4"];
+        let mut buckets = vec![vec![], vec![], vec!["x"]];
+        let is_number = |s: &str| s.parse::<u32>().is_ok();
+        route_vec_by_handles(&mut v, &mut buckets, |mut handle| {
+            if *handle.get() == "e" {
+                handle.stop_iteration();
+                return Route::Keep;
+            }
+            if is_number(handle.get()) {
+                return Route::To(2);
+            }
+            if handle
+                .peek_forward_slice(1)
+                .is_some_and(|next| is_number(next))
+            {
+                handle.skip_forward(1);
+                return Route::To(0);
+            }
+            Route::To(1)
+        });
+        assert_eq!(v, vec!["1", "2", "e", "4"]);
+        assert_eq!(
+            buckets,
+            vec![vec!["a", "c"], vec!["b", "d"], vec!["x", "3"]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "route to bucket 2, but there are only 2 buckets")]
+    fn test_route_out_of_range() {
+        let mut v = vec![1, 2, 3];
+        let mut buckets = vec![vec![], vec![]];
+        route_vec_by_handles(&mut v, &mut buckets, |handle| Route::To(*handle.get() - 1));
+    }
+}
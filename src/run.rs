@@ -0,0 +1,206 @@
+// Contract:
+// `start + len <= vec.len()`, and `len > 0`
+// `next_index >= start`
+// `vec` may not be mutated at indices smaller than `start`
+/// Represents a maximal run of related elements in a vector, allowing mutation of the vector with that run as a "context".
+/// Created by [`mutate_vec_by_runs`].
+#[derive(Debug)]
+pub struct RunHandle<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    start: usize, // The index of the first element of the run. Should not be mutated.
+    len: usize,
+    next_index: &'b mut usize, // The index of the first element of the next run to be processed.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> RunHandle<'a, 'b, T> {
+    fn new(
+        vec: &'a mut Vec<T>,
+        same_run: &mut impl FnMut(&T, &T) -> bool,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let start = *next_index;
+        let (first, rest) = vec.get(start..).filter(|_| !*stopped)?.split_first()?;
+        let len = 1 + rest.iter().take_while(|t| same_run(first, t)).count();
+        *next_index = start + len;
+        Some(RunHandle {
+            vec,
+            start,
+            len,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the elements of the run. Never empty.
+    #[must_use]
+    pub fn get(&self) -> &[T] {
+        &self.vec[self.start..self.start + self.len]
+    }
+
+    /// Get a mutable reference to the elements of the run. Never empty.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut [T] {
+        &mut self.vec[self.start..self.start + self.len]
+    }
+
+    /// Replace the whole run with a single element.
+    pub fn collapse_to(self, t: T) {
+        self.vec.splice(self.start..self.start + self.len, [t]);
+        *self.next_index = self.start + 1;
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the whole run, and return its elements as owned.
+    pub fn discard_run(self) -> Vec<T> {
+        *self.next_index = self.start;
+        self.vec.drain(self.start..self.start + self.len).collect()
+    }
+
+    /// Keep only the first element of the run, like `Vec::dedup_by` does.
+    pub fn keep_first_discard_rest(self) {
+        self.vec.drain(self.start + 1..self.start + self.len);
+        *self.next_index = self.start + 1;
+    }
+
+    /// Do not process any more runs.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a vec by looking at maximal runs of related elements, e.g. consecutive equal elements, one run at a time.
+///
+/// A run starts at the first element not yet processed, and continues for as long as `same_run(first, element)` holds, where
+/// `first` is the first element of the run. After the closure returns, the iteration continues with the first element after the
+/// (possibly modified) run, even if that element would have belonged to the run; runs are never merged.
+pub fn mutate_vec_by_runs<T>(
+    vec: &mut Vec<T>,
+    mut same_run: impl FnMut(&T, &T) -> bool,
+    mut op: impl FnMut(RunHandle<T>),
+) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = RunHandle::new(vec, &mut same_run, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+}
+
+/// Fold adjacent elements together in place, like itertools' `coalesce`. Each element is merged with the one after it for as long
+/// as `f(element, next)` returns `Some`, replacing both with the merged element, which is then tried against the new next element.
+/// When `f` returns `None`, the next element is kept as is, and merging continues from it.
+///
+/// A single forward pass, moving each kept element at most once, without repeated removals. Built on `Vec::dedup_by`.
+pub fn coalesce_vec_by_handles<T>(vec: &mut Vec<T>, mut f: impl FnMut(&T, &T) -> Option<T>) {
+    vec.dedup_by(|next, kept| match f(kept, next) {
+        Some(merged) => {
+            *kept = merged;
+            true
+        }
+        None => false,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_dedup() {
+        let original = vec![1, 1, 2, 3, 3, 3, 1, 4, 4, 5];
+
+        let mut deduped = original.clone();
+        deduped.dedup();
+
+        let mut v = original;
+        let mut runs = vec![];
+        mutate_vec_by_runs(&mut v, PartialEq::eq, |handle| {
+            runs.push(handle.get().len());
+            handle.keep_first_discard_rest();
+        });
+        assert_eq!(v, deduped);
+        assert_eq!(runs, vec![2, 1, 3, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_runs_sum_counts() {
+        let original = vec![
+            ("a", 1),
+            ("a", 2),
+            ("b", 1),
+            ("c", 4),
+            ("c", 1),
+            ("c", 1),
+            ("a", 3),
+        ];
+
+        let mut reference = original.clone();
+        reference.dedup_by(|later, first| {
+            let same = later.0 == first.0;
+            if same {
+                first.1 += later.1;
+            }
+            same
+        });
+
+        let mut v = original;
+        mutate_vec_by_runs(
+            &mut v,
+            |first, t| first.0 == t.0,
+            |handle| {
+                let key = handle.get()[0].0;
+                let count = handle.get().iter().map(|t| t.1).sum();
+                handle.collapse_to((key, count));
+            },
+        );
+        assert_eq!(v, reference);
+        assert_eq!(v, vec![("a", 3), ("b", 1), ("c", 6), ("a", 3)]);
+    }
+
+    #[test]
+    fn test_runs_discard_and_stop() {
+        let mut v = vec![0, 0, 1, 2, 2, 0, 3, 3, 4];
+        mutate_vec_by_runs(&mut v, PartialEq::eq, |mut handle| match handle.get()[0] {
+            0 => {
+                handle.discard_run();
+            }
+            3 => handle.stop_iteration(),
+            _ => handle.get_mut().iter_mut().for_each(|t| *t *= 10),
+        });
+        assert_eq!(v, vec![10, 20, 20, 3, 3, 4]);
+    }
+
+    // Merges touching or overlapping intervals, which are sorted by their start.
+    fn merge_intervals(a: &(i64, i64), b: &(i64, i64)) -> Option<(i64, i64)> {
+        (b.0 <= a.1).then(|| (a.0, a.1.max(b.1)))
+    }
+
+    #[test]
+    fn test_coalesce_chains() {
+        let mut v = vec![(1, 3), (2, 5), (5, 6), (8, 9), (10, 12), (11, 11), (12, 15)];
+        coalesce_vec_by_handles(&mut v, merge_intervals);
+        assert_eq!(v, vec![(1, 6), (8, 9), (10, 15)]);
+
+        // Run-length encoding.
+        let mut v: Vec<(char, usize)> = "aaabccdddd".chars().map(|c| (c, 1)).collect();
+        coalesce_vec_by_handles(&mut v, |a, b| (a.0 == b.0).then_some((a.0, a.1 + b.1)));
+        assert_eq!(v, vec![('a', 3), ('b', 1), ('c', 2), ('d', 4)]);
+    }
+
+    #[test]
+    fn test_coalesce_none_or_all() {
+        let mut v = vec![(1, 2), (3, 4), (5, 6)];
+        coalesce_vec_by_handles(&mut v, merge_intervals);
+        assert_eq!(v, vec![(1, 2), (3, 4), (5, 6)]);
+
+        let mut v = vec![(1, 4), (2, 3), (4, 8), (5, 9)];
+        coalesce_vec_by_handles(&mut v, merge_intervals);
+        assert_eq!(v, vec![(1, 9)]);
+
+        let mut v: Vec<(i64, i64)> = vec![];
+        coalesce_vec_by_handles(&mut v, merge_intervals);
+        assert!(v.is_empty());
+    }
+}
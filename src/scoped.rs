@@ -0,0 +1,207 @@
+use std::num::NonZeroUsize;
+use std::slice::SliceIndex;
+
+use crate::VecMutationHandle;
+
+// Contract:
+// `scope <= handle.peek_forward_slice(1..).len()`
+// The elements after the scope are never touched, except by moving them when elements in the scope are inserted or removed.
+/// A [`VecMutationHandle`] restricted to the current element and a limited number of elements after it, the scope.
+/// Created by [`VecMutationHandle::scoped`].
+///
+/// Nothing after the scope can be read or changed through this handle. The scope grows with elements inserted after the current
+/// element, and shrinks with elements discarded from it. The current element itself can't be discarded, as the outer handle is still on it.
+#[derive(Debug)]
+pub struct ScopedHandle<'h, 'a, 'b, T> {
+    handle: &'h mut VecMutationHandle<'a, 'b, T>,
+    scope: usize, // The number of elements after the current one in the scope.
+}
+
+impl<T> ScopedHandle<'_, '_, '_, T> {
+    /// The number of elements after the current one which are in the scope.
+    #[must_use]
+    pub fn scope_len(&self) -> usize {
+        self.scope
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.handle.get()
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.handle.get_mut()
+    }
+
+    /// Assign a new value to the current element.
+    pub fn set(&mut self, t: T) {
+        self.handle.set(t);
+    }
+
+    /// "Peek" a reference to a slice of the scope, with 0 being the index of the current element, like
+    /// `VecMutationHandle::peek_forward_slice`. Returns `None` for anything reaching past the scope.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.handle.peek_forward_slice(..=self.scope)?.get(slice)
+    }
+
+    /// "Peek" a mutable reference to a slice of the scope, like `peek_forward_slice`.
+    #[must_use]
+    pub fn peek_forward_slice_mut<I>(&mut self, slice: I) -> Option<&mut I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.handle
+            .peek_forward_slice_mut(..=self.scope)?
+            .get_mut(slice)
+    }
+
+    /// Insert a new element AFTER the current one, into the scope, and process it in the next iteration.
+    pub fn insert_and_process(&mut self, t: T) {
+        self.handle.insert_and_process(t);
+        self.scope += 1;
+    }
+
+    /// Insert a new element AFTER the current one, into the scope, but do not process it.
+    pub fn insert_and_skip(&mut self, t: T) {
+        self.handle.insert_and_skip(t);
+        self.scope += 1;
+    }
+
+    /// Remove the element `offset` positions after the current one, and return it, like `VecMutationHandle::discard_forward_at`.
+    /// Returns `None`, and does nothing, if the element is not in the scope.
+    pub fn discard_forward_at(&mut self, offset: NonZeroUsize) -> Option<T> {
+        if offset.get() > self.scope {
+            return None;
+        }
+        self.scope -= 1;
+        self.handle.discard_forward_at(offset)
+    }
+
+    /// Remove all elements of the scope after the current one, and return them in order. The scope is empty afterwards.
+    pub fn discard_rest_of_scope(&mut self) -> Vec<T> {
+        let scope = std::mem::take(&mut self.scope);
+        self.handle.take_forward(scope)
+    }
+
+    /// Skip a certain amount of elements, like `VecMutationHandle::skip_forward`, but never past the first element after the scope.
+    /// Skipping further is clamped to it. Counts elements, also in passes visiting every `step`-th element.
+    pub fn skip_forward(&mut self, elements_to_skip: usize) {
+        let offset = self.handle.next_offset();
+        let target = offset.saturating_add(elements_to_skip).min(self.scope + 1);
+        if target > offset {
+            let mut seen = 0;
+            self.handle.skip_until(|_| {
+                seen += 1;
+                seen == target
+            });
+        }
+    }
+}
+
+impl<'a, 'b, T> VecMutationHandle<'a, 'b, T> {
+    /// Run `f` with a handle restricted to the current element and at most `lookahead` elements after it, see [`ScopedHandle`].
+    /// Useful for giving a helper function handle powers, with the guarantee that it can't touch anything further away.
+    ///
+    /// Afterwards, this handle continues as usual, with the insertions, removals and skips done in the scope taken into account.
+    pub fn scoped<R>(
+        &mut self,
+        lookahead: usize,
+        f: impl FnOnce(ScopedHandle<'_, 'a, 'b, T>) -> R,
+    ) -> R {
+        let scope = self
+            .peek_forward_slice(1..)
+            .map_or(0, <[T]>::len)
+            .min(lookahead);
+        f(ScopedHandle {
+            handle: self,
+            scope,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutate_vec_by_handles;
+
+    use super::*;
+
+    #[test]
+    fn test_scoped_skip_clamped() {
+        let mut v: Vec<i32> = (0..10).collect();
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 0 {
+                handle.scoped(2, |mut scoped| {
+                    assert_eq!(scoped.peek_forward_slice(..), Some(&[0, 1, 2][..]));
+                    assert_eq!(scoped.peek_forward_slice(3), None);
+                    scoped.skip_forward(5);
+                });
+            }
+            if *handle.get() == 8 {
+                handle.scoped(5, |mut scoped| {
+                    assert_eq!(scoped.scope_len(), 1);
+                    scoped.skip_forward(usize::MAX);
+                });
+            }
+        });
+        assert_eq!(visited, vec![0, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_scoped_discard_whole_scope() {
+        let mut v = vec![0, 1, 2, 3, 4];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 0 {
+                let discarded = handle.scoped(3, |mut scoped| {
+                    assert_eq!(
+                        scoped.discard_forward_at(NonZeroUsize::new(4).unwrap()),
+                        None
+                    );
+                    scoped.set(10);
+                    scoped.discard_rest_of_scope()
+                });
+                assert_eq!(discarded, vec![1, 2, 3]);
+            }
+        });
+        assert_eq!(v, vec![10, 4]);
+        assert_eq!(visited, vec![0, 4]);
+    }
+
+    #[test]
+    fn test_scoped_insert_grows() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = vec![];
+        mutate_vec_by_handles(&mut v, |mut handle| {
+            visited.push(*handle.get());
+            if *handle.get() == 1 {
+                handle.scoped(1, |mut scoped| {
+                    scoped.insert_and_process(20);
+                    scoped.insert_and_skip(10);
+                    assert_eq!(scoped.scope_len(), 3);
+                    assert_eq!(scoped.peek_forward_slice(..), Some(&[1, 10, 20, 2][..]));
+                    // The original next element is still the last one in the scope.
+                    assert_eq!(
+                        scoped.discard_forward_at(NonZeroUsize::new(3).unwrap()),
+                        Some(2)
+                    );
+                    assert_eq!(
+                        scoped.discard_forward_at(NonZeroUsize::new(3).unwrap()),
+                        None
+                    );
+                });
+            }
+        });
+        assert_eq!(v, vec![1, 10, 20, 3]);
+        assert_eq!(visited, vec![1, 20, 3]);
+    }
+}
@@ -0,0 +1,225 @@
+use std::slice::SliceIndex;
+
+// Contract:
+// `index < slice.len()`
+// `next_index >= index`
+// `slice` may not be mutated at indices smaller than `index`
+/// Represents an index in a slice, allowing mutation of the slice with that index as a "context". Created by [`mutate_slice_by_handles`].
+///
+/// The length of a slice can't change, so this has the subset of the [`VecMutationHandle`](crate::VecMutationHandle) API which
+/// neither inserts nor removes elements.
+#[derive(Debug)]
+pub struct SliceMutationHandle<'a, 'b, T> {
+    slice: &'a mut [T],
+    index: usize,              // The current index. Should not be mutated.
+    next_index: &'b mut usize, // The next index to be processed.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> SliceMutationHandle<'a, 'b, T> {
+    fn new(slice: &'a mut [T], next_index: &'b mut usize, stopped: &'b mut bool) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index >= slice.len() {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(SliceMutationHandle {
+            slice,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.slice[self.index]
+    }
+
+    /// Get a mutable reference to the current element.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.slice[self.index]
+    }
+
+    /// Assign a new value to this element.
+    pub fn set(&mut self, t: T) {
+        *self.get_mut() = t;
+    }
+
+    /// Replace the current element with another, and get ownership of the value currently there.
+    pub fn replace(&mut self, t: T) -> T {
+        std::mem::replace(self.get_mut(), t)
+    }
+
+    /// Swap the current element with the element `offset` positions after it. Returns `false`, and does nothing, if there is no such element.
+    /// An offset of 0 does nothing, and returns `true`.
+    ///
+    /// Afterwards, `get` and `get_mut` refer to the newly promoted element. The demoted element is now ahead of the current position,
+    /// so it will be processed again when the iteration reaches it.
+    pub fn swap_with_forward(&mut self, offset: usize) -> bool {
+        match self.index.checked_add(offset) {
+            Some(other) if other < self.slice.len() => {
+                self.slice.swap(self.index, other);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rotate the elements after the current one to the left, such that the element `mid` positions after the next one becomes the next one.
+    /// `mid` is taken modulo the number of elements after the current one, and nothing happens if there are none.
+    pub fn rotate_rest_left(&mut self, mid: usize) {
+        let rest = &mut self.slice[self.index + 1..];
+        if !rest.is_empty() {
+            let mid = mid % rest.len();
+            rest.rotate_left(mid);
+        }
+    }
+
+    /// Rotate the elements after the current one to the right, such that the element `k` positions before the end becomes the next one.
+    /// `k` is taken modulo the number of elements after the current one, and nothing happens if there are none.
+    pub fn rotate_rest_right(&mut self, k: usize) {
+        let rest = &mut self.slice[self.index + 1..];
+        if !rest.is_empty() {
+            let k = k % rest.len();
+            rest.rotate_right(k);
+        }
+    }
+
+    /// Process the current element again in the next iteration, instead of advancing. Overrides any skipping done earlier on this handle.
+    ///
+    /// Note that calling this unconditionally makes the iteration process the same element forever.
+    pub fn reprocess_current(&mut self) {
+        *self.next_index = self.index;
+    }
+
+    /// Skip a certain amount of the elements after the current one. Skipping past the end of the slice simply ends the pass.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(steps_to_skip);
+    }
+
+    /// Do not process any more elements.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+
+    /// "Peek" a reference to a slice of the remaining elements, with 0 being the index of the current element. E.g. `1` is the next element,
+    /// and `0..` is a slice of the remaining elements, including this one.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.slice[self.index..].get(slice)
+    }
+
+    /// "Peek" a mutable reference to a slice of the remaining elements, with 0 being the index of the current element. See `peek_forward_slice`.
+    #[must_use]
+    pub fn peek_forward_slice_mut<I>(&mut self, slice: I) -> Option<&mut I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.slice[self.index..].get_mut(slice)
+    }
+}
+
+/// Mutate a slice using index-style looping, but without thinking about the indices. Works on anything which derefs to a mutable slice,
+/// like arrays, boxed slices, or sub-slices of a vector. Elements can't be inserted or removed, as the length of a slice is fixed.
+pub fn mutate_slice_by_handles<T>(slice: &mut [T], mut op: impl FnMut(SliceMutationHandle<T>)) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = SliceMutationHandle::new(slice, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+}
+
+/// Trait for adding slice mutation by handles as an extension trait to slices, and through them to arrays, boxed slices and vectors.
+pub trait SliceMutateByHandles<T> {
+    /// Mutate a slice using index-style looping. See [`mutate_slice_by_handles`].
+    fn mutate_slice_by_handles(&mut self, op: impl FnMut(SliceMutationHandle<T>));
+}
+
+impl<T> SliceMutateByHandles<T> for [T] {
+    fn mutate_slice_by_handles(&mut self, op: impl FnMut(SliceMutationHandle<T>)) {
+        mutate_slice_by_handles(self, op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutate_vec_by_handles;
+
+    #[test]
+    fn test_slice_matches_vec_handles() {
+        let original = vec![5, 1, 4, 2, 8, 3, 9, 7];
+
+        let mut with_vec = original.clone();
+        mutate_vec_by_handles(&mut with_vec, |mut handle| {
+            if let Some(min_offset) = (1..handle.peek_forward_slice(0..).unwrap().len())
+                .min_by_key(|offset| handle.peek_forward_slice(*offset).unwrap())
+            {
+                if handle.peek_forward_slice(min_offset) < Some(handle.get()) {
+                    handle.swap_with_forward(min_offset);
+                }
+            }
+        });
+
+        let mut with_slice = original.into_boxed_slice();
+        with_slice.mutate_slice_by_handles(|mut handle| {
+            if let Some(min_offset) = (1..handle.peek_forward_slice(0..).unwrap().len())
+                .min_by_key(|offset| handle.peek_forward_slice(*offset).unwrap())
+            {
+                if handle.peek_forward_slice(min_offset) < Some(handle.get()) {
+                    handle.swap_with_forward(min_offset);
+                }
+            }
+        });
+
+        assert_eq!(*with_slice, *with_vec);
+        assert_eq!(*with_slice, [1, 2, 3, 4, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_slice_skip_stop_set() {
+        let mut array = [1, 2, 3, 4, 5, 6, 7];
+        let mut seen = vec![];
+        array.mutate_slice_by_handles(|mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                1 => handle.skip_forward(1),
+                4 => {
+                    assert_eq!(handle.replace(40), 4);
+                    *handle.peek_forward_slice_mut(1).unwrap() = 50;
+                }
+                6 => handle.stop_iteration(),
+                _ => handle.set(*handle.get() * 10),
+            }
+        });
+        assert_eq!(seen, vec![1, 3, 4, 50, 6]);
+        assert_eq!(array, [1, 2, 30, 40, 500, 6, 7]);
+    }
+
+    #[test]
+    fn test_slice_rotate_and_reprocess() {
+        let mut v = vec![0, 1, 2, 3, 4, 5];
+        let mut seen = vec![];
+        mutate_slice_by_handles(&mut v[1..], |mut handle| {
+            seen.push(*handle.get());
+            match *handle.get() {
+                1 => handle.rotate_rest_left(1),
+                3 => handle.rotate_rest_right(1),
+                4 => {
+                    handle.set(40);
+                    handle.reprocess_current();
+                }
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![1, 3, 2, 4, 40, 5]);
+        assert_eq!(v, vec![0, 1, 3, 2, 40, 5]);
+    }
+}
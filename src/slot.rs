@@ -0,0 +1,216 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{HandleStorage, VecMutationHandle};
+
+impl<'a, 'b, T, S: HandleStorage<Item = T>> VecMutationHandle<'a, 'b, T, S> {
+    /// Temporarily take ownership of the current element. The returned slot derefs to the element, which stays in place until it is
+    /// moved out with `CurrentSlot::into_value`, e.g. to move fields out of an enum variant. The slot can then be filled or discarded.
+    #[must_use]
+    pub fn take_current(self) -> CurrentSlot<'a, 'b, T, S> {
+        CurrentSlot { handle: self }
+    }
+}
+
+/// The current element, temporarily taken out of the vector. See [`VecMutationHandle::take_current`].
+///
+/// The vector is always kept valid: the element is only moved out of its place by `into_value`, which leaves an [`EmptySlot`].
+#[derive(Debug)]
+pub struct CurrentSlot<'a, 'b, T, S = Vec<T>>
+where
+    S: HandleStorage<Item = T>,
+{
+    handle: VecMutationHandle<'a, 'b, T, S>,
+}
+
+impl<'a, 'b, T, S: HandleStorage<Item = T>> CurrentSlot<'a, 'b, T, S> {
+    /// Put the (possibly modified) element back in its place, and get the handle back.
+    #[allow(clippy::must_use_candidate)]
+    pub fn restore(self) -> VecMutationHandle<'a, 'b, T, S> {
+        self.handle
+    }
+
+    /// Remove the element from the vector for good, like `VecMutationHandle::discard`, and return it as owned.
+    #[allow(clippy::must_use_candidate)]
+    pub fn discard(self) -> T {
+        self.handle.discard()
+    }
+
+    /// Move the element out of the slot, leaving an [`EmptySlot`] which can be filled with a new value, or discarded.
+    #[must_use]
+    pub fn into_value(mut self) -> (T, EmptySlot<'a, 'b, T, S>) {
+        let value = self.handle.take_out_current();
+        (
+            value,
+            EmptySlot {
+                handle: Some(self.handle),
+            },
+        )
+    }
+}
+
+impl<T, S: HandleStorage<Item = T>> Deref for CurrentSlot<'_, '_, T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.handle.get()
+    }
+}
+
+impl<T, S: HandleStorage<Item = T>> DerefMut for CurrentSlot<'_, '_, T, S> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.handle.get_mut()
+    }
+}
+
+/// The place of the current element, after it was moved out with [`CurrentSlot::into_value`].
+///
+/// The vector is always kept valid: when dropped (also during a panic) without being filled, the place is removed, like with
+/// `VecMutationHandle::discard`.
+#[derive(Debug)]
+pub struct EmptySlot<'a, 'b, T, S = Vec<T>>
+where
+    S: HandleStorage<Item = T>,
+{
+    handle: Option<VecMutationHandle<'a, 'b, T, S>>, // Always `Some`, until consumed by one of the methods.
+}
+
+impl<'a, 'b, T, S: HandleStorage<Item = T>> EmptySlot<'a, 'b, T, S> {
+    /// Put a value in the place of the current element, and get the handle back.
+    #[allow(clippy::must_use_candidate)]
+    pub fn fill(mut self, t: T) -> VecMutationHandle<'a, 'b, T, S> {
+        // The handle has to be in an `Option` to be moved out of a type with a destructor in safe code, but it is only taken here
+        // and in `drop`, which both consume the slot.
+        let Some(mut handle) = self.handle.take() else {
+            crate::invariant_violated(format_args!(
+                "the handle of an empty slot was already taken"
+            ))
+        };
+        handle.put_back_current(t);
+        handle
+    }
+
+    /// Remove the place of the current element, like `VecMutationHandle::discard`. Equivalent to dropping the slot.
+    pub fn discard(self) {}
+}
+
+impl<T, S: HandleStorage<Item = T>> Drop for EmptySlot<'_, '_, T, S> {
+    fn drop(&mut self) {
+        if let Some(mut handle) = self.handle.take() {
+            handle.forget_taken_current();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::VecMutateByHandles;
+
+    #[derive(Debug, PartialEq)]
+    enum Entry {
+        Text(String),
+        Pair(String, String),
+        Empty,
+    }
+
+    #[test]
+    fn test_take_current_restore() {
+        let mut v = vec![1, 2, 3, 4];
+        let mut visited = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            let mut slot = handle.take_current();
+            *slot *= 10;
+            visited.push(*slot);
+            let mut handle = slot.restore();
+            if *handle.get() == 20 {
+                handle.insert_and_skip(0);
+            }
+        });
+        assert_eq!(visited, vec![10, 20, 30, 40]);
+        assert_eq!(v, vec![10, 20, 0, 30, 40]);
+    }
+
+    #[test]
+    fn test_take_current_dropped_restores() {
+        let mut v = vec![String::from("a"), String::from("b")];
+        v.mutate_vec_by_handles(|handle| {
+            let mut slot = handle.take_current();
+            slot.push('!');
+        });
+        assert_eq!(v, vec!["a!", "b!"]);
+    }
+
+    #[test]
+    fn test_take_current_discard() {
+        let mut v = vec![1, 2, 3, 4, 5];
+        let mut discarded = vec![];
+        v.mutate_vec_by_handles(|handle| {
+            let slot = handle.take_current();
+            if *slot % 2 == 0 {
+                discarded.push(slot.discard());
+            }
+        });
+        assert_eq!(v, vec![1, 3, 5]);
+        assert_eq!(discarded, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_take_current_rebuild_by_value() {
+        let mut v = vec![
+            Entry::Pair("a".into(), "b".into()),
+            Entry::Empty,
+            Entry::Text("c".into()),
+            Entry::Pair("d".into(), "e".into()),
+        ];
+        v.mutate_vec_by_handles(|handle| {
+            let (value, empty) = handle.take_current().into_value();
+            match value {
+                Entry::Pair(first, second) => {
+                    let mut handle = empty.fill(Entry::Text(first));
+                    handle.insert_and_skip(Entry::Text(second));
+                }
+                Entry::Empty => empty.discard(),
+                text @ Entry::Text(_) => {
+                    empty.fill(text);
+                }
+            }
+        });
+        assert_eq!(
+            v,
+            vec![
+                Entry::Text("a".into()),
+                Entry::Text("b".into()),
+                Entry::Text("c".into()),
+                Entry::Text("d".into()),
+                Entry::Text("e".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_take_current_panic_with_slot() {
+        let mut v = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            v.mutate_vec_by_handles(|handle| {
+                let mut slot = handle.take_current();
+                *slot += 10;
+                assert!(*slot != 12, "boom");
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![11, 12, 3, 4]);
+    }
+
+    #[test]
+    fn test_take_current_panic_with_empty_slot() {
+        let mut v = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            v.mutate_vec_by_handles(|handle| {
+                let (value, empty) = handle.take_current().into_value();
+                assert!(value != 2, "boom");
+                empty.fill(value);
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(v, vec![1, 3, 4]);
+    }
+}
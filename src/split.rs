@@ -0,0 +1,137 @@
+use std::slice::SliceIndex;
+
+/// Where [`split_vec_by_handles`] splits the vector, relative to an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Keep the element in the current segment.
+    Continue,
+    /// Keep the element in the current segment, and start a new segment after it.
+    SplitAfter,
+    /// Start a new segment with the element.
+    SplitBefore,
+    /// Drop the element, and start a new segment after it, like a separator.
+    DropElement,
+}
+
+/// Represents an element of a vector being split, allowing it to be looked at along with the elements after it.
+/// Created by [`split_vec_by_handles`].
+#[derive(Debug, Clone, Copy)]
+pub struct SplitHandle<'a, T> {
+    rest: &'a [T], // The current element and all elements after it. Never empty.
+}
+
+impl<'a, T> SplitHandle<'a, T> {
+    /// Get a reference to the current element.
+    #[must_use]
+    pub fn get(&self) -> &'a T {
+        &self.rest[0]
+    }
+
+    /// "Peek" a reference to a slice of the vector, with 0 being the index of the current element. E.g. `1` is the next element, and `0..` is a slice of the remaining elements, including this one.
+    #[must_use]
+    pub fn peek_forward_slice<I>(&self, slice: I) -> Option<&'a I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.rest.get(slice)
+    }
+
+    /// The number of elements after the current one.
+    #[must_use]
+    pub fn remaining_len(&self) -> usize {
+        self.rest.len() - 1
+    }
+}
+
+/// Break a vector into segments, by deciding for each element where the boundaries go, with access to the elements after it.
+///
+/// The segments are in order, and contain the elements in their original order. Empty segments are left out, so consecutive
+/// boundaries, or a boundary at the start or the end, never produce an empty vector. An empty input gives no segments.
+///
+/// The vector is taken by value, and the elements are moved into the segments, so they are never cloned.
+pub fn split_vec_by_handles<T>(
+    vec: Vec<T>,
+    mut op: impl FnMut(SplitHandle<T>) -> Boundary,
+) -> Vec<Vec<T>> {
+    let boundaries: Vec<Boundary> = (0..vec.len())
+        .map(|index| {
+            op(SplitHandle {
+                rest: &vec[index..],
+            })
+        })
+        .collect();
+
+    let mut segments = vec![];
+    let mut segment = vec![];
+    for (t, boundary) in vec.into_iter().zip(boundaries) {
+        if matches!(boundary, Boundary::SplitBefore | Boundary::DropElement) && !segment.is_empty()
+        {
+            segments.push(std::mem::take(&mut segment));
+        }
+        if boundary == Boundary::DropElement {
+            continue;
+        }
+        segment.push(t);
+        if boundary == Boundary::SplitAfter {
+            segments.push(std::mem::take(&mut segment));
+        }
+    }
+    if !segment.is_empty() {
+        segments.push(segment);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_by_budget() {
+        let v = vec![3, 4, 2, 6, 1, 1, 1, 9, 2];
+        let mut sum = 0;
+        let segments = split_vec_by_handles(v, |handle| {
+            sum += handle.get();
+            match handle.peek_forward_slice(1) {
+                Some(next) if sum + next > 8 => {
+                    sum = 0;
+                    Boundary::SplitAfter
+                }
+                _ => Boundary::Continue,
+            }
+        });
+        assert_eq!(
+            segments,
+            vec![vec![3, 4], vec![2, 6], vec![1, 1, 1], vec![9], vec![2]]
+        );
+    }
+
+    #[test]
+    fn test_split_on_sentinels() {
+        let v = vec![0, 1, 2, 0, 0, 3, 0, 4, 5, 6, 0];
+        let mut remaining = vec![];
+        let segments = split_vec_by_handles(v, |handle| {
+            remaining.push(handle.remaining_len());
+            if *handle.get() == 0 {
+                Boundary::DropElement
+            } else if *handle.get() == 5 {
+                Boundary::SplitBefore
+            } else {
+                Boundary::Continue
+            }
+        });
+        assert_eq!(segments, vec![vec![1, 2], vec![3], vec![4], vec![5, 6]]);
+        assert_eq!(remaining, (0..11).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_split_single_segment() {
+        let v = vec![String::from("a"), String::from("b")];
+        let segments = split_vec_by_handles(v, |_| Boundary::Continue);
+        assert_eq!(segments, vec![vec![String::from("a"), String::from("b")]]);
+
+        let segments = split_vec_by_handles(vec![1], |_| Boundary::SplitAfter);
+        assert_eq!(segments, vec![vec![1]]);
+        assert!(split_vec_by_handles(Vec::<i32>::new(), |_| Boundary::SplitBefore).is_empty());
+    }
+}
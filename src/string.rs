@@ -0,0 +1,179 @@
+// Contract:
+// `index < string.len()`, and `index` is on a char boundary
+// `next_index` is on a char boundary, and `next_index >= index` (or `next_index > index`, unless the current char is discarded)
+// `string` may not be mutated at byte offsets smaller than `index`
+/// Represents a char in a string, allowing mutation of the string with that char as a "context".
+/// Created by [`mutate_string_by_handles`].
+///
+/// All positions and counts in this API are in chars. The byte offsets needed to keep the string valid UTF-8 are handled internally,
+/// also when a char is replaced by one with a different width.
+#[derive(Debug)]
+pub struct CharMutationHandle<'a, 'b> {
+    string: &'a mut String,
+    index: usize,  // The byte offset of the current char. Should not be mutated.
+    current: char, // The current char, kept up to date by `set`.
+    next_index: &'b mut usize, // The byte offset of the next char to be processed.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b> CharMutationHandle<'a, 'b> {
+    fn new(
+        string: &'a mut String,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let index = *next_index;
+        let current = string.get(index..).filter(|_| !*stopped)?.chars().next()?;
+        *next_index = index + current.len_utf8();
+        Some(CharMutationHandle {
+            string,
+            index,
+            current,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get the current char.
+    #[must_use]
+    pub fn get(&self) -> char {
+        self.current
+    }
+
+    /// Replace the current char with another, which may have a different width in bytes.
+    pub fn set(&mut self, c: char) {
+        let old_len = self.current.len_utf8();
+        self.string
+            .replace_range(self.index..self.index + old_len, c.encode_utf8(&mut [0; 4]));
+        *self.next_index = (*self.next_index + c.len_utf8()) - old_len;
+        self.current = c;
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the current char, and return it.
+    pub fn discard(self) -> char {
+        *self.next_index = self.index;
+        self.string.remove(self.index)
+    }
+
+    /// Insert a char AFTER the current one, but do not process it in the next iteration.
+    pub fn insert_and_skip(&mut self, c: char) {
+        self.insert_str_and_skip(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Insert a string AFTER the current char, but do not process its chars in the next iterations.
+    pub fn insert_str_and_skip(&mut self, s: &str) {
+        let position = self.index + self.get().len_utf8();
+        self.string.insert_str(position, s);
+        *self.next_index += s.len();
+    }
+
+    /// "Peek" at a char after the current one, with 0 being the current char, and 1 the next.
+    #[must_use]
+    pub fn peek_forward(&self, offset: usize) -> Option<char> {
+        self.string[self.index..].chars().nth(offset)
+    }
+
+    /// Skip a certain amount of chars after the current one. Skipping past the end of the string simply ends the pass.
+    pub fn skip_forward(&mut self, chars_to_skip: usize) {
+        let rest = self.string.get(*self.next_index..).unwrap_or_default();
+        let bytes_to_skip: usize = rest.chars().take(chars_to_skip).map(char::len_utf8).sum();
+        *self.next_index += bytes_to_skip;
+    }
+
+    /// Do not process any more chars.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a string char by char using index-style looping, but without thinking about char boundaries or byte offsets.
+pub fn mutate_string_by_handles(string: &mut String, mut op: impl FnMut(CharMutationHandle)) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = CharMutationHandle::new(string, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+}
+
+/// Trait for adding string mutation by handles as an extension trait to string.
+pub trait StringMutateByHandles {
+    /// Mutate a string char by char using index-style looping. See [`mutate_string_by_handles`].
+    fn mutate_string_by_handles(&mut self, op: impl FnMut(CharMutationHandle));
+}
+
+impl StringMutateByHandles for String {
+    fn mutate_string_by_handles(&mut self, op: impl FnMut(CharMutationHandle)) {
+        mutate_string_by_handles(self, op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_set_different_widths() {
+        let mut s = String::from("aé😀b");
+        let mut seen = vec![];
+        s.mutate_string_by_handles(|mut handle| {
+            seen.push(handle.get());
+            match handle.get() {
+                'a' => handle.set('🦀'),
+                'é' => handle.set('e'),
+                '😀' => handle.set('ß'),
+                _ => handle.set('\u{301}'),
+            }
+        });
+        assert_eq!(seen, vec!['a', 'é', '😀', 'b']);
+        assert_eq!(s, "🦀eß\u{301}");
+    }
+
+    #[test]
+    fn test_string_discard_and_insert() {
+        let mut s = String::from("e\u{301}x😀y");
+        let mut seen = vec![];
+        mutate_string_by_handles(&mut s, |mut handle| {
+            seen.push(handle.get());
+            match handle.get() {
+                '\u{301}' => {
+                    handle.discard();
+                }
+                'x' => handle.insert_str_and_skip("—ü"),
+                '😀' => {
+                    handle.insert_and_skip('é');
+                    handle.set('y');
+                }
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec!['e', '\u{301}', 'x', '😀', 'y']);
+        assert_eq!(s, "ex—üyéy");
+    }
+
+    #[test]
+    fn test_string_peek_skip_stop() {
+        let mut s = String::from("😀a\u{301}bc🦀de");
+        let mut seen = vec![];
+        mutate_string_by_handles(&mut s, |mut handle| {
+            seen.push(handle.get());
+            match handle.get() {
+                '😀' => {
+                    assert_eq!(handle.peek_forward(0), Some('😀'));
+                    assert_eq!(handle.peek_forward(2), Some('\u{301}'));
+                    assert_eq!(handle.peek_forward(8), None);
+                    handle.skip_forward(2);
+                }
+                'b' => {
+                    handle.insert_and_skip('ü');
+                    handle.skip_forward(1);
+                }
+                '🦀' => handle.stop_iteration(),
+                _ => handle.skip_forward(100),
+            }
+        });
+        assert_eq!(seen, vec!['😀', 'b', '🦀']);
+        assert_eq!(s, "😀a\u{301}büc🦀de");
+    }
+}
@@ -0,0 +1,238 @@
+use crate::driver::PassDriver;
+use crate::{InsertedPositions, VecMutationHandle};
+
+/// Counts of what a pass did, returned by [`mutate_vec_by_handles_summarized`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MutationSummary {
+    /// The number of handles given to the closure.
+    pub visited: usize,
+    /// The number of elements removed, by `discard` or any other method removing elements.
+    pub discarded: usize,
+    /// The number of elements inserted, by any method inserting or appending elements.
+    pub inserted: usize,
+    /// The number of times an element was given a new value by `set`, `replace`, or `replace_with`.
+    /// Changes done through `get_mut` or `peek_forward_slice_mut` are not counted, as they can't be detected.
+    pub set_or_replaced: usize,
+    /// Whether the iteration was stopped early, e.g. with `stop_iteration`.
+    pub stopped: bool,
+}
+
+impl MutationSummary {
+    /// Whether any elements were discarded, inserted, set or replaced. See `set_or_replaced` for what is not detected.
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        self.discarded > 0 || self.inserted > 0 || self.set_or_replaced > 0
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), and return a summary
+/// of what the pass did.
+///
+/// Operations which move a number of elements at once are counted per element, e.g. `insert_and_process_vec` counts as
+/// one insertion per element, and `split_current` as one discard and one insertion per piece.
+pub fn mutate_vec_by_handles_summarized<T>(
+    vec: &mut Vec<T>,
+    op: impl FnMut(VecMutationHandle<T>),
+) -> MutationSummary {
+    summarize_pass(vec, InsertedPositions::new(), op).0
+}
+
+// Runs a summarized pass, keeping the positions of inserted elements in `inserted`, which may already hold positions of elements
+// inserted earlier, e.g. by an outer pass. Returns them along with the summary.
+pub(crate) fn summarize_pass<T>(
+    vec: &mut Vec<T>,
+    inserted: InsertedPositions,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) -> (MutationSummary, InsertedPositions) {
+    let mut summary = MutationSummary::default();
+    let mut driver = PassDriver::new(vec).with_inserted(inserted);
+    while let Some(handle) = driver.next(vec) {
+        summary.visited += 1;
+        op(handle.with_summary(&mut summary));
+    }
+    summary.stopped = driver.is_stopped();
+    (summary, driver.into_inserted())
+}
+
+/// Repeatedly run full passes over a vec, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), until a pass changes nothing.
+/// Returns the number of passes run, including the final one which changed nothing.
+///
+/// A pass changed something if its [`MutationSummary`] says so, so changes done only through `get_mut` are not seen.
+///
+/// # Errors
+/// Returns the number of passes run if `max_passes` passes were run, and the last one still changed something.
+pub fn mutate_vec_by_handles_until_stable<T>(
+    vec: &mut Vec<T>,
+    max_passes: Option<usize>,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) -> Result<usize, usize> {
+    let mut passes = 0;
+    loop {
+        if max_passes.is_some_and(|max_passes| passes >= max_passes) {
+            return Err(passes);
+        }
+        passes += 1;
+        if !mutate_vec_by_handles_summarized(vec, &mut op).changed() {
+            return Ok(passes);
+        }
+    }
+}
+
+/// Mutate a vec using index-style looping, like [`mutate_vec_by_handles`](crate::mutate_vec_by_handles), and return the number of
+/// visits which changed something.
+///
+/// Unlike [`MutationSummary`], this also sees changes done through `get_mut`, at the cost of a clone and a comparison per visit.
+/// Visits which discard or insert elements always count as changes.
+pub fn mutate_vec_by_handles_count_changes<T: PartialEq + Clone>(
+    vec: &mut Vec<T>,
+    mut op: impl FnMut(VecMutationHandle<T>),
+) -> usize {
+    let mut driver = PassDriver::new(vec);
+    let mut changes = 0;
+
+    while let Some(handle) = driver.next(vec) {
+        let index = handle.index();
+        let before = handle.get().clone();
+        let mut summary = MutationSummary::default();
+        op(handle.with_summary(&mut summary));
+        if summary.discarded > 0 || summary.inserted > 0 || vec.get(index) != Some(&before) {
+            changes += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarized_mixed() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| match *handle.get() {
+            1 => handle.set(10),
+            2 => {
+                handle.discard();
+            }
+            3 => handle.insert_and_skip_vec(vec![30, 31]),
+            4 => {
+                handle.replace(40);
+                handle.append_and_skip(41);
+            }
+            5 => {
+                handle.replace_with(|x| x * 10);
+                *handle.get_mut() += 1;
+            }
+            6 => {
+                handle.take_forward(1);
+            }
+            8 => handle.insert_and_process(80),
+            80 => {
+                handle.discard_and_stop_iteration();
+            }
+            _ => {}
+        });
+        assert_eq!(
+            summary,
+            MutationSummary {
+                visited: 8,
+                discarded: 3,
+                inserted: 4,
+                set_or_replaced: 3,
+                stopped: true,
+            }
+        );
+        assert!(summary.changed());
+        assert_eq!(v, vec![10, 3, 30, 31, 40, 51, 6, 8, 9, 41]);
+    }
+
+    #[test]
+    fn test_summarized_no_op() {
+        let mut v = vec![1, 2, 3];
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| {
+            let _ = handle.get_mut();
+        });
+        assert_eq!(
+            summary,
+            MutationSummary {
+                visited: 3,
+                ..MutationSummary::default()
+            }
+        );
+        assert!(!summary.changed());
+        assert!(!summary.stopped);
+
+        let summary = mutate_vec_by_handles_summarized(&mut Vec::<i32>::new(), |_| {});
+        assert_eq!(summary, MutationSummary::default());
+    }
+
+    #[test]
+    fn test_summarized_bulk() {
+        let mut v: Vec<usize> = (0..10).collect();
+        let summary = mutate_vec_by_handles_summarized(&mut v, |mut handle| {
+            if *handle.get() == 0 {
+                handle.retain_rest(|x| x % 3 != 0);
+                handle.split_current_and_skip(|x| vec![x, x]);
+            } else if *handle.get() == 5 {
+                handle.discard_rest();
+            }
+        });
+        assert_eq!(summary.discarded, 3 + 1 + 3);
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.visited, 5);
+        assert!(summary.stopped);
+        assert_eq!(v, vec![0, 0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_until_stable_three_passes() {
+        // Each pass moves every non-zero element one step closer to 0.
+        let mut v: Vec<i32> = vec![2, 1, -1, 2];
+        let passes = mutate_vec_by_handles_until_stable(&mut v, Some(10), |mut handle| {
+            let x = *handle.get();
+            if x != 0 {
+                handle.set(x - x.signum());
+            }
+        });
+        assert_eq!(passes, Ok(3));
+        assert_eq!(v, vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_until_stable_cut_off() {
+        let mut v = vec![1, 2];
+        let passes = mutate_vec_by_handles_until_stable(&mut v, Some(5), |mut handle| {
+            handle.replace_with(|x| x + 1);
+        });
+        assert_eq!(passes, Err(5));
+        assert_eq!(v, vec![6, 7]);
+
+        let passes = mutate_vec_by_handles_until_stable(&mut v, Some(0), |_| {});
+        assert_eq!(passes, Err(0));
+        let passes = mutate_vec_by_handles_until_stable(&mut v, None, |_| {});
+        assert_eq!(passes, Ok(1));
+    }
+
+    #[test]
+    fn test_count_changes() {
+        let mut v = vec![1, 2, 3, 4, 5, 6];
+        let changes =
+            mutate_vec_by_handles_count_changes(&mut v, |mut handle| match *handle.get() {
+                2 => *handle.get_mut() += 20,
+                3 => *handle.get_mut() += 0,
+                4 => handle.set(4),
+                5 => {
+                    handle.discard();
+                }
+                6 => handle.insert_and_skip(6),
+                _ => {}
+            });
+        assert_eq!(v, vec![1, 22, 3, 4, 6, 6]);
+        assert_eq!(changes, 3);
+
+        let changes = mutate_vec_by_handles_count_changes(&mut v, |handle| {
+            let _ = handle.get();
+        });
+        assert_eq!(changes, 0);
+    }
+}
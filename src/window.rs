@@ -0,0 +1,206 @@
+use std::num::NonZeroUsize;
+
+/// Whether [`mutate_vec_by_windows`] gives the closure the shorter windows at the end of the vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingWindows {
+    /// Only give full windows to the closure. Vectors shorter than the window size are not processed at all.
+    Skip,
+    /// Also give the windows starting at each of the last elements, which are shorter than the window size.
+    Shorten,
+}
+
+// Contract:
+// `0 < len`, and `index + len <= vec.len()`
+// `next_index >= index`
+// `vec` may not be mutated at indices smaller than `index`
+/// Represents a window of consecutive elements in a vector, starting at the current index, allowing mutation of the vector with that
+/// window as a "context". Created by [`mutate_vec_by_windows`].
+#[derive(Debug)]
+pub struct WindowHandle<'a, 'b, T> {
+    vec: &'a mut Vec<T>,
+    index: usize, // The index of the first element of the window. Should not be mutated.
+    len: usize,
+    next_index: &'b mut usize, // The index of the first element of the next window.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, T> WindowHandle<'a, 'b, T> {
+    fn new(
+        vec: &'a mut Vec<T>,
+        size: usize,
+        trailing: TrailingWindows,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let index = *next_index;
+        let available = vec
+            .len()
+            .checked_sub(index)
+            .filter(|available| *available > 0)?;
+        if *stopped || (trailing == TrailingWindows::Skip && available < size) {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(WindowHandle {
+            vec,
+            index,
+            len: available.min(size),
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the elements of the window. Never empty.
+    #[must_use]
+    pub fn window(&self) -> &[T] {
+        &self.vec[self.index..self.index + self.len]
+    }
+
+    /// Get a mutable reference to the elements of the window. Never empty.
+    #[must_use]
+    pub fn window_mut(&mut self) -> &mut [T] {
+        &mut self.vec[self.index..self.index + self.len]
+    }
+
+    /// Replace the elements of the window with any number of elements. The next window starts at the first element after
+    /// the replacement, so the replacement is not looked at again.
+    pub fn replace_window(self, replacement: Vec<T>) {
+        *self.next_index = self.index + replacement.len();
+        self.vec
+            .splice(self.index..self.index + self.len, replacement);
+    }
+
+    /// Replace the elements of the window with any number of elements, like `replace_window`, but start the next window at the first
+    /// element of the replacement, so it can be rewritten again.
+    pub fn replace_window_and_reprocess(self, replacement: Vec<T>) {
+        *self.next_index = self.index;
+        self.vec
+            .splice(self.index..self.index + self.len, replacement);
+    }
+
+    /// Skip a certain amount of the windows after this one, i.e. start the next window `steps_to_skip` elements further along.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(steps_to_skip);
+    }
+
+    /// Do not process any more windows.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate a vec by looking at a window of `size` consecutive elements starting at each element in turn, like `slice::windows`,
+/// while allowing the window to be rewritten.
+///
+/// Unless the window is replaced, the next window starts at the element after the first one of this window.
+/// `trailing` decides whether the shorter windows at the end of the vector are given to the closure.
+pub fn mutate_vec_by_windows<T>(
+    vec: &mut Vec<T>,
+    size: NonZeroUsize,
+    trailing: TrailingWindows,
+    mut op: impl FnMut(WindowHandle<T>),
+) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) =
+        WindowHandle::new(vec, size.get(), trailing, &mut next_index, &mut stopped)
+    {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Op {
+        Push(i32),
+        Add,
+        Neg,
+        AddConst(i32),
+        Double,
+    }
+
+    #[test]
+    fn test_windows_peephole_merge_pairs() {
+        let mut ops = vec![
+            Op::Push(1),
+            Op::Push(2),
+            Op::Add,
+            Op::Neg,
+            Op::Neg,
+            Op::Push(3),
+            Op::Add,
+            Op::Neg,
+        ];
+        let mut windows = vec![];
+        mutate_vec_by_windows(&mut ops, TWO, TrailingWindows::Skip, |handle| {
+            windows.push(handle.window().len());
+            match *handle.window() {
+                [Op::Push(x), Op::Add] => handle.replace_window(vec![Op::AddConst(x)]),
+                [Op::Neg, Op::Neg] => handle.replace_window(vec![]),
+                _ => {}
+            }
+        });
+        assert_eq!(
+            ops,
+            vec![Op::Push(1), Op::AddConst(2), Op::AddConst(3), Op::Neg]
+        );
+        assert_eq!(windows, vec![2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_windows_expand_and_trailing() {
+        let mut ops = vec![Op::Push(1), Op::Double, Op::Push(2), Op::Double];
+        let mut windows = vec![];
+        mutate_vec_by_windows(&mut ops, TWO, TrailingWindows::Shorten, |mut handle| {
+            windows.push(handle.window().to_vec());
+            if handle.window()[0] == Op::Double {
+                handle.replace_window(vec![Op::Push(2), Op::Push(0), Op::Add]);
+            } else if handle.window().len() == 1 {
+                handle.window_mut()[0] = Op::Neg;
+            }
+        });
+        assert_eq!(
+            windows,
+            vec![
+                vec![Op::Push(1), Op::Double],
+                vec![Op::Double, Op::Push(2)],
+                vec![Op::Double],
+            ]
+        );
+        assert_eq!(
+            ops,
+            vec![
+                Op::Push(1),
+                Op::Push(2),
+                Op::Push(0),
+                Op::Add,
+                Op::Push(2),
+                Op::Push(0),
+                Op::Add
+            ]
+        );
+    }
+
+    #[test]
+    fn test_windows_reprocess_skip_stop() {
+        let mut v = vec![1, 1, 1, 1, 5, 6, 7, 8, 9];
+        let mut seen = vec![];
+        mutate_vec_by_windows(&mut v, TWO, TrailingWindows::Skip, |mut handle| {
+            seen.push(handle.window()[0]);
+            match *handle.window() {
+                [a, b] if a == b => handle.replace_window_and_reprocess(vec![a + b]),
+                [5, _] => handle.skip_forward(1),
+                [8, _] => handle.stop_iteration(),
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 1, 2, 5, 7, 8]);
+        assert_eq!(v, vec![2, 2, 5, 6, 7, 8, 9]);
+    }
+}
@@ -0,0 +1,155 @@
+// Contract:
+// `index < a.len()` and `index < b.len()`
+// `next_index > index`, unless the current elements are discarded
+// `a` and `b` are only ever changed together, at the same indices
+/// Represents a shared index in two vectors, allowing mutation of both with that index as a "context", while keeping them aligned.
+/// Created by [`mutate_vecs_zipped_by_handles`].
+#[derive(Debug)]
+pub struct ZipHandle<'a, 'b, A, B> {
+    a: &'a mut Vec<A>,
+    b: &'a mut Vec<B>,
+    index: usize,              // The current index. Should not be mutated.
+    next_index: &'b mut usize, // The next index to be processed.
+    stopped: &'b mut bool,
+}
+
+impl<'a, 'b, A, B> ZipHandle<'a, 'b, A, B> {
+    fn new(
+        a: &'a mut Vec<A>,
+        b: &'a mut Vec<B>,
+        next_index: &'b mut usize,
+        stopped: &'b mut bool,
+    ) -> Option<Self> {
+        let index = *next_index;
+        if *stopped || index >= a.len().min(b.len()) {
+            return None;
+        }
+        *next_index = index + 1;
+        Some(ZipHandle {
+            a,
+            b,
+            index,
+            next_index,
+            stopped,
+        })
+    }
+
+    /// Get a reference to the current element of the first vector.
+    #[must_use]
+    pub fn get_a(&self) -> &A {
+        &self.a[self.index]
+    }
+
+    /// Get a reference to the current element of the second vector.
+    #[must_use]
+    pub fn get_b(&self) -> &B {
+        &self.b[self.index]
+    }
+
+    /// Get a mutable reference to the current element of the first vector.
+    #[must_use]
+    pub fn get_mut_a(&mut self) -> &mut A {
+        &mut self.a[self.index]
+    }
+
+    /// Get a mutable reference to the current element of the second vector.
+    #[must_use]
+    pub fn get_mut_b(&mut self) -> &mut B {
+        &mut self.b[self.index]
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    /// Remove the current element from both vectors, and return them as owned.
+    pub fn discard_both(self) -> (A, B) {
+        *self.next_index = self.index;
+        (self.a.remove(self.index), self.b.remove(self.index))
+    }
+
+    /// Insert an element into each vector after the current ones, which will be skipped by the iteration.
+    pub fn insert_and_skip_both(&mut self, a: A, b: B) {
+        self.a.insert(self.index + 1, a);
+        self.b.insert(self.index + 1, b);
+        *self.next_index += 1;
+    }
+
+    /// Skip a certain amount of the elements after the current ones, in both vectors.
+    pub fn skip_forward(&mut self, steps_to_skip: usize) {
+        *self.next_index = self.next_index.saturating_add(steps_to_skip);
+    }
+
+    /// Do not process any more elements.
+    pub fn stop_iteration(self) {
+        *self.stopped = true;
+    }
+}
+
+/// Mutate two vecs in lockstep using index-style looping, where the handle has access to the elements at the same index in both.
+///
+/// Discards and inserts always happen in both vectors, so they stay aligned. If the vectors have different lengths, only the
+/// common prefix is iterated over, and the elements past it in the longer vector are left as they are.
+pub fn mutate_vecs_zipped_by_handles<A, B>(
+    a: &mut Vec<A>,
+    b: &mut Vec<B>,
+    mut op: impl FnMut(ZipHandle<A, B>),
+) {
+    let mut next_index = 0;
+    let mut stopped = false;
+
+    while let Some(handle) = ZipHandle::new(a, b, &mut next_index, &mut stopped) {
+        op(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zipped_discard_keeps_aligned() {
+        let mut keys = vec![1, 2, 3, 4, 5, 6];
+        let mut values = vec!["a", "b", "c", "d", "e", "f"];
+        let mut discarded = vec![];
+        mutate_vecs_zipped_by_handles(&mut keys, &mut values, |handle| {
+            if *handle.get_a() % 2 == 0 {
+                discarded.push(handle.discard_both());
+            }
+        });
+        assert_eq!(keys, vec![1, 3, 5]);
+        assert_eq!(values, vec!["a", "c", "e"]);
+        assert_eq!(discarded, vec![(2, "b"), (4, "d"), (6, "f")]);
+    }
+
+    #[test]
+    fn test_zipped_discard_final_common_index() {
+        let mut keys = vec![1, 2, 3];
+        let mut values = vec!["a", "b", "c", "d", "e"];
+        mutate_vecs_zipped_by_handles(&mut keys, &mut values, |handle| {
+            if *handle.get_a() == 3 {
+                handle.discard_both();
+            }
+        });
+        assert_eq!(keys, vec![1, 2]);
+        assert_eq!(values, vec!["a", "b", "d", "e"]);
+    }
+
+    #[test]
+    fn test_zipped_insert_skip_stop() {
+        let mut keys = vec![1, 2, 3, 4, 5];
+        let mut values = vec![10, 20, 30, 40, 50, 60];
+        let mut seen = vec![];
+        mutate_vecs_zipped_by_handles(&mut keys, &mut values, |mut handle| {
+            seen.push(*handle.get_a());
+            *handle.get_mut_b() += 1;
+            match *handle.get_a() {
+                1 => handle.insert_and_skip_both(-1, -10),
+                2 => handle.skip_forward(1),
+                4 => handle.stop_iteration(),
+                _ => {}
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 4]);
+        assert_eq!(keys, vec![1, -1, 2, 3, 4, 5]);
+        assert_eq!(values, vec![11, -10, 21, 30, 41, 50, 60]);
+        assert_eq!(keys.len() + 1, values.len());
+    }
+}